@@ -0,0 +1,101 @@
+//! Measures how long post-processing the generated code takes, and how much memory it takes, for a
+//! schema generating a single multi-megabyte Rust file. Runs the `flatc` found through the `PATH`,
+//! or the one set in `FLATC`. The number of tables in the schema can be set through
+//! `FLATBUFFERS_BUILD_BENCH_TABLES`.
+//!
+//! ```text
+//! cargo bench --bench post_processing
+//! ```
+
+use flatbuffers_build::BuilderOptions;
+use std::{fmt::Write, path::Path};
+
+const DEFAULT_TABLES: usize = 5000;
+const STAMP_FILE_NAME: &str = ".flatbuffers-build-stamp.json";
+
+fn main() {
+    let tables = std::env::var("FLATBUFFERS_BUILD_BENCH_TABLES")
+        .ok()
+        .and_then(|tables| tables.parse().ok())
+        .unwrap_or(DEFAULT_TABLES);
+    let flatc = std::env::var("FLATC").unwrap_or_else(|_| "flatc".into());
+    let dir = tempfile::tempdir().expect("failed to create a temporary directory");
+    let schema_path = dir.path().join("large.fbs");
+    std::fs::write(&schema_path, large_schema(tables)).expect("failed to write the schema");
+    let output_path = dir.path().join("out");
+    let report_path = dir.path().join("report.json");
+    let builder = BuilderOptions::new_with_files([&schema_path])
+        .set_compiler(&flatc)
+        .set_output_path(&output_path)
+        .set_report_path(&report_path)
+        .gen_onefile()
+        .supress_buildrs_directives();
+
+    // Writes every generated file
+    let counters = builder
+        .clone()
+        .compile_with_counters()
+        .expect("flatbuffer compilation failed");
+    println!(
+        "{tables} tables generated {} bytes in {} files",
+        counters.bytes_written, counters.generated_files
+    );
+    print_phase("writing new files", &report_path);
+
+    // Compares every generated file against an identical one, without writing any
+    std::fs::remove_file(output_path.join(STAMP_FILE_NAME)).expect("failed to remove the stamp");
+    builder
+        .clone()
+        .compile()
+        .expect("flatbuffer compilation failed");
+    print_phase("comparing unchanged files", &report_path);
+
+    builder
+        .check_generated_code()
+        .compile()
+        .expect("generated code out of date");
+    print_phase("checking generated code", &report_path);
+
+    if let Some(peak) = peak_memory() {
+        println!("peak memory: {peak}");
+    }
+}
+
+/// A schema with `tables` tables of a few fields each.
+fn large_schema(tables: usize) -> String {
+    let mut schema = String::from("namespace bench.large;\n\n");
+    for idx in 0..tables {
+        writeln!(
+            schema,
+            "table Table{idx} {{\n  id: uint64;\n  name: string;\n  values: [float];\n  \
+             flags: [ubyte];\n  next: Table{next};\n}}\n",
+            next = (idx + 1) % tables
+        )
+        .unwrap();
+    }
+    writeln!(schema, "root_type Table0;").unwrap();
+    schema
+}
+
+/// Prints how long the post-processing phase of the compilation recorded in `report_path` took.
+fn print_phase(name: &str, report_path: &Path) {
+    let report: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(report_path).expect("failed to read the compilation report"),
+    )
+    .expect("invalid compilation report");
+    println!(
+        "{name}: post-processing took {:.3}s",
+        report["phase_secs"]["post_processing"]
+            .as_f64()
+            .unwrap_or_default()
+    );
+}
+
+/// The most memory this process had resident at once, where the OS tells.
+fn peak_memory() -> Option<String> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let peak = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?;
+    Some(peak.trim().to_owned())
+}
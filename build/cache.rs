@@ -0,0 +1,56 @@
+//! Per-user cache of vendored flatc binaries, so that every crate and workspace on a machine
+//! doesn't have to download or build its own copy.
+
+use crate::flatc_binary_name;
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+};
+
+/// Environment variable overriding where vendored binaries get cached. Setting it to an empty
+/// string disables the cache.
+const CACHE_DIR_ENV_VAR: &str = "FLATBUFFERS_BUILD_CACHE_DIR";
+const LOCK_FILE_NAME: &str = ".lock";
+
+/// Returns the cached flatc for the host stored under `key` if there is one. Otherwise runs
+/// `vendor` to get one and stores it in the cache for next time. The key identifies the flavour of
+/// flatc being vendored, which is mostly just its version.
+pub fn cached_flatc<F: FnOnce() -> anyhow::Result<PathBuf>>(
+    key: &str,
+    vendor: F,
+) -> anyhow::Result<PathBuf> {
+    println!("cargo::rerun-if-env-changed={CACHE_DIR_ENV_VAR}");
+    let Some(cache_dir) = cache_dir(key)? else {
+        return vendor();
+    };
+    let cached_path = cache_dir.join(flatc_binary_name()?);
+    fs::create_dir_all(&cache_dir)?;
+    // Builds of other crates might be vendoring the same flatc at the same time. Whoever gets the
+    // lock first vendors it, and everyone else then finds it in the cache.
+    let lock = File::create(cache_dir.join(LOCK_FILE_NAME))?;
+    lock.lock()?;
+    if !cached_path.is_file() {
+        let flatc_path = vendor()?;
+        // Copy under a temporary name first so that other builds never pick up a partially
+        // written binary.
+        let staged = tempfile::NamedTempFile::new_in(&cache_dir)?;
+        fs::copy(&flatc_path, staged.path())?;
+        staged.persist(&cached_path)?;
+    }
+    drop(lock);
+    println!("cargo::rerun-if-changed={}", cached_path.display());
+    Ok(cached_path)
+}
+
+/// Directory the flatc stored under `key` for the host gets cached in, if caching is enabled.
+fn cache_dir(key: &str) -> anyhow::Result<Option<PathBuf>> {
+    let root = match std::env::var_os(CACHE_DIR_ENV_VAR) {
+        Some(dir) if dir.is_empty() => return Ok(None),
+        Some(dir) => PathBuf::from(dir),
+        None => match dirs::cache_dir() {
+            Some(dir) => dir.join("flatbuffers-build"),
+            None => return Ok(None),
+        },
+    };
+    Ok(Some(root.join(key).join(std::env::var("HOST")?)))
+}
@@ -0,0 +1,81 @@
+//! Helpers shared by every way of vendoring flatc that involves fetching something off the
+//! network.
+
+use ring::digest::{Context, SHA256};
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+/// Environment variable with a proxy to send every download through. When it's not set, the usual
+/// `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` variables are respected instead.
+const PROXY_ENV_VAR: &str = "FLATC_DOWNLOAD_PROXY";
+/// Environment variable forbidding any download, for air-gapped builds and `cargo vendor` setups.
+const OFFLINE_ENV_VAR: &str = "FLATBUFFERS_BUILD_OFFLINE";
+/// cargo's own offline switch, when set through the environment rather than `--offline`.
+const CARGO_OFFLINE_ENV_VAR: &str = "CARGO_NET_OFFLINE";
+
+pub fn download_file<P: AsRef<Path>>(
+    url: &str,
+    dir: P,
+    file_name: &str,
+) -> anyhow::Result<PathBuf> {
+    if let Some(var) = offline_env_var() {
+        anyhow::bail!(
+            "refusing to download {url} since {var} is set. Prefill the flatc cache, or use the \
+             `vendored` feature with FLATC_SOURCE_TARBALL or FLATC_SOURCE_DIR"
+        );
+    }
+    let path = dir.as_ref().join(file_name);
+    let mut file = File::create(&path)?;
+    let mut response = http_client()?.get(url).send()?.error_for_status()?;
+    response.copy_to(&mut file)?;
+    Ok(path)
+}
+
+/// The environment variable putting us in offline mode, if any is set.
+fn offline_env_var() -> Option<&'static str> {
+    let vars = [OFFLINE_ENV_VAR, CARGO_OFFLINE_ENV_VAR];
+    for var in vars {
+        println!("cargo::rerun-if-env-changed={var}");
+    }
+    vars.into_iter()
+        .find(|var| std::env::var(var).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true")))
+}
+
+fn http_client() -> anyhow::Result<reqwest::blocking::Client> {
+    println!("cargo::rerun-if-env-changed={PROXY_ENV_VAR}");
+    let mut builder = reqwest::blocking::Client::builder();
+    if let Ok(proxy) = std::env::var(PROXY_ENV_VAR) {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?.no_proxy(reqwest::NoProxy::from_env()));
+    }
+    Ok(builder.build()?)
+}
+
+pub fn sha256_file<P: AsRef<Path>>(file_path: P) -> anyhow::Result<String> {
+    let mut digester = Context::new(&SHA256);
+    let mut file = File::open(file_path)?;
+    let mut reader = BufReader::new(&mut file);
+    let mut buffer = [0u8; 4096];
+    loop {
+        let byte_count = reader.read(&mut buffer)?;
+        if byte_count == 0 {
+            break;
+        }
+        digester.update(&buffer[..byte_count]);
+    }
+    let digest = digester.finish();
+    Ok(hex::encode(digest.as_ref()))
+}
+
+pub fn checksum_check<P: AsRef<Path>>(file_path: P, expected_checksum: &str) -> anyhow::Result<()> {
+    let digest_str = sha256_file(file_path)?;
+    if digest_str.eq_ignore_ascii_case(expected_checksum) {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "checskum for file did not match; expected {expected_checksum}, got {digest_str}"
+        ))
+    }
+}
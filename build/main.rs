@@ -0,0 +1,123 @@
+#![warn(clippy::all, clippy::pedantic)]
+
+#[cfg(any(feature = "vendored", feature = "vendored-prebuilt"))]
+mod cache;
+#[cfg(any(feature = "vendored", feature = "vendored-prebuilt"))]
+mod download;
+#[cfg(feature = "vendored-prebuilt")]
+mod prebuilt;
+#[cfg(any(feature = "vendored", feature = "vendored-prebuilt"))]
+mod selftest;
+#[cfg(all(feature = "vendored", not(feature = "vendored-prebuilt")))]
+mod source;
+#[cfg(all(
+    feature = "vendored-fallback",
+    any(feature = "vendored", feature = "vendored-prebuilt")
+))]
+mod system;
+
+#[cfg(any(feature = "vendored", feature = "vendored-prebuilt"))]
+const SUPPORTED_FLATC_VERSION: &str = "24.3.25";
+/// Environment variable selecting which flatbuffers release to vendor, instead of
+/// [`SUPPORTED_FLATC_VERSION`].
+#[cfg(any(feature = "vendored", feature = "vendored-prebuilt"))]
+const VERSION_ENV_VAR: &str = "FLATC_VENDORED_VERSION";
+
+fn main() {
+    #[cfg(any(feature = "vendored", feature = "vendored-prebuilt"))]
+    {
+        println!("cargo::rerun-if-env-changed={VERSION_ENV_VAR}");
+        // docs.rs builds have no network access, and compilation gets skipped there anyway
+        println!("cargo::rerun-if-env-changed=DOCS_RS");
+        if std::env::var_os("DOCS_RS").is_some() {
+            return;
+        }
+        let version =
+            std::env::var(VERSION_ENV_VAR).unwrap_or_else(|_| SUPPORTED_FLATC_VERSION.into());
+        let flatc_path = if let Some(system_flatc) = system_flatc(&version) {
+            Ok(system_flatc)
+        } else if cacheable() {
+            cache::cached_flatc(&cache_key(&version), || vendor_flatc(&version))
+        } else {
+            vendor_flatc(&version)
+        }
+        .expect("failed to vendor flatc");
+        if let Err(e) = selftest::check(&flatc_path, &version) {
+            panic!("{e}");
+        }
+        println!("cargo::rustc-env=FLATC_PATH={}", flatc_path.display());
+        println!("cargo::rustc-env=FLATC_VENDORED_VERSION={version}");
+        // Also expose the binary to the build scripts of crates depending on us, as
+        // `DEP_FLATBUFFERS_BUILD_FLATC_PATH` and `DEP_FLATBUFFERS_BUILD_FLATC_VERSION`.
+        println!("cargo::metadata=flatc_path={}", flatc_path.display());
+        println!("cargo::metadata=flatc_version={version}");
+    }
+}
+
+/// Downloading a prebuilt binary is a lot faster than building one from source, so when both
+/// features are enabled we prefer the former.
+#[cfg(feature = "vendored-prebuilt")]
+fn vendor_flatc(version: &str) -> anyhow::Result<std::path::PathBuf> {
+    prebuilt::vendor_flatc(version)
+}
+
+#[cfg(all(feature = "vendored", not(feature = "vendored-prebuilt")))]
+fn vendor_flatc(version: &str) -> anyhow::Result<std::path::PathBuf> {
+    source::vendor_flatc(version)
+}
+
+/// With `vendored-fallback`, a system `flatc` of the right version takes precedence over vendoring
+/// one.
+#[cfg(all(
+    feature = "vendored-fallback",
+    any(feature = "vendored", feature = "vendored-prebuilt")
+))]
+fn system_flatc(version: &str) -> Option<std::path::PathBuf> {
+    system::system_flatc(version)
+}
+
+#[cfg(all(
+    not(feature = "vendored-fallback"),
+    any(feature = "vendored", feature = "vendored-prebuilt")
+))]
+fn system_flatc(_version: &str) -> Option<std::path::PathBuf> {
+    None
+}
+
+#[cfg(feature = "vendored-prebuilt")]
+fn cache_key(version: &str) -> String {
+    version.into()
+}
+
+/// Builds of a git commit are cached under the commit rather than the version. Static builds get
+/// cached separately, since they can be used in places dynamically linked ones can't.
+#[cfg(all(feature = "vendored", not(feature = "vendored-prebuilt")))]
+fn cache_key(version: &str) -> String {
+    let mut key = source::source_rev().map_or_else(|| version.into(), |rev| format!("git-{rev}"));
+    if cfg!(feature = "vendored-static") {
+        key.push_str("-static");
+    }
+    key
+}
+
+#[cfg(feature = "vendored-prebuilt")]
+fn cacheable() -> bool {
+    true
+}
+
+/// A local source tree might carry any number of patches, so what gets built from it is never
+/// cached.
+#[cfg(all(feature = "vendored", not(feature = "vendored-prebuilt")))]
+fn cacheable() -> bool {
+    source::local_source_dir().is_none()
+}
+
+/// Name of the flatc executable on the host.
+#[cfg(any(feature = "vendored", feature = "vendored-prebuilt"))]
+fn flatc_binary_name() -> anyhow::Result<&'static str> {
+    Ok(if std::env::var("HOST")?.contains("windows") {
+        "flatc.exe"
+    } else {
+        "flatc"
+    })
+}
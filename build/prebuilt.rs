@@ -0,0 +1,72 @@
+//! Vendoring by downloading the `flatc` binary attached to the official flatbuffers release for
+//! the host platform. This skips the cmake build entirely, which takes minutes and needs a full
+//! C++ toolchain.
+
+use crate::{download, flatc_binary_name};
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+};
+
+const RELEASE_URL: &str =
+    "https://github.com/google/flatbuffers/releases/download/v{version}/{asset}";
+/// Environment variable with the expected SHA-256 of the release asset. Upstream doesn't publish
+/// checksums for its release binaries, so we can only verify the download if one is provided.
+const CHECKSUM_ENV_VAR: &str = "FLATC_PREBUILT_SHA256";
+/// Environment variable overriding [`RELEASE_URL`], e.g. to download from an internal mirror.
+const RELEASE_URL_ENV_VAR: &str = "FLATC_PREBUILT_URL";
+
+pub fn vendor_flatc(version: &str) -> anyhow::Result<PathBuf> {
+    println!("cargo::rerun-if-env-changed={CHECKSUM_ENV_VAR}");
+    println!("cargo::rerun-if-env-changed={RELEASE_URL_ENV_VAR}");
+    let host = std::env::var("HOST")?;
+    let asset = release_asset(version, &host)?;
+    let tmpdir = tempfile::tempdir()?;
+    let url = std::env::var(RELEASE_URL_ENV_VAR)
+        .unwrap_or_else(|_| RELEASE_URL.into())
+        .replace("{version}", version)
+        .replace("{asset}", asset);
+    let zip_path = download::download_file(&url, &tmpdir, asset)?;
+
+    if let Ok(expected_checksum) = std::env::var(CHECKSUM_ENV_VAR) {
+        download::checksum_check(&zip_path, &expected_checksum)?;
+    } else {
+        println!(
+            "cargo::warning=prebuilt flatc was downloaded without verifying its checksum; set \
+             {CHECKSUM_ENV_VAR} to verify it"
+        );
+    }
+
+    let binary_name = flatc_binary_name()?;
+    let dest_dir = PathBuf::from(std::env::var("OUT_DIR")?).join("flatc-prebuilt");
+    fs::create_dir_all(&dest_dir)?;
+    let flatc_path = dest_dir.join(binary_name);
+
+    let mut archive = zip::ZipArchive::new(File::open(&zip_path)?)?;
+    let mut binary = archive.by_name(binary_name)?;
+    let mut dest = File::create(&flatc_path)?;
+    std::io::copy(&mut binary, &mut dest)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&flatc_path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(flatc_path)
+}
+
+/// Picks the release asset of flatc `version` to download for the `host` target triple.
+fn release_asset(version: &str, host: &str) -> anyhow::Result<&'static str> {
+    match host {
+        "x86_64-unknown-linux-gnu" | "x86_64-unknown-linux-musl" => {
+            Ok("Linux.flatc.binary.g++-13.zip")
+        }
+        "aarch64-apple-darwin" => Ok("Mac.flatc.binary.zip"),
+        "x86_64-apple-darwin" => Ok("MacIntel.flatc.binary.zip"),
+        "x86_64-pc-windows-msvc" | "x86_64-pc-windows-gnu" => Ok("Windows.flatc.binary.zip"),
+        _ => Err(anyhow::anyhow!(
+            "no prebuilt flatc {version} release is available for {host}; use \
+             the `vendored` feature to build it from source instead"
+        )),
+    }
+}
@@ -0,0 +1,85 @@
+//! Sanity check of a freshly vendored flatc. A binary built for the wrong architecture or missing
+//! shared libraries would otherwise only surface much later, as confusing errors when compiling
+//! schemas.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const SCHEMA: &str = "table SelfTest { value: int; }\nroot_type SelfTest;\n";
+const EXPECTED_OUTPUT: &str = "pub struct SelfTest<'a>";
+
+/// Returned when the vendored flatc doesn't work.
+#[derive(Debug)]
+pub struct SelfTestError {
+    flatc: PathBuf,
+    reason: String,
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "vendored flatc at {} is broken: {}",
+            self.flatc.display(),
+            self.reason
+        )
+    }
+}
+
+impl std::error::Error for SelfTestError {}
+
+/// Makes sure `flatc` runs, reports being `version`, and can generate Rust code for a tiny schema.
+pub fn check(flatc: &Path, version: &str) -> Result<(), SelfTestError> {
+    let fail = |reason: String| SelfTestError {
+        flatc: flatc.into(),
+        reason,
+    };
+
+    let stdout = run(flatc, &["--version".as_ref()]).map_err(fail)?;
+    if stdout.trim_end() != format!("flatc version {version}") {
+        return Err(fail(format!(
+            "expected it to report version {version}, got '{}'",
+            stdout.trim_end()
+        )));
+    }
+
+    let dir = tempfile::tempdir().map_err(|e| fail(e.to_string()))?;
+    let schema_path = dir.path().join("self_test.fbs");
+    std::fs::write(&schema_path, SCHEMA).map_err(|e| fail(e.to_string()))?;
+    run(
+        flatc,
+        &[
+            "--rust".as_ref(),
+            "-o".as_ref(),
+            dir.path().as_os_str(),
+            schema_path.as_os_str(),
+        ],
+    )
+    .map_err(fail)?;
+    let generated = std::fs::read_to_string(dir.path().join("self_test_generated.rs"))
+        .map_err(|e| fail(format!("failed to read generated code: {e}")))?;
+    if generated.contains(EXPECTED_OUTPUT) {
+        Ok(())
+    } else {
+        Err(fail("generated code is missing the expected table".into()))
+    }
+}
+
+fn run(flatc: &Path, args: &[&std::ffi::OsStr]) -> Result<String, String> {
+    let output = Command::new(flatc)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run it: {e}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "it exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
@@ -0,0 +1,241 @@
+//! Vendoring by downloading the flatbuffers source release and building flatc with cmake.
+
+use crate::{download, flatc_binary_name};
+use flate2::read::GzDecoder;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tar::Archive;
+
+const SOURCE_URL: &str =
+    "https://github.com/google/flatbuffers/archive/refs/tags/v{version}.tar.gz";
+const SOURCE_REV_URL: &str = "https://github.com/google/flatbuffers/archive/{rev}.tar.gz";
+/// Releases we know how to vendor, along with the SHA-256 of their source tarball.
+const KNOWN_RELEASES: &[(&str, &str)] = &[(
+    "24.3.25",
+    "4157c5cacdb59737c5d627e47ac26b140e9ee28b1102f812b36068aab728c1ed",
+)];
+/// Environment variable with the SHA-256 the source tarball is expected to have, for versions
+/// missing from [`KNOWN_RELEASES`] or tarballs coming from a custom source.
+const CHECKSUM_ENV_VAR: &str = "FLATC_SOURCE_SHA256";
+/// Environment variable pointing to an already downloaded source tarball, for building without
+/// network access.
+const TARBALL_ENV_VAR: &str = "FLATC_SOURCE_TARBALL";
+/// Environment variable pointing to an already extracted source tree, such as a git submodule, to
+/// build instead of downloading one. This skips the checksum check entirely.
+const SOURCE_DIR_ENV_VAR: &str = "FLATC_SOURCE_DIR";
+/// Environment variable with a git commit to build flatc from, rather than a tagged release.
+const SOURCE_REV_ENV_VAR: &str = "FLATC_SOURCE_REV";
+/// Environment variable overriding [`SOURCE_URL`] or [`SOURCE_REV_URL`], e.g. to download from an
+/// internal mirror or a fork.
+const SOURCE_URL_ENV_VAR: &str = "FLATC_SOURCE_URL";
+/// Environment variables that configure the C++ toolchain for whatever is being built, which are
+/// ignored when cross compiling.
+const CROSS_TOOLCHAIN_ENV_VARS: [&str; 7] = [
+    "CC",
+    "CXX",
+    "CFLAGS",
+    "CXXFLAGS",
+    "AR",
+    "CMAKE_TOOLCHAIN_FILE",
+    "CMAKE_GENERATOR_PLATFORM",
+];
+
+pub fn vendor_flatc(version: &str) -> anyhow::Result<PathBuf> {
+    if let Some(source_dir) = local_source_dir() {
+        println!("cargo::rerun-if-changed={}", source_dir.display());
+        let dest = compile_flatc(source_dir)?;
+        return find_flatc(&dest);
+    }
+
+    let rev = source_rev();
+    // Commits aren't in our list of known releases, so we can only verify those if the user tells
+    // us what to expect.
+    let checksum = if rev.is_some() {
+        println!("cargo::rerun-if-env-changed={CHECKSUM_ENV_VAR}");
+        std::env::var(CHECKSUM_ENV_VAR).ok()
+    } else {
+        Some(expected_checksum(version)?)
+    };
+    let tmpdir = tempfile::tempdir()?;
+
+    println!("cargo::rerun-if-env-changed={TARBALL_ENV_VAR}");
+    let tarball_path = if let Some(tarball_path) = std::env::var_os(TARBALL_ENV_VAR) {
+        let tarball_path = PathBuf::from(tarball_path);
+        println!("cargo::rerun-if-changed={}", tarball_path.display());
+        tarball_path
+    } else {
+        let url = get_full_source_url(version, rev.as_deref());
+        download::download_file(&url, &tmpdir, "flatbuffers.tar.gz")?
+    };
+    if let Some(checksum) = checksum {
+        download::checksum_check(&tarball_path, &checksum)?;
+    } else {
+        println!(
+            "cargo::warning=flatbuffers source tarball was not verified against a checksum; set \
+             {CHECKSUM_ENV_VAR} to verify it"
+        );
+    }
+
+    // Extract the source tarball
+    let extract_path = tmpdir.path().join("flatbuffers");
+    unpack_tarball(tarball_path, &extract_path)?;
+
+    let source_dir = single_subdirectory(&extract_path)?;
+    let dest = compile_flatc(source_dir)?;
+    find_flatc(&dest)
+}
+
+/// Already extracted source tree to build from, if the user provided one.
+pub fn local_source_dir() -> Option<PathBuf> {
+    println!("cargo::rerun-if-env-changed={SOURCE_DIR_ENV_VAR}");
+    std::env::var_os(SOURCE_DIR_ENV_VAR).map(PathBuf::from)
+}
+
+/// Git commit to build from instead of a release, if the user asked for one.
+pub fn source_rev() -> Option<String> {
+    println!("cargo::rerun-if-env-changed={SOURCE_REV_ENV_VAR}");
+    std::env::var(SOURCE_REV_ENV_VAR).ok()
+}
+
+/// Source tarballs hold everything under a single top level directory, whose name depends on
+/// where it was downloaded from and which release or commit it is.
+fn single_subdirectory(path: &Path) -> anyhow::Result<PathBuf> {
+    let mut entries = std::fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+    match (entries.pop(), entries.is_empty()) {
+        (Some(entry), true) if entry.path().is_dir() => Ok(entry.path()),
+        _ => Err(anyhow::anyhow!(
+            "expected the flatbuffers source tarball to contain a single directory"
+        )),
+    }
+}
+
+/// Finds the compiled binary under the cmake output directory. It usually gets installed into
+/// `bin`, but multi-config generators like the MSVC ones nest it under a directory named after
+/// the configuration instead.
+fn find_flatc(dest: &Path) -> anyhow::Result<PathBuf> {
+    let binary_name = flatc_binary_name()?;
+    let candidates = [
+        dest.join("bin").join(binary_name),
+        dest.join("bin").join("Release").join(binary_name),
+        dest.join("build").join("Release").join(binary_name),
+    ];
+    candidates
+        .iter()
+        .find(|candidate| candidate.is_file())
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("could not find {binary_name} under {}", dest.display()))
+}
+
+fn unpack_tarball<P: AsRef<Path>, Q: AsRef<Path>>(
+    tarball_path: P,
+    extraction_path: Q,
+) -> anyhow::Result<()> {
+    let tar_gz = File::open(tarball_path)?;
+    let tar = GzDecoder::new(tar_gz);
+    let mut archive = Archive::new(tar);
+    archive.unpack(extraction_path)?;
+    Ok(())
+}
+
+fn compile_flatc<P: AsRef<Path>>(source_dir: P) -> anyhow::Result<PathBuf> {
+    let host = std::env::var("HOST")?;
+    let mut config = cmake::Config::new(source_dir);
+    config
+        .profile("Release")
+        .define("FLATBUFFERS_BUILD_TESTS", "OFF")
+        .define("FLATBUFFERS_BUILD_FLATHASH", "OFF");
+    // With Ninja, the cmake crate builds with as many jobs as cargo's `NUM_JOBS` allows. The MSVC
+    // toolchain needs a developer prompt to work with it though, so we leave the Visual Studio
+    // generators alone there.
+    if std::env::var_os("CMAKE_GENERATOR").is_none()
+        && !host.contains("msvc")
+        && tool_available("ninja")
+    {
+        config.generator("Ninja");
+    }
+    if cfg!(feature = "vendored-static") {
+        link_statically(&mut config, &host);
+    }
+    if std::env::var("TARGET")? != host {
+        // flatc runs as part of the build, so it has to be built for the host rather than the
+        // target being cross compiled to. The unprefixed toolchain variables describe the cross
+        // toolchain, so only their `HOST_` prefixed variants are allowed to take effect.
+        config.target(&host).host(&host);
+        for var in CROSS_TOOLCHAIN_ENV_VARS {
+            std::env::remove_var(var);
+        }
+    }
+    Ok(config.build())
+}
+
+/// Makes the build produce a fully static binary that can be copied between machines regardless
+/// of their libc. This is only possible on Linux, where we prefer building against musl if its
+/// toolchain is installed.
+fn link_statically(config: &mut cmake::Config, host: &str) {
+    if !host.contains("linux") {
+        println!(
+            "cargo::warning=static flatc builds are only supported on Linux, building a \
+             dynamically linked one for {host}"
+        );
+        return;
+    }
+    let arch = host.split('-').next().unwrap_or_default();
+    let musl_cxx = format!("{arch}-linux-musl-g++");
+    if std::env::var_os("HOST_CXX").is_none() && tool_available(&musl_cxx) {
+        config
+            .define("CMAKE_C_COMPILER", format!("{arch}-linux-musl-gcc"))
+            .define("CMAKE_CXX_COMPILER", musl_cxx);
+    }
+    config.define("CMAKE_EXE_LINKER_FLAGS", "-static");
+}
+
+fn tool_available(tool: &str) -> bool {
+    Command::new(tool)
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Works out the SHA-256 the source tarball of `version` should have, taking
+/// [`CHECKSUM_ENV_VAR`] into account.
+fn expected_checksum(version: &str) -> anyhow::Result<String> {
+    println!("cargo::rerun-if-env-changed={CHECKSUM_ENV_VAR}");
+    let known_checksum = KNOWN_RELEASES
+        .iter()
+        .find(|(known_version, _)| *known_version == version)
+        .map(|(_, checksum)| *checksum);
+    match (known_checksum, std::env::var(CHECKSUM_ENV_VAR).ok()) {
+        (Some(known), Some(provided)) if !known.eq_ignore_ascii_case(&provided) => {
+            Err(anyhow::anyhow!(
+                "{CHECKSUM_ENV_VAR} is set to {provided}, but the source tarball for flatc \
+                 {version} is known to have a checksum of {known}"
+            ))
+        }
+        (_, Some(provided)) => Ok(provided),
+        (Some(known), None) => Ok(known.into()),
+        (None, None) => {
+            let known_versions: Vec<_> = KNOWN_RELEASES.iter().map(|(v, _)| *v).collect();
+            Err(anyhow::anyhow!(
+                "don't know the checksum of the source tarball for flatc {version}; set \
+                 {CHECKSUM_ENV_VAR} to provide one (known versions are {})",
+                known_versions.join(", ")
+            ))
+        }
+    }
+}
+
+fn get_full_source_url(version: &str, rev: Option<&str>) -> String {
+    println!("cargo::rerun-if-env-changed={SOURCE_URL_ENV_VAR}");
+    let default_url = if rev.is_some() {
+        SOURCE_REV_URL
+    } else {
+        SOURCE_URL
+    };
+    std::env::var(SOURCE_URL_ENV_VAR)
+        .unwrap_or_else(|_| default_url.into())
+        .replace("{version}", version)
+        .replace("{rev}", rev.unwrap_or_default())
+}
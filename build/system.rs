@@ -0,0 +1,48 @@
+//! Lookup of a `flatc` already installed on the system, which lets us skip vendoring entirely when
+//! it's the version we'd vendor anyway.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const FLATC_VERSION_PREFIX: &str = "flatc version ";
+
+/// Returns the absolute path of the system `flatc` if it reports being `version`. Like the
+/// library itself, this looks at `FLATC_PATH` first and falls back to whatever `flatc` is on the
+/// `PATH`.
+pub fn system_flatc(version: &str) -> Option<PathBuf> {
+    println!("cargo::rerun-if-env-changed=FLATC_PATH");
+    println!("cargo::rerun-if-env-changed=PATH");
+    let flatc = resolve(&std::env::var("FLATC_PATH").unwrap_or_else(|_| "flatc".into()))?;
+    let output = Command::new(&flatc).arg("--version").output().ok()?;
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let system_version = stdout.strip_prefix(FLATC_VERSION_PREFIX)?.trim_end();
+    if output.status.success() && system_version == version {
+        Some(flatc)
+    } else {
+        println!(
+            "cargo::warning=system flatc reports version {system_version} rather than {version}, \
+             vendoring one instead"
+        );
+        None
+    }
+}
+
+/// The absolute path of `program`, looked up on the `PATH` when it's a bare name, the same way
+/// running it would. The path gets exported to the crate and its dependents, whose build scripts
+/// run with a different `PATH` and current directory, so it has to stand on its own.
+fn resolve(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return std::path::absolute(path).ok();
+    }
+    let names = [
+        PathBuf::from(program),
+        PathBuf::from(format!("{program}{}", std::env::consts::EXE_SUFFIX)),
+    ];
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+        .and_then(|candidate| std::path::absolute(candidate).ok())
+}
@@ -0,0 +1,170 @@
+//! `cargo flatbuffers check`, which compares the generated code committed to a repository against
+//! what the configured schemas generate, and fails with a diff of every file that drifted.
+
+use crate::{config::ConfigArgs, Result};
+use similar::TextDiff;
+use std::path::{Path, PathBuf};
+
+/// Written next to the generated code by every compilation, so not part of the generated code.
+const STAMP_FILE_NAME: &str = ".flatbuffers-build-stamp.json";
+
+/// What a file missing on one side of a diff gets shown as.
+const MISSING_FILE_NAME: &str = "/dev/null";
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+    /// Generated code to check, in place of the `output-path` of the configuration, or of its
+    /// `symlink-directory` when there's no output path.
+    #[arg(long)]
+    output_path: Option<PathBuf>,
+}
+
+/// A file to compare, on the side of the checked code and on the side of the fresh one.
+struct Comparison {
+    checked: PathBuf,
+    generated: PathBuf,
+}
+
+/// Generates the code configured in `args` into a temporary directory, and compares it against
+/// the checked code.
+pub fn run(args: &Args, verbose: bool) -> Result {
+    let mut builder = args.config.builder()?;
+    if verbose {
+        builder = builder.verbose();
+    }
+    let generated_dir = tempfile::tempdir()?;
+    let fresh = builder
+        .clone()
+        .set_output_path(generated_dir.path().join("generated"));
+    let plan = fresh.plan()?;
+    let checked_path = match &args.output_path {
+        Some(output_path) => output_path.clone(),
+        None => match (builder.plan(), plan.symlink_path) {
+            (Ok(configured), _) => configured.output_path,
+            (Err(_), Some(symlink_path)) => symlink_path,
+            (Err(e), None) => return Err(e.into()),
+        },
+    };
+    // Leaves the configured symlink pointing at the checked code
+    fresh
+        .set_symlink_directory(generated_dir.path().join("symlink"))
+        .compile()?;
+
+    let mut differing = 0;
+    for comparison in comparisons(&checked_path, &plan.output_path)? {
+        if print_diff(&comparison)? {
+            differing += 1;
+        }
+    }
+    if differing > 0 {
+        return Err(format!(
+            "generated code in {} is out of date, {differing} {} Run `cargo flatbuffers compile` \
+             to regenerate it",
+            checked_path.display(),
+            if differing == 1 {
+                "file differs."
+            } else {
+                "files differ."
+            }
+        )
+        .into());
+    }
+    eprintln!("generated code in {} is up to date", checked_path.display());
+    Ok(())
+}
+
+/// The files to compare between the code at `checked_path` and the code generated into
+/// `generated_dir`. That's every file on either side, or with a symlink to the single file
+/// generated with `gen-onefile`, that one file.
+fn comparisons(checked_path: &Path, generated_dir: &Path) -> Result<Vec<Comparison>> {
+    let generated_files = list_files(generated_dir)?;
+    if checked_path.is_file() {
+        let generated_code: Vec<_> = generated_files
+            .iter()
+            .filter(|file| file.extension().is_some_and(|ext| ext == "rs"))
+            .collect();
+        if let [generated_file] = &generated_code[..] {
+            return Ok(vec![Comparison {
+                checked: checked_path.into(),
+                generated: generated_dir.join(generated_file),
+            }]);
+        }
+    }
+
+    let mut files = generated_files;
+    if checked_path.is_dir() {
+        files.extend(list_files(checked_path)?);
+    }
+    files.sort();
+    files.dedup();
+    Ok(files
+        .into_iter()
+        .map(|file| Comparison {
+            checked: checked_path.join(&file),
+            generated: generated_dir.join(file),
+        })
+        .collect())
+}
+
+/// Prints a unified diff from the checked file to the generated one, if they differ. Returns
+/// whether they do.
+fn print_diff(comparison: &Comparison) -> Result<bool> {
+    let checked = read_if_exists(&comparison.checked)?;
+    let generated = read_if_exists(&comparison.generated)?;
+    if checked == generated {
+        return Ok(false);
+    }
+
+    let name = |path: &Path, contents: &Option<Vec<u8>>| match contents {
+        Some(_) => path.display().to_string(),
+        None => MISSING_FILE_NAME.into(),
+    };
+    let checked_name = name(&comparison.checked, &checked);
+    let generated_name = name(&comparison.generated, &generated);
+    let checked = checked.unwrap_or_default();
+    let generated = generated.unwrap_or_default();
+    match (
+        std::str::from_utf8(&checked),
+        std::str::from_utf8(&generated),
+    ) {
+        (Ok(checked), Ok(generated)) => print!(
+            "{}",
+            TextDiff::from_lines(checked, generated)
+                .unified_diff()
+                .header(&checked_name, &generated_name)
+        ),
+        _ => println!("Binary files {checked_name} and {generated_name} differ"),
+    }
+    Ok(true)
+}
+
+/// The contents of the file at `path`, or nothing if there's no such file.
+fn read_if_exists(path: &Path) -> Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("failed to read {}: {e}", path.display()).into()),
+    }
+}
+
+/// Paths, relative to `dir`, of every file under it, other than the stamp.
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative_dir) = pending.pop() {
+        let entries = std::fs::read_dir(dir.join(&relative_dir))
+            .map_err(|e| format!("failed to list {}: {e}", dir.join(&relative_dir).display()))?;
+        for entry in entries {
+            let relative_path = relative_dir.join(entry?.file_name());
+            if dir.join(&relative_path).is_dir() {
+                pending.push(relative_path);
+            } else if relative_path != Path::new(STAMP_FILE_NAME) {
+                files.push(relative_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
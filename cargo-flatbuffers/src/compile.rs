@@ -0,0 +1,35 @@
+//! `cargo flatbuffers compile`, which generates the code of the configured schemas.
+
+use crate::{config::ConfigArgs, Result};
+use flatbuffers_build::BuilderOptions;
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    pub config: ConfigArgs,
+    /// Directory to generate the code into, in place of the `output-path` of the configuration.
+    #[arg(long)]
+    output_path: Option<PathBuf>,
+}
+
+/// Compiles the schemas configured in `args`.
+pub fn run(args: &Args, verbose: bool) -> Result {
+    let builder = builder(args, verbose)?;
+    let output_path = builder.plan()?.output_path;
+    builder.compile()?;
+    eprintln!("generated code in {}", output_path.display());
+    Ok(())
+}
+
+/// The builder options to compile the schemas configured in `args` with.
+pub fn builder(args: &Args, verbose: bool) -> Result<BuilderOptions> {
+    let mut builder = args.config.builder()?;
+    if let Some(output_path) = &args.output_path {
+        builder = builder.set_output_path(output_path);
+    }
+    if verbose {
+        builder = builder.verbose();
+    }
+    Ok(builder)
+}
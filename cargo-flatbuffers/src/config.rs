@@ -0,0 +1,38 @@
+//! Where the commands get the builder options to work with from.
+
+use crate::Result;
+use flatbuffers_build::BuilderOptions;
+use std::path::{Path, PathBuf};
+
+/// File the configuration gets read from when none is passed.
+const DEFAULT_CONFIG_FILE_NAME: &str = "flatbuffers-build.toml";
+
+/// Name of the manifest of a package, which holds the configuration in its
+/// `[package.metadata.flatbuffers]` table rather than at the top level.
+const MANIFEST_FILE_NAME: &str = "Cargo.toml";
+
+#[derive(clap::Args)]
+pub struct ConfigArgs {
+    /// Configuration file to read the schemas and options from. A `Cargo.toml` gets its
+    /// `[package.metadata.flatbuffers]` table read instead.
+    #[arg(long, default_value = DEFAULT_CONFIG_FILE_NAME)]
+    config: PathBuf,
+}
+
+impl ConfigArgs {
+    /// The builder options set in the configuration file, without any of the directives only
+    /// cargo understands.
+    pub fn builder(&self) -> Result<BuilderOptions> {
+        let builder = if self.config.file_name() == Some(MANIFEST_FILE_NAME.as_ref()) {
+            BuilderOptions::from_cargo_manifest(&self.config)?
+        } else {
+            BuilderOptions::from_config_file(&self.config)?
+        };
+        Ok(builder.supress_buildrs_directives())
+    }
+
+    /// The configuration file the options come from.
+    pub fn path(&self) -> &Path {
+        &self.config
+    }
+}
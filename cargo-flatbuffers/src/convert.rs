@@ -0,0 +1,89 @@
+//! `cargo flatbuffers json2bin` and `bin2json`, which convert payloads between JSON and the
+//! flatbuffer binary format with the configured schemas, so captured buffers can be decoded and
+//! fixtures encoded without working out the `flatc` invocation for it.
+
+use crate::{config::ConfigArgs, Result};
+use flatbuffers_build::Converter;
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct ConvertArgs {
+    #[command(flatten)]
+    config: ConfigArgs,
+    /// Fully-qualified root type of the payloads, in place of the `root-type` of the
+    /// configuration or the one the schemas declare.
+    #[arg(long)]
+    root_type: Option<String>,
+    /// Directory to write the converted files to.
+    #[arg(long, default_value = ".")]
+    output_path: PathBuf,
+    /// Read and write binaries prefixed with their size.
+    #[arg(long)]
+    size_prefixed: bool,
+    /// Payload files to convert.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(clap::Args)]
+pub struct JsonToBinaryArgs {
+    #[command(flatten)]
+    convert: ConvertArgs,
+    /// Ignore fields of the JSON that aren't in the schema instead of failing.
+    #[arg(long)]
+    unknown_json: bool,
+}
+
+#[derive(clap::Args)]
+pub struct BinaryToJsonArgs {
+    #[command(flatten)]
+    convert: ConvertArgs,
+    /// Decode binaries that don't start with the file identifier of the schema.
+    #[arg(long)]
+    raw_binary: bool,
+    /// Include fields that are equal to their default value.
+    #[arg(long)]
+    defaults_json: bool,
+}
+
+/// Converts the JSON files in `args` into binaries.
+pub fn json_to_binary(args: &JsonToBinaryArgs) -> Result {
+    let mut converter = converter(&args.convert)?;
+    if args.unknown_json {
+        converter = converter.unknown_json();
+    }
+    print_outputs(&converter.json_to_binary(&args.convert.files)?);
+    Ok(())
+}
+
+/// Decodes the binaries in `args` into JSON.
+pub fn binary_to_json(args: &BinaryToJsonArgs) -> Result {
+    let mut converter = converter(&args.convert)?;
+    if args.raw_binary {
+        converter = converter.raw_binary();
+    }
+    if args.defaults_json {
+        converter = converter.defaults_json();
+    }
+    print_outputs(&converter.binary_to_json(&args.convert.files)?);
+    Ok(())
+}
+
+/// The converter for the root type of the schemas configured in `args`.
+fn converter(args: &ConvertArgs) -> Result<Converter> {
+    let mut builder = args.config.builder()?;
+    if let Some(root_type) = &args.root_type {
+        builder = builder.set_root_type(root_type);
+    }
+    let mut converter = builder.converter()?.set_output_path(&args.output_path);
+    if args.size_prefixed {
+        converter = converter.size_prefixed();
+    }
+    Ok(converter)
+}
+
+fn print_outputs(outputs: &[PathBuf]) {
+    for output in outputs {
+        eprintln!("wrote {}", output.display());
+    }
+}
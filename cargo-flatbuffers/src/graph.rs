@@ -0,0 +1,150 @@
+//! `cargo flatbuffers graph`, which prints the include graph of the configured schemas, to be
+//! rendered with Graphviz or Mermaid. Every schema is labelled with its place in the order they
+//! compile in, and includes between schemas that include each other are highlighted.
+
+use crate::{config::ConfigArgs, Result};
+use flatbuffers_build::IncludeGraph;
+use std::{fmt::Write as _, path::Path};
+
+/// Color of the schemas and includes that are part of a cycle.
+const CYCLE_COLOR: &str = "red";
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    /// Graphviz DOT.
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+    /// Format to print the graph in.
+    #[arg(long, value_enum, default_value_t = Format::Dot)]
+    format: Format,
+}
+
+/// A schema of the graph, as printed.
+struct Node {
+    label: String,
+    in_cycle: bool,
+}
+
+/// Prints the include graph of the schemas configured in `args`, and warns about every cycle.
+pub fn run(args: &Args) -> Result {
+    let graph = args.config.builder()?.include_graph()?;
+    let current_dir = std::env::current_dir()?;
+    let cycles = graph.cycles();
+    let cycle_of = |idx: usize| cycles.iter().position(|cycle| cycle.contains(&idx));
+
+    let mut nodes: Vec<_> = graph
+        .schemas
+        .iter()
+        .enumerate()
+        .map(|(idx, schema)| Node {
+            label: display_path(schema, &current_dir),
+            in_cycle: cycle_of(idx).is_some(),
+        })
+        .collect();
+    for (position, idx) in graph.compile_order().into_iter().enumerate() {
+        nodes[idx].label = format!("{}. {}", position + 1, nodes[idx].label);
+    }
+    let cyclic_includes: Vec<_> = graph
+        .includes
+        .iter()
+        .map(|&(from, to)| cycle_of(from).is_some() && cycle_of(from) == cycle_of(to))
+        .collect();
+
+    print!(
+        "{}",
+        match args.format {
+            Format::Dot => dot(&graph, &nodes, &cyclic_includes),
+            Format::Mermaid => mermaid(&graph, &nodes, &cyclic_includes),
+        }
+    );
+    for cycle in &cycles {
+        let schemas: Vec<_> = cycle
+            .iter()
+            .map(|&idx| display_path(&graph.schemas[idx], &current_dir))
+            .collect();
+        eprintln!("warning: include cycle between {}", schemas.join(", "));
+    }
+    Ok(())
+}
+
+/// The graph in Graphviz DOT.
+fn dot(graph: &IncludeGraph, nodes: &[Node], cyclic_includes: &[bool]) -> String {
+    let escape = |label: &str| label.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut dot = String::from("digraph includes {\n    node [shape=box];\n");
+    for (idx, node) in nodes.iter().enumerate() {
+        let color = if node.in_cycle {
+            format!(", color={CYCLE_COLOR}")
+        } else {
+            String::new()
+        };
+        let _ = writeln!(
+            dot,
+            "    n{idx} [label=\"{}\"{color}];",
+            escape(&node.label)
+        );
+    }
+    for (&(from, to), &cyclic) in graph.includes.iter().zip(cyclic_includes) {
+        let color = if cyclic {
+            format!(" [color={CYCLE_COLOR}]")
+        } else {
+            String::new()
+        };
+        let _ = writeln!(dot, "    n{from} -> n{to}{color};");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// The graph as a Mermaid flowchart.
+fn mermaid(graph: &IncludeGraph, nodes: &[Node], cyclic_includes: &[bool]) -> String {
+    let escape = |label: &str| label.replace('"', "#quot;");
+    let mut mermaid = String::from("flowchart TD\n");
+    for (idx, node) in nodes.iter().enumerate() {
+        let _ = writeln!(mermaid, "    n{idx}[\"{}\"]", escape(&node.label));
+    }
+    for &(from, to) in &graph.includes {
+        let _ = writeln!(mermaid, "    n{from} --> n{to}");
+    }
+
+    let cyclic_nodes: Vec<_> = (0..nodes.len())
+        .filter(|&idx| nodes[idx].in_cycle)
+        .map(|idx| format!("n{idx}"))
+        .collect();
+    if !cyclic_nodes.is_empty() {
+        let _ = writeln!(mermaid, "    classDef cycle stroke:{CYCLE_COLOR}");
+        let _ = writeln!(mermaid, "    class {} cycle", cyclic_nodes.join(","));
+    }
+    // Links are styled by the order they were declared in
+    let cyclic_links: Vec<_> = (0..cyclic_includes.len())
+        .filter(|&idx| cyclic_includes[idx])
+        .map(|idx| idx.to_string())
+        .collect();
+    if !cyclic_links.is_empty() {
+        let _ = writeln!(
+            mermaid,
+            "    linkStyle {} stroke:{CYCLE_COLOR}",
+            cyclic_links.join(",")
+        );
+    }
+    mermaid
+}
+
+/// `path` as shown in the graph: relative to `current_dir` if it's under it, with `/` separators
+/// so that the graph looks the same everywhere.
+fn display_path(path: &Path, current_dir: &Path) -> String {
+    match path.strip_prefix(current_dir) {
+        Ok(relative) => relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => path.display().to_string(),
+    }
+}
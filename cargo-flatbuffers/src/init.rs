@@ -0,0 +1,156 @@
+//! `cargo flatbuffers init`, which sets a package up to generate code from its schemas in a build
+//! script: a `schemas` directory with an example schema, a `build.rs` compiling it, the
+//! dependencies that takes and a gitignore entry for the symlink to the generated code. Files
+//! that are already there are left alone.
+
+use crate::Result;
+use flatbuffers_build::SUPPORTED_FLATC_VERSION;
+use std::path::{Path, PathBuf};
+
+/// Directory the schemas go in, relative to the package.
+const SCHEMA_DIR: &str = "schemas";
+
+/// Name of the example schema.
+const EXAMPLE_SCHEMA_FILE_NAME: &str = "example.fbs";
+
+/// Symlink to the generated code, relative to the package.
+const SYMLINK_DIRECTORY: &str = "src/gen_flatbuffers";
+
+const EXAMPLE_SCHEMA: &str = r#"// Example schema, compiled by build.rs. See https://flatbuffers.dev/schema/ for the syntax.
+
+namespace MyGame.Sample;
+
+struct Vec3 {
+  x: float;
+  y: float;
+  z: float;
+}
+
+table Monster {
+  pos: Vec3 (id: 0);
+  hp: short = 100 (id: 1);
+  name: string (id: 2);
+}
+
+root_type Monster;
+file_identifier "MONS";
+"#;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory of the package to set up.
+    #[arg(long, default_value = ".")]
+    path: PathBuf,
+}
+
+/// Sets up the package at the path in `args`.
+pub fn run(args: &Args) -> Result {
+    let package_dir = &args.path;
+    let manifest_path = package_dir.join("Cargo.toml");
+    if !manifest_path.is_file() {
+        return Err(format!(
+            "no Cargo.toml in {}, run this from the root of a package",
+            package_dir.display()
+        )
+        .into());
+    }
+
+    // Written with `/` rather than the separator of the platform, as it ends up in Rust code
+    let schema_path = format!("{SCHEMA_DIR}/{EXAMPLE_SCHEMA_FILE_NAME}");
+    std::fs::create_dir_all(package_dir.join(SCHEMA_DIR))?;
+    write_new_file(package_dir, Path::new(&schema_path), EXAMPLE_SCHEMA)?;
+    let build_script = build_script(&schema_path);
+    if !write_new_file(package_dir, Path::new("build.rs"), &build_script)?
+        && !std::fs::read_to_string(package_dir.join("build.rs"))?.contains("flatbuffers_build")
+    {
+        println!("add this to build.rs to compile the example schema:\n\n{build_script}");
+    }
+    add_dependencies(&manifest_path)?;
+    add_gitignore_entry(package_dir)?;
+
+    println!(
+        "\nThe generated code is now reachable through {SYMLINK_DIRECTORY} after a build. Declare \
+         it as a module in src/main.rs or src/lib.rs to use it:\n\n#[allow(warnings)]\n\
+         #[rustfmt::skip]\nmod gen_flatbuffers;\n\nuse gen_flatbuffers::my_game::sample::Monster;"
+    );
+    Ok(())
+}
+
+/// A build script compiling the schema at `schema_path`.
+fn build_script(schema_path: &str) -> String {
+    format!(
+        "use flatbuffers_build::BuilderOptions;\n\nfn main() {{\n    \
+         BuilderOptions::new_with_files([\"{schema_path}\"])\n        \
+         .set_symlink_directory(\"{SYMLINK_DIRECTORY}\")\n        .compile()\n        \
+         .expect(\"flatbuffer compilation failed\");\n}}\n"
+    )
+}
+
+/// Writes `contents` to `path`, relative to `package_dir`, unless there's a file there already.
+/// Returns whether it got written.
+fn write_new_file(package_dir: &Path, path: &Path, contents: &str) -> Result<bool> {
+    let full_path = package_dir.join(path);
+    if full_path.exists() {
+        println!("leaving {} as it is, it already exists", path.display());
+        return Ok(false);
+    }
+    std::fs::write(&full_path, contents)?;
+    println!("created {}", path.display());
+    Ok(true)
+}
+
+/// Adds `flatbuffers` to the dependencies of the package and `flatbuffers-build` to its build
+/// dependencies, pinned to the versions matching this command, unless they're already there.
+fn add_dependencies(manifest_path: &Path) -> Result {
+    let mut manifest: toml_edit::DocumentMut = std::fs::read_to_string(manifest_path)?.parse()?;
+    // The version without the `+flatc-...` build metadata, which cargo ignores anyway
+    let version = env!("CARGO_PKG_VERSION")
+        .split('+')
+        .next()
+        .unwrap_or_default();
+    let mut changed = false;
+    for (table, name, version) in [
+        ("dependencies", "flatbuffers", SUPPORTED_FLATC_VERSION),
+        ("build-dependencies", "flatbuffers-build", version),
+    ] {
+        let dependencies = manifest
+            .entry(table)
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or_else(|| format!("[{table}] of {} isn't a table", manifest_path.display()))?;
+        if dependencies.contains_key(name) {
+            println!("leaving the {name} dependency as it is, it's already in Cargo.toml");
+            continue;
+        }
+        dependencies.insert(name, toml_edit::value(format!("={version}")));
+        println!("added {name} to the [{table}] of Cargo.toml");
+        changed = true;
+    }
+    if changed {
+        std::fs::write(manifest_path, manifest.to_string())?;
+    }
+    Ok(())
+}
+
+/// Adds the symlink to the generated code to the `.gitignore` of the package, as it points into
+/// the target directory.
+fn add_gitignore_entry(package_dir: &Path) -> Result {
+    let path = package_dir.join(".gitignore");
+    let entry = format!("/{SYMLINK_DIRECTORY}");
+    let mut contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if contents.lines().any(|line| line.trim() == entry) {
+        return Ok(());
+    }
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(&entry);
+    contents.push('\n');
+    std::fs::write(&path, contents)?;
+    println!("added {entry} to .gitignore");
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! `cargo flatbuffers lint`, which checks the configured schemas against the lint rules of
+//! flatbuffers-build, printing every problem along with the line of the schema it's on.
+
+use crate::{config::ConfigArgs, Result};
+use clap::builder::PossibleValuesParser;
+use flatbuffers_build::{Lint, LintRule};
+use std::collections::HashMap;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+    /// Rule not to check. Can be passed more than once.
+    #[arg(long, value_name = "RULE", value_parser = PossibleValuesParser::new(LintRule::ALL.map(LintRule::name)))]
+    allow: Vec<String>,
+}
+
+/// Lints the schemas configured in `args`, failing if any of them breaks a rule.
+pub fn run(args: &Args) -> Result {
+    let allowed: Vec<_> = args
+        .allow
+        .iter()
+        .filter_map(|name| LintRule::from_name(name))
+        .collect();
+    let lints: Vec<_> = args
+        .config
+        .builder()?
+        .lint_schemas()?
+        .into_iter()
+        .filter(|lint| !allowed.contains(&lint.rule))
+        .collect();
+
+    let mut sources = HashMap::new();
+    for lint in &lints {
+        let source = sources
+            .entry(lint.file.clone())
+            .or_insert_with(|| std::fs::read_to_string(&lint.file).unwrap_or_default());
+        print_lint(lint, source);
+    }
+    if !lints.is_empty() {
+        let schemas = sources.len();
+        return Err(format!(
+            "{} {} in {schemas} {}",
+            lints.len(),
+            if lints.len() == 1 { "lint" } else { "lints" },
+            if schemas == 1 { "schema" } else { "schemas" },
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Prints `lint` the way rustc prints warnings, pointing at the offending part of its line in
+/// `source`, the contents of the schema.
+fn print_lint(lint: &Lint, source: &str) {
+    let line_number = lint.line.to_string();
+    let gutter = " ".repeat(line_number.len());
+    let line = source.lines().nth(lint.line - 1).unwrap_or_default();
+    // Tabs would throw the marker off, so they're shown as single spaces on both lines
+    let line = line.replace('\t', " ");
+    println!("warning: {} [{}]", lint.message, lint.rule);
+    println!(
+        "{gutter}--> {}:{}:{}",
+        lint.file.display(),
+        lint.line,
+        lint.column
+    );
+    println!("{gutter} |");
+    println!("{line_number} | {line}");
+    println!(
+        "{gutter} | {}{}",
+        " ".repeat(lint.column.saturating_sub(1)),
+        "^".repeat(lint.length.max(1))
+    );
+    println!();
+}
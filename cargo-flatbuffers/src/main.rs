@@ -0,0 +1,112 @@
+//! `cargo flatbuffers`, which runs the same code generation as a build script calling
+//! `flatbuffers-build` does, from the command line. That lets anything outside of cargo, like
+//! other build systems or non-Rust consumers of the schemas, generate the exact same code:
+//!
+//! ```text
+//! cargo flatbuffers compile --config flatbuffers-build.toml
+//! cargo flatbuffers check --config flatbuffers-build.toml
+//! cargo flatbuffers watch --config flatbuffers-build.toml
+//! cargo flatbuffers graph --format mermaid
+//! cargo flatbuffers lint
+//! cargo flatbuffers init
+//! cargo flatbuffers vendor
+//! cargo flatbuffers json2bin captured.json
+//! cargo flatbuffers bin2json captured.bin
+//! ```
+//!
+//! The configuration file holds the keys of the `[package.metadata.flatbuffers]` table at its top
+//! level. Passing a `Cargo.toml` reads that table from it instead.
+
+mod check;
+mod compile;
+mod config;
+mod convert;
+mod graph;
+mod init;
+mod lint;
+mod vendor;
+mod watch;
+
+use clap::{Parser, Subcommand};
+use std::process::ExitCode;
+use tracing_subscriber::filter::LevelFilter;
+
+/// Result of a command, with errors printed before exiting.
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Cargo runs `cargo-flatbuffers` with `flatbuffers` as the first argument.
+#[derive(Parser)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    Flatbuffers(Cli),
+}
+
+/// Generates Rust code from flatbuffer schemas, the same way flatbuffers-build does from build
+/// scripts.
+#[derive(clap::Args)]
+#[command(version, about)]
+struct Cli {
+    /// Print every step of the compilation and every flatc invocation.
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compile the configured schemas.
+    Compile(compile::Args),
+    /// Check that the generated code is up to date with the configured schemas, printing a diff
+    /// of every file that isn't.
+    Check(check::Args),
+    /// Compile the configured schemas again every time they change, printing any errors.
+    Watch(compile::Args),
+    /// Print the include graph of the configured schemas, along with the order they compile in
+    /// and any include cycles.
+    Graph(graph::Args),
+    /// Check the configured schemas against the lint rules, failing if any of them breaks one.
+    Lint(lint::Args),
+    /// Set a package up to generate code from its schemas in a build script.
+    Init(init::Args),
+    /// Print the path of the flatc vendored into the shared cache when installing
+    /// cargo-flatbuffers with one of the vendored features.
+    Vendor,
+    /// Convert JSON payloads into binaries with the root type of the configured schemas.
+    Json2bin(convert::JsonToBinaryArgs),
+    /// Decode binary payloads into JSON with the root type of the configured schemas.
+    Bin2json(convert::BinaryToJsonArgs),
+}
+
+fn main() -> ExitCode {
+    let Cargo::Flatbuffers(cli) = Cargo::parse();
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(if cli.verbose {
+            LevelFilter::DEBUG
+        } else {
+            LevelFilter::INFO
+        })
+        .without_time()
+        .with_target(false)
+        .init();
+
+    let result = match cli.command {
+        Command::Compile(args) => compile::run(&args, cli.verbose),
+        Command::Check(args) => check::run(&args, cli.verbose),
+        Command::Watch(args) => watch::run(&args, cli.verbose),
+        Command::Graph(args) => graph::run(&args),
+        Command::Lint(args) => lint::run(&args),
+        Command::Init(args) => init::run(&args),
+        Command::Vendor => vendor::run(),
+        Command::Json2bin(args) => convert::json_to_binary(&args),
+        Command::Bin2json(args) => convert::binary_to_json(&args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! `cargo flatbuffers vendor`, which prints the path of the `flatc` vendored into the shared cache
+//! when installing `cargo-flatbuffers` with one of the `vendored` features. Installing it is what
+//! downloads or builds `flatc`, so CI images can be warmed up by installing it ahead of any build,
+//! and air-gapped environments can stage the binary from the printed path.
+
+use crate::Result;
+
+/// Prints the path of the vendored `flatc` to stdout, and its version to stderr.
+pub fn run() -> Result {
+    let Some((path, version)) = flatbuffers_build::vendored_flatc() else {
+        return Err(
+            "cargo-flatbuffers was installed without vendoring flatc, install it again \
+                    with `--features vendored-prebuilt` to download the release binary or \
+                    `--features vendored` to build it from source"
+                .into(),
+        );
+    };
+    // Without the shared cache, it was vendored into a target directory that might be gone by now
+    if !path.is_file() {
+        return Err(format!(
+            "the flatc {version} vendored when installing cargo-flatbuffers is no longer at {}, \
+             install it again to vendor it again",
+            path.display()
+        )
+        .into());
+    }
+    eprintln!("flatc {version}");
+    println!("{}", path.display());
+    Ok(())
+}
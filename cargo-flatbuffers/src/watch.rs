@@ -0,0 +1,100 @@
+//! `cargo flatbuffers watch`, which compiles the configured schemas again every time one of them,
+//! or the configuration, changes. That gives schema authors the errors `flatc` reports as they
+//! edit, without running a build in a loop.
+
+use crate::{compile, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+/// How long to wait for more changes after one, so that saving several schemas at once, or an
+/// editor writing a file in a few steps, only compiles once.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(100);
+
+/// Compiles the schemas configured in `args`, then again on every change, until interrupted.
+pub fn run(args: &compile::Args, verbose: bool) -> Result {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    let mut watched_files = BTreeSet::from([absolute(args.config.path())]);
+    let mut watched_dirs = BTreeSet::new();
+    loop {
+        if let Some(dependencies) = compile_once(args, verbose) {
+            watched_files = dependencies
+                .iter()
+                .map(|dependency| absolute(dependency))
+                .chain([absolute(args.config.path())])
+                .collect();
+        }
+
+        // Editors often save by replacing files, which only the directories they're in notice
+        let dirs: BTreeSet<_> = watched_files
+            .iter()
+            .filter_map(|file| file.parent())
+            .map(Path::to_path_buf)
+            .collect();
+        for dir in dirs.difference(&watched_dirs) {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+        for dir in watched_dirs.difference(&dirs) {
+            // Fails if the directory is gone, which stops the watch just as well
+            let _ = watcher.unwatch(dir);
+        }
+        watched_dirs = dirs;
+
+        eprintln!("watching {} files for changes", watched_files.len());
+        let changed = wait_for_change(&receiver, &watched_files)?;
+        eprintln!("{} changed, compiling again", changed.display());
+    }
+}
+
+/// Compiles the schemas configured in `args`, printing how that went. Returns the schemas the
+/// generated code depends on, if they could be worked out.
+fn compile_once(args: &compile::Args, verbose: bool) -> Option<Vec<PathBuf>> {
+    let builder = match compile::builder(args, verbose) {
+        Ok(builder) => builder,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return None;
+        }
+    };
+    let plan = match builder.plan() {
+        Ok(plan) => plan,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return None;
+        }
+    };
+    match builder.compile() {
+        Ok(()) => eprintln!("generated code in {}", plan.output_path.display()),
+        Err(e) => eprintln!("error: {e}"),
+    }
+    Some(plan.dependencies)
+}
+
+/// Blocks until one of `files` changes, then until changes stop coming in. Returns the first file
+/// that changed.
+fn wait_for_change(
+    receiver: &mpsc::Receiver<notify::Result<notify::Event>>,
+    files: &BTreeSet<PathBuf>,
+) -> Result<PathBuf> {
+    let changed = loop {
+        let event = receiver.recv()??;
+        if matches!(event.kind, EventKind::Access(_)) {
+            continue;
+        }
+        if let Some(path) = event.paths.into_iter().find(|path| files.contains(path)) {
+            break path;
+        }
+    };
+    while receiver.recv_timeout(DEBOUNCE_DELAY).is_ok() {}
+    Ok(changed)
+}
+
+/// `path`, made absolute so that it can be compared against the paths of events.
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.into())
+}
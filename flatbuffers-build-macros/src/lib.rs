@@ -0,0 +1,208 @@
+//! The [`include_fbs!`] and [`flatbuffers_schema`] macros, which run `flatc` on a schema while
+//! the crate using them compiles and expand to the generated code, for crates that would rather
+//! not have a build script just for that. It builds on [`flatbuffers_build`], so `flatc` gets found the same way, and its
+//! `vendored` and `vendored-prebuilt` features are available here too.
+//!
+//! ```rust,ignore
+//! mod monster {
+//!     flatbuffers_build_macros::include_fbs!("schemas/monster.fbs");
+//! }
+//!
+//! use monster::my_game::sample::Monster;
+//! ```
+//!
+//! The attribute fills a module with the generated code instead, where editors expanding macros
+//! can see it:
+//!
+//! ```rust,ignore
+//! #[flatbuffers_build_macros::flatbuffers_schema("schemas/monster.fbs")]
+//! mod monster {}
+//!
+//! use monster::Monster;
+//! ```
+
+use flatbuffers_build::BuilderOptions;
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+use syn::{AttrStyle, ItemMod, LitStr};
+
+/// Directory the generated code goes in, under `OUT_DIR` for crates with a build script, or else
+/// under the temporary directory of the system.
+const OUTPUT_DIR_NAME: &str = "flatbuffers-build-macros";
+
+/// Compiles the flatbuffer schema at the given path, relative to the directory of the crate, and
+/// expands to the code generated from it, along with the code of every schema it includes and a
+/// `prelude` module re-exporting the generated types. Expanding the same schema twice in a module would declare everything in it twice, so give it
+/// a module of its own.
+///
+/// `flatc` only runs when the schema, or one of the schemas it includes, changed since the last
+/// time, which also makes the crate get recompiled. Errors compiling the schema, like `flatc`
+/// failing or not being found, get reported as compilation errors.
+///
+/// ```rust,ignore
+/// mod monster {
+///     flatbuffers_build_macros::include_fbs!("schemas/monster.fbs");
+/// }
+/// ```
+#[proc_macro]
+pub fn include_fbs(input: TokenStream) -> TokenStream {
+    syn::parse::<LitStr>(input)
+        .and_then(|schema| expand(&schema))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(schema: &LitStr) -> syn::Result<proc_macro2::TokenStream> {
+    let compiled = compile(schema)?;
+    let dependencies = compiled.dependency_bytes();
+    let generated_files = compiled
+        .generated_files
+        .iter()
+        .map(|file| LitStr::new(&file.to_string_lossy(), Span::call_site()));
+    Ok(quote! {
+        #dependencies
+        #(include!(#generated_files);)*
+    })
+}
+
+/// Binds a module to the flatbuffer schema at the given path, relative to the directory of the
+/// crate: the module gets filled with the code generated from it, along with the code of every
+/// schema it includes, and re-exports the generated types at its top level. Items already in the
+/// module are kept.
+///
+/// Unlike with [`include_fbs!`], the generated code ends up in the expansion of the macro itself,
+/// rather than getting pulled in with `include!`, so editors expanding macros, like
+/// rust-analyzer, see every generated item.
+///
+/// ```rust,ignore
+/// #[flatbuffers_build_macros::flatbuffers_schema("schemas/monster.fbs")]
+/// mod monster {}
+///
+/// use monster::Monster;
+/// ```
+#[proc_macro_attribute]
+pub fn flatbuffers_schema(args: TokenStream, item: TokenStream) -> TokenStream {
+    syn::parse::<LitStr>(args)
+        .and_then(|schema| Ok((schema, syn::parse::<ItemMod>(item)?)))
+        .and_then(|(schema, module)| expand_module(&schema, module))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_module(schema: &LitStr, module: ItemMod) -> syn::Result<proc_macro2::TokenStream> {
+    let Some((_, items)) = module.content else {
+        return Err(syn::Error::new_spanned(
+            module,
+            "flatbuffers_schema needs a module with a body, like `mod name {}`",
+        ));
+    };
+    let compiled = compile(schema)?;
+    let dependencies = compiled.dependency_bytes();
+    let generated_code = compiled
+        .generated_files
+        .iter()
+        .map(|file| {
+            std::fs::read_to_string(file)
+                .map_err(|e| schema_error(schema, &format!("failed to read {file:?}: {e}")))?
+                .parse::<proc_macro2::TokenStream>()
+                .map_err(|e| schema_error(schema, &format!("failed to parse {file:?}: {e}")))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let (inner_attrs, outer_attrs): (Vec<_>, Vec<_>) = module
+        .attrs
+        .into_iter()
+        .partition(|attr| matches!(attr.style, AttrStyle::Inner(_)));
+    let ItemMod {
+        vis,
+        unsafety,
+        mod_token,
+        ident,
+        ..
+    } = module;
+    Ok(quote! {
+        #(#outer_attrs)*
+        #vis #unsafety #mod_token #ident {
+            #(#inner_attrs)*
+            #(#generated_code)*
+            #dependencies
+            pub use self::prelude::*;
+            #(#items)*
+        }
+    })
+}
+
+/// The code generated from a schema, by [`compile`].
+struct Compiled {
+    /// Every schema the generated code depends on.
+    dependencies: Vec<PathBuf>,
+    /// Absolute paths of the generated Rust files.
+    generated_files: Vec<PathBuf>,
+}
+
+impl Compiled {
+    /// Reads in every schema the generated code depends on, which is what makes rustc recompile
+    /// the crate when they change.
+    fn dependency_bytes(&self) -> proc_macro2::TokenStream {
+        let dependencies = self
+            .dependencies
+            .iter()
+            .map(|dependency| LitStr::new(&dependency.to_string_lossy(), Span::call_site()));
+        quote! { #(const _: &[u8] = include_bytes!(#dependencies);)* }
+    }
+}
+
+/// Compiles the schema at the path in `schema`, relative to the directory of the crate, into a
+/// single file along with every schema it includes, and with a prelude re-exporting its types.
+fn compile(schema: &LitStr) -> syn::Result<Compiled> {
+    let error = |message: &dyn std::fmt::Display| schema_error(schema, message);
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .ok_or_else(|| error(&"CARGO_MANIFEST_DIR is not set, the macro needs cargo"))?;
+    let schema_path = manifest_dir.join(schema.value());
+    let builder = BuilderOptions::new_with_files([&schema_path])
+        .add_include_path(&manifest_dir)
+        .set_output_path(output_dir(&schema_path))
+        // The macro expands in one place, so everything has to be in one file
+        .gen_onefile()
+        .gen_all()
+        .generate_prelude()
+        // Crates expanding the same schema can compile at the same time
+        .shared_output()
+        .supress_buildrs_directives();
+    let plan = builder.plan().map_err(|e| error(&e))?;
+    builder.compile().map_err(|e| error(&e))?;
+
+    let generated_files = plan
+        .outputs
+        .iter()
+        .filter(|output| output.extension().is_some_and(|ext| ext == "rs"))
+        .map(|output| plan.output_path.join(output))
+        .collect();
+    Ok(Compiled {
+        dependencies: plan.dependencies,
+        generated_files,
+    })
+}
+
+/// An error compiling `schema`, reported at the schema path in the macro invocation.
+fn schema_error(schema: &LitStr, message: &dyn std::fmt::Display) -> syn::Error {
+    syn::Error::new(schema.span(), message)
+}
+
+/// The directory to generate the code of the schema at `schema_path` into, which is specific to
+/// it so that the code of different schemas doesn't get mixed up.
+fn output_dir(schema_path: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    schema_path.hash(&mut hasher);
+    std::env::var_os("OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(OUTPUT_DIR_NAME)
+        .join(format!("{:016x}", hasher.finish()))
+}
@@ -4,6 +4,8 @@ import tomlkit
 import click
 from semver import Version
 
+# Released along with the library they depend on
+DEPENDENT_CARGO_TOMLS = ["flatbuffers-build-macros/Cargo.toml", "cargo-flatbuffers/Cargo.toml"]
 
 @click.command()
 @click.argument("bump_level", type=click.Choice(["major", "minor", "patch"]))
@@ -30,6 +32,16 @@ def main(bump_level: str):
     with open("Cargo.toml", mode="wt", encoding="utf-8") as f:
         tomlkit.dump(cargo_toml, f)
 
+    for path in DEPENDENT_CARGO_TOMLS:
+        with open(path, mode="rt", encoding="utf-8") as f:
+            dependent_cargo_toml = tomlkit.load(f)
+        dependent_cargo_toml["package"]["version"] = str(new_version)
+        dependent_cargo_toml["dependencies"]["flatbuffers-build"]["version"] = str(
+            new_version.replace(build=None)
+        )
+        with open(path, mode="wt", encoding="utf-8") as f:
+            tomlkit.dump(dependent_cargo_toml, f)
+
 
 if __name__ == "__main__":
     main()
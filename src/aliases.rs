@@ -0,0 +1,127 @@
+//! Type aliases for the root types of the schemas, written with
+//! [`crate::BuilderOptions::generate_aliases`]. Root types are the ones buffers get read as, so
+//! they come up in most of the code using the generated code, and their aliases spare it from
+//! spelling out the modules of their namespaces.
+
+use crate::{
+    log, output,
+    schema::{self, OutputLayout},
+    BuilderOptions, Error, Result,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+/// Name of the module the aliases go in, and of its file next to the module root.
+pub(crate) const ALIASES_MODULE_NAME: &str = "aliases";
+
+/// Adds the aliases of the root types of `schemas` to the code generated from them into
+/// `staging_dir` with `layout`. That's an `aliases.rs` declared in `mod.rs`, or with
+/// [`OutputLayout::one_file`], an `aliases` module at the end of the file generated from each
+/// schema, covering the root type of that schema.
+pub(crate) fn write(
+    builder_options: &BuilderOptions,
+    schemas: &[PathBuf],
+    layout: &OutputLayout,
+    staging_dir: &Path,
+) -> Result {
+    if layout.one_file {
+        for schema in schemas {
+            let root_types = root_types(builder_options, std::slice::from_ref(schema))?;
+            for file in layout.generated_files_for(schema)? {
+                let contents = format!(
+                    "\npub mod {ALIASES_MODULE_NAME} {{\n{}}}\n",
+                    type_aliases(&root_types, "    ")
+                );
+                output::append_file(&staging_dir.join(file), &contents)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let root_types = root_types(builder_options, schemas)?;
+    let aliases_path = staging_dir.join(format!("{ALIASES_MODULE_NAME}.rs"));
+    let contents = format!(
+        "// Aliases of the root types, written by flatbuffers-build.\n// @generated\n{}",
+        type_aliases(&root_types, "")
+    );
+    std::fs::write(&aliases_path, contents).map_err(|source| Error::OutputWriteFailure {
+        path: aliases_path,
+        source,
+    })?;
+    output::append_file(
+        &staging_dir.join(output::MOD_ROOT_FILE_NAME),
+        &format!("pub mod {ALIASES_MODULE_NAME};\n"),
+    )
+}
+
+/// The root types of `schemas`, as fully-qualified names split on `.`, by their name. That's the
+/// root type set with [`BuilderOptions::set_root_type`] for the schemas declaring it, or else the
+/// ones the schemas declare themselves. Names of root types in more than one namespace are left
+/// out, as their aliases would clash.
+fn root_types(
+    builder_options: &BuilderOptions,
+    schemas: &[PathBuf],
+) -> Result<BTreeMap<String, Vec<String>>> {
+    let overridden: Option<Vec<String>> = builder_options
+        .root_type
+        .as_ref()
+        .map(|root_type| root_type.split('.').map(String::from).collect());
+    let mut root_types = BTreeSet::new();
+    for schema in schemas {
+        let schema = schema::Schema::from_file(schema)?;
+        let root_type = match &overridden {
+            Some(root_type) => schema
+                .declarations
+                .iter()
+                .any(|declaration| {
+                    root_type.split_last() == Some((&declaration.name, &declaration.namespace))
+                })
+                .then(|| root_type.clone()),
+            None => schema.root_type,
+        };
+        root_types.extend(root_type);
+    }
+
+    let mut by_name: BTreeMap<String, Vec<Vec<String>>> = BTreeMap::new();
+    for root_type in root_types {
+        if let Some(name) = root_type.last() {
+            by_name.entry(name.clone()).or_default().push(root_type);
+        }
+    }
+    let mut unique = BTreeMap::new();
+    for (name, mut root_types) in by_name {
+        match root_types.pop() {
+            Some(root_type) if root_types.is_empty() => {
+                unique.insert(name, root_type);
+            }
+            _ => log::warning!(
+                builder_options.directives,
+                "not aliasing {name}, as it's a root type in more than one namespace"
+            ),
+        }
+    }
+    Ok(unique)
+}
+
+/// The aliases of `root_types`, for the module one level down from the root of the generated
+/// code, each on lines starting with `indent`.
+fn type_aliases(root_types: &BTreeMap<String, Vec<String>>, indent: &str) -> String {
+    let mut aliases = String::new();
+    for (name, root_type) in root_types {
+        let path = root_type[..root_type.len() - 1]
+            .iter()
+            .fold(String::new(), |path, part| {
+                path + &schema::to_snake_case(part) + "::"
+            });
+        let _ = writeln!(
+            aliases,
+            "{indent}/// Alias of the root type `{}`.\n\
+             {indent}pub type {name}<'a> = super::{path}{name}<'a>;",
+            root_type.join(".")
+        );
+    }
+    aliases
+}
@@ -0,0 +1,54 @@
+//! Opt-in cache of generated code, shared by every build on a machine. Entries are keyed by the
+//! fingerprint of what the code got generated from (see [`crate::stamp::fingerprint`]), so that
+//! switching branches or building in a fresh target directory restores the generated code without
+//! running `flatc` for anything but its version.
+
+use crate::{output, Error, Result};
+use std::path::{Path, PathBuf};
+
+/// Environment variable with the directory to cache generated code in, same as
+/// [`crate::BuilderOptions::set_output_cache_dir`].
+pub(crate) const CACHE_DIR_ENV_VAR: &str = "FLATBUFFERS_BUILD_OUTPUT_CACHE_DIR";
+
+/// The cache directory set through [`CACHE_DIR_ENV_VAR`], if any. A leading `~` stands for the
+/// home directory, since the variable is often set somewhere the shell won't expand it.
+pub(crate) fn cache_dir_from_env() -> Option<PathBuf> {
+    let dir = std::env::var_os(CACHE_DIR_ENV_VAR).filter(|dir| !dir.is_empty())?;
+    let dir = PathBuf::from(dir);
+    match (dir.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Some(PathBuf::from(home).join(rest)),
+        _ => Some(dir),
+    }
+}
+
+/// Copies the code cached under `fingerprint` into `staging_dir`. Returns whether there was any.
+pub(crate) fn restore(cache_dir: &Path, fingerprint: &str, staging_dir: &Path) -> Result<bool> {
+    let entry = cache_dir.join(fingerprint);
+    if !entry.is_dir() {
+        return Ok(false);
+    }
+    output::copy_files(&entry, staging_dir)?;
+    Ok(true)
+}
+
+/// Caches the code in `staging_dir` under `fingerprint`, unless it already is.
+pub(crate) fn store(cache_dir: &Path, fingerprint: &str, staging_dir: &Path) -> Result {
+    let entry = cache_dir.join(fingerprint);
+    if entry.is_dir() {
+        return Ok(());
+    }
+    let failure = |source| Error::OutputWriteFailure {
+        path: cache_dir.into(),
+        source,
+    };
+    std::fs::create_dir_all(cache_dir).map_err(failure)?;
+    // Fill in a temporary directory first so that other builds never pick up a partial entry
+    let staged = tempfile::tempdir_in(cache_dir).map_err(failure)?;
+    output::copy_files(staging_dir, staged.path())?;
+    // Once renamed there's nothing left for the temporary directory to clean up
+    match std::fs::rename(staged.path(), &entry) {
+        // Another build cached the same code in the meantime
+        Err(_) if entry.is_dir() => Ok(()),
+        result => result.map_err(failure),
+    }
+}
@@ -0,0 +1,131 @@
+//! Schema conformance checking. This makes sure a set of schemas is a valid evolution of an older
+//! baseline schema, which is how you make sure changes to a schema stay backwards compatible.
+
+use crate::{confirm_flatc_version, resolve_compiler, run_flatc, Error, Flatc, Result};
+use std::{ffi::OsString, path::Path};
+
+const FLATC_ERROR_PREFIX: &str = "error:";
+
+/// Checks that the schemas in `files` are a valid evolution of the `baseline` schema, by running
+/// `flatc` with `--conform`. This is useful to fail a CI build whenever someone makes a backwards
+/// incompatible change to a schema. The compiler is resolved the same way as when calling
+/// [`crate::BuilderOptions::compile`] without setting a compiler. If you're already compiling the
+/// schemas, consider using [`crate::BuilderOptions::check_conformance`] instead.
+///
+/// ```no_run
+/// flatbuffers_build::check_conformance("baseline/example.fbs", ["schemas/example.fbs"])
+///     .expect("schema is not backwards compatible");
+/// ```
+///
+/// # Arguments
+/// * `baseline` - The older schema the files provided should be an evolution of.
+/// * `files` - The schemas to check against the baseline.
+///
+/// # Errors
+/// Returns [`Error::ConformanceViolation`] if any of the schemas break compatibility with the
+/// baseline, or any of the errors that can come up when running `flatc`.
+pub fn check_conformance<P: AsRef<Path>, Q: AsRef<Path>, I: IntoIterator<Item = Q>>(
+    baseline: P,
+    files: I,
+) -> Result {
+    check_conformance_with_includes(baseline, std::iter::empty::<&Path>(), files)
+}
+
+/// Same as [`check_conformance`], but also provides the include paths used to resolve the
+/// `include` directives of the baseline schema (passed to `flatc` as `--conform-includes`). Use
+/// this when your baseline schemas live in a different directory tree than the schemas being
+/// checked.
+///
+/// ```no_run
+/// flatbuffers_build::check_conformance_with_includes(
+///     "baseline/example.fbs",
+///     ["baseline"],
+///     ["schemas/weapon.fbs", "schemas/example.fbs"],
+/// )
+/// .expect("schema is not backwards compatible");
+/// ```
+///
+/// # Arguments
+/// * `baseline` - The older schema the files provided should be an evolution of.
+/// * `conform_include_paths` - Directories to look for the baseline schema's includes in.
+/// * `files` - The schemas to check against the baseline.
+///
+/// # Errors
+/// Returns [`Error::ConformanceViolation`] if any of the schemas break compatibility with the
+/// baseline, or any of the errors that can come up when running `flatc`.
+pub fn check_conformance_with_includes<
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    R: AsRef<Path>,
+    I: IntoIterator<Item = Q>,
+    J: IntoIterator<Item = R>,
+>(
+    baseline: P,
+    conform_include_paths: I,
+    files: J,
+) -> Result {
+    let compiler = resolve_compiler(None);
+    confirm_flatc_version(&compiler)?;
+    let conform_include_paths: Vec<_> = conform_include_paths
+        .into_iter()
+        .map(|p| p.as_ref().to_path_buf())
+        .collect();
+    let files: Vec<_> = files
+        .into_iter()
+        .map(|f| f.as_ref().to_path_buf())
+        .collect();
+    run_conformance_check(&compiler, baseline.as_ref(), &conform_include_paths, &files)
+}
+
+/// Runs the conformance check with an already resolved and verified compiler.
+pub(crate) fn run_conformance_check<P: AsRef<Path>, Q: AsRef<Path>>(
+    compiler: &Flatc,
+    baseline: &Path,
+    conform_include_paths: &[P],
+    files: &[Q],
+) -> Result {
+    match run_flatc(
+        compiler,
+        conformance_args(baseline, conform_include_paths, files),
+    ) {
+        Ok(_) => Ok(()),
+        Err(Error::FlatcErrorCode { stdout, stderr, .. }) => Err(Error::ConformanceViolation {
+            baseline: baseline.into(),
+            violations: parse_violations(&stdout, &stderr),
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// Arguments for the `flatc` invocation checking `files` against `baseline`.
+pub(crate) fn conformance_args<P: AsRef<Path>, Q: AsRef<Path>>(
+    baseline: &Path,
+    conform_include_paths: &[P],
+    files: &[Q],
+) -> Vec<OsString> {
+    let mut args = vec![OsString::from("--conform"), baseline.as_os_str().to_owned()];
+    for include_path in conform_include_paths {
+        args.extend([
+            OsString::from("--conform-includes"),
+            include_path.as_ref().as_os_str().to_owned(),
+        ]);
+    }
+    args.extend(files.iter().map(|f| f.as_ref().as_os_str().to_owned()));
+    args
+}
+
+/// Pulls out the individual error messages `flatc` printed, dropping any `flatc: error:` style
+/// prefix.
+fn parse_violations(stdout: &str, stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .chain(stdout.lines())
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.find(FLATC_ERROR_PREFIX)
+                .map_or(line, |idx| line[idx + FLATC_ERROR_PREFIX.len()..].trim())
+                .to_owned()
+        })
+        .collect()
+}
@@ -0,0 +1,77 @@
+//! Running `flatc` inside a container image with docker or podman, for hosts where running a
+//! downloaded binary directly isn't an option.
+
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Environment variable selecting the container engine to run images with.
+const ENGINE_ENV_VAR: &str = "FLATC_CONTAINER_ENGINE";
+const DEFAULT_ENGINE: &str = "docker";
+
+/// Builds the command running `flatc` with `args` inside `image`. The current directory and the
+/// directories of any paths among the arguments get mounted at the same location inside the
+/// container, so the arguments can be passed along untouched. When `offline`, the image has to be
+/// available locally already rather than getting pulled.
+pub(crate) fn command(image: &str, args: Vec<OsString>, offline: bool) -> std::io::Result<Command> {
+    let current_dir = std::env::current_dir()?;
+    let mut mounts = BTreeSet::from([current_dir.clone()]);
+    mounts.extend(args.iter().filter_map(|arg| mount_for(Path::new(arg))));
+
+    let engine = std::env::var(ENGINE_ENV_VAR).unwrap_or_else(|_| DEFAULT_ENGINE.into());
+    let mut command = Command::new(engine);
+    command.args(["run", "--rm", "--entrypoint", "flatc"]);
+    if offline {
+        command.arg("--pull=never");
+    }
+    // Otherwise files get written out as whatever user the image runs as, usually root
+    if let Some(user) = current_user() {
+        command.arg("--user").arg(user);
+    }
+    for mount in mounts {
+        let mut spec = OsString::from("type=bind,source=");
+        spec.push(&mount);
+        spec.push(",target=");
+        spec.push(&mount);
+        command.arg("--mount").arg(spec);
+    }
+    command
+        .arg("--workdir")
+        .arg(current_dir)
+        .arg(image)
+        .args(args);
+    Ok(command)
+}
+
+/// The directory to mount for `path` to be visible in the container, if it's a path at all.
+fn mount_for(path: &Path) -> Option<PathBuf> {
+    if !path.exists() {
+        return None;
+    }
+    let path = std::path::absolute(path).ok()?;
+    if path.is_dir() {
+        Some(path)
+    } else {
+        path.parent().map(Path::to_path_buf)
+    }
+}
+
+#[cfg(unix)]
+fn current_user() -> Option<String> {
+    let id = |flag: &str| {
+        let output = Command::new("id").arg(flag).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    };
+    Some(format!("{}:{}", id("-u")?, id("-g")?))
+}
+
+#[cfg(not(unix))]
+fn current_user() -> Option<String> {
+    None
+}
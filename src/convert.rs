@@ -0,0 +1,498 @@
+//! Conversion of data files between JSON and the flatbuffer binary format, using `flatc`. This can
+//! be used from a `build.rs` to bake fixtures into binaries, or from tools that need to produce
+//! flatbuffers from human-readable data or inspect captured buffers.
+
+use crate::{
+    confirm_flatc_version,
+    directives::Directives,
+    proto, resolve_compiler, run_flatc,
+    schema::{DeclarationKind, Schema},
+    Backend, BuilderOptions, Error, Result,
+};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+const DEFAULT_BINARY_EXTENSION: &str = "bin";
+
+/// Builder for converting data files using a flatbuffer schema. The basic usage for this struct
+/// looks something like this:
+/// ```no_run
+/// use flatbuffers_build::Converter;
+///
+/// let binaries = Converter::new("schemas/example.fbs")
+///     .set_root_type("MyGame.Sample.Monster")
+///     .set_output_path("fixtures")
+///     .json_to_binary(["fixtures/orc.json", "fixtures/goblin.json"])
+///     .expect("conversion failed");
+/// ```
+///
+/// Like [`crate::BuilderOptions`], unless you call [`Self::supress_buildrs_directives`] this will
+/// print `build.rs` directives so that conversion re-runs whenever the inputs change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Converter {
+    schema: Option<PathBuf>,
+    root_type: Option<String>,
+    compiler: Option<Backend>,
+    include_paths: Vec<PathBuf>,
+    output_path: Option<PathBuf>,
+    directives: Directives,
+    flatc_flags: Vec<&'static str>,
+}
+
+impl Converter {
+    /// Create a new converter for data matching the schema provided.
+    ///
+    /// # Arguments
+    /// * `schema` - The schema describing the data being converted. Includes get resolved relative
+    ///   to it, same as when compiling.
+    #[must_use]
+    pub fn new<P: AsRef<Path>>(schema: P) -> Self {
+        Converter {
+            schema: Some(schema.as_ref().into()),
+            root_type: None,
+            compiler: None,
+            include_paths: Vec::new(),
+            output_path: None,
+            directives: Directives::default(),
+            flatc_flags: Vec::new(),
+        }
+    }
+
+    /// Create a new converter for [flexbuffers](https://flatbuffers.dev/flexbuffers/), the
+    /// schemaless sibling of flatbuffers (by passing `--flexbuffers` to `flatc`). With it,
+    /// [`Self::json_to_binary`] produces flexbuffer blobs and [`Self::binary_to_json`] decodes
+    /// them, so you can round-trip flexbuffers with the same tooling as the rest of your data:
+    /// ```no_run
+    /// use flatbuffers_build::Converter;
+    ///
+    /// let blobs = Converter::flexbuffers()
+    ///     .set_output_path("fixtures")
+    ///     .json_to_binary(["fixtures/settings.json"])
+    ///     .expect("conversion failed");
+    /// ```
+    #[must_use]
+    pub fn flexbuffers() -> Self {
+        Converter {
+            schema: None,
+            root_type: None,
+            compiler: None,
+            include_paths: Vec::new(),
+            output_path: None,
+            directives: Directives::default(),
+            flatc_flags: vec!["--flexbuffers"],
+        }
+    }
+
+    /// Set up a converter for data of the root type of the schemas of `builder_options`. See
+    /// [`BuilderOptions::converter`].
+    pub(crate) fn for_builder(builder_options: &BuilderOptions) -> Result<Self> {
+        let (schema, root_type) = match &builder_options.root_type {
+            Some(root_type) => (
+                declaring_schema(builder_options, root_type)?,
+                root_type.clone(),
+            ),
+            None => declared_root_type(builder_options)?,
+        };
+        Ok(Converter {
+            compiler: builder_options.compiler.clone(),
+            include_paths: builder_options.include_paths.clone(),
+            directives: builder_options.directives.clone(),
+            ..Converter::new(schema).set_root_type(root_type)
+        })
+    }
+
+    /// Set the root type of the data being converted (passed to `flatc` as `--root-type`). This is
+    /// required if the schema doesn't declare a `root_type` itself.
+    ///
+    /// # Arguments
+    /// * `root_type` - Fully-qualified name of the root table, such as `MyGame.Sample.Monster`.
+    #[must_use]
+    pub fn set_root_type<S: AsRef<str>>(self, root_type: S) -> Self {
+        Converter {
+            root_type: Some(root_type.as_ref().into()),
+            ..self
+        }
+    }
+
+    /// Set the path of the `flatc` binary to use. If no such path is provided, it gets resolved the
+    /// same way as in [`crate::BuilderOptions::set_compiler`].
+    ///
+    /// # Arguments
+    /// * `compiler` - Path to the compiler to run. This can also be a name that we should resolve
+    ///   using standard `PATH` resolution.
+    #[must_use]
+    pub fn set_compiler<S: AsRef<str>>(self, compiler: S) -> Self {
+        Converter {
+            compiler: Some(Backend::Native(compiler.as_ref().into())),
+            ..self
+        }
+    }
+
+    /// Add a directory to look for the schemas the schema includes in (passed to `flatc` as `-I`),
+    /// same as [`crate::BuilderOptions::add_include_path`].
+    ///
+    /// # Arguments
+    /// * `include_path` - Directory to resolve `include` statements against.
+    #[must_use]
+    pub fn add_include_path<P: AsRef<Path>>(mut self, include_path: P) -> Self {
+        self.include_paths.push(include_path.as_ref().into());
+        self
+    }
+
+    /// Run `flatc` inside a container image, the same way as with
+    /// [`crate::BuilderOptions::set_container_image`].
+    ///
+    /// # Arguments
+    /// * `image` - The container image to run `flatc` in.
+    #[must_use]
+    pub fn set_container_image<S: AsRef<str>>(self, image: S) -> Self {
+        Converter {
+            compiler: Some(Backend::Container(image.as_ref().into())),
+            ..self
+        }
+    }
+
+    /// Use a `flatc` compiled to WASI, the same way as with
+    /// [`crate::BuilderOptions::set_wasm_compiler`].
+    ///
+    /// # Arguments
+    /// * `module` - Path to the `flatc` WASI module.
+    #[cfg(feature = "wasi")]
+    #[must_use]
+    pub fn set_wasm_compiler<P: AsRef<Path>>(self, module: P) -> Self {
+        Converter {
+            compiler: Some(Backend::Wasi(module.as_ref().into())),
+            ..self
+        }
+    }
+
+    /// Set the directory converted files get written to. If you don't set this, we will default to
+    /// writing to `${OUT_DIR}`.
+    ///
+    /// # Arguments
+    /// * `output_path` - The directory to write the files to.
+    #[must_use]
+    pub fn set_output_path<P: AsRef<Path>>(self, output_path: P) -> Self {
+        Converter {
+            output_path: Some(output_path.as_ref().into()),
+            ..self
+        }
+    }
+
+    /// Set this if you're not running from a `build.rs` script and don't want us to print the
+    /// build.rs instructions/directives that we would otherwise print in stdout.
+    #[must_use]
+    pub fn supress_buildrs_directives(self) -> Self {
+        Converter {
+            directives: Directives::none(),
+            ..self
+        }
+    }
+
+    /// Print directives with `prefix` in front of them instead of `cargo::`, same as
+    /// [`crate::BuilderOptions::set_directive_prefix`].
+    ///
+    /// # Arguments
+    /// * `prefix` - What to print in front of every directive.
+    #[must_use]
+    pub fn set_directive_prefix<S: AsRef<str>>(self, prefix: S) -> Self {
+        Converter {
+            directives: Directives {
+                prefix: prefix.as_ref().into(),
+                ..self.directives
+            },
+            ..self
+        }
+    }
+
+    /// Require and produce strictly conforming JSON, with quoted field names and no trailing commas
+    /// (by passing `--strict-json` to `flatc`).
+    #[must_use]
+    pub fn strict_json(self) -> Self {
+        self.add_flatc_flag("--strict-json")
+    }
+
+    /// Ignore fields in the JSON input that aren't part of the schema instead of failing (by
+    /// passing `--unknown-json` to `flatc`). Useful for producers that emit extra fields.
+    #[must_use]
+    pub fn unknown_json(self) -> Self {
+        self.add_flatc_flag("--unknown-json")
+    }
+
+    /// Allow strings that aren't valid UTF-8 in the input, passing them through as-is (by passing
+    /// `--allow-non-utf8` to `flatc`).
+    #[must_use]
+    pub fn allow_non_utf8(self) -> Self {
+        self.add_flatc_flag("--allow-non-utf8")
+    }
+
+    /// Allow decoding binaries that don't start with the `file_identifier` declared in the schema
+    /// (by passing `--raw-binary` to `flatc`). This is needed for schemas that don't declare one.
+    #[must_use]
+    pub fn raw_binary(self) -> Self {
+        self.add_flatc_flag("--raw-binary")
+    }
+
+    /// Treat binaries as size-prefixed, both when reading and writing them (by passing
+    /// `--size-prefixed` to `flatc`). Use this when your framing layer expects buffers to start
+    /// with their length.
+    #[must_use]
+    pub fn size_prefixed(self) -> Self {
+        self.add_flatc_flag("--size-prefixed")
+    }
+
+    /// Include fields that are equal to their default value when producing JSON (by passing
+    /// `--defaults-json` to `flatc`).
+    #[must_use]
+    pub fn defaults_json(self) -> Self {
+        self.add_flatc_flag("--defaults-json")
+    }
+
+    /// Converts each of the JSON files provided into a flatbuffer binary (by running `flatc` with
+    /// `--binary`). The binary for `name.json` gets written to the output directory as `name.bin`,
+    /// or with whatever extension the schema declares with `file_extension`.
+    ///
+    /// # Arguments
+    /// * `json_files` - The JSON files to convert.
+    ///
+    /// # Errors
+    /// Will fail if any of the JSON files don't match the schema, or for any of the reasons
+    /// compilation can fail. See [`Error`] for more details.
+    pub fn json_to_binary<P: AsRef<Path>, I: IntoIterator<Item = P>>(
+        &self,
+        json_files: I,
+    ) -> Result<Vec<PathBuf>> {
+        let json_files: Vec<PathBuf> = json_files
+            .into_iter()
+            .map(|f| f.as_ref().to_path_buf())
+            .collect();
+        let extension = match &self.schema {
+            Some(schema) => Schema::from_file(schema)?.file_extension,
+            None => None,
+        }
+        .unwrap_or_else(|| DEFAULT_BINARY_EXTENSION.into());
+
+        let output_path = self.run("--binary", &json_files, false)?;
+
+        Ok(output_files(&output_path, &json_files, &extension))
+    }
+
+    /// Decodes each of the flatbuffer binaries provided into JSON (by running `flatc` with
+    /// `--json`). The JSON for `name.bin` gets written to the output directory as `name.json`.
+    /// This is particularly handy for debugging buffers captured in tests.
+    ///
+    /// ```no_run
+    /// use flatbuffers_build::Converter;
+    ///
+    /// let json_files = Converter::new("schemas/example.fbs")
+    ///     .set_root_type("MyGame.Sample.Monster")
+    ///     .set_output_path("decoded")
+    ///     .raw_binary()
+    ///     .strict_json()
+    ///     .supress_buildrs_directives()
+    ///     .binary_to_json(["captured/monster.bin"])
+    ///     .expect("conversion failed");
+    /// ```
+    ///
+    /// # Arguments
+    /// * `binary_files` - The flatbuffer binaries to decode.
+    ///
+    /// # Errors
+    /// Will fail if any of the binaries can't be decoded with the schema, or for any of the reasons
+    /// compilation can fail. See [`Error`] for more details.
+    pub fn binary_to_json<P: AsRef<Path>, I: IntoIterator<Item = P>>(
+        &self,
+        binary_files: I,
+    ) -> Result<Vec<PathBuf>> {
+        let binary_files: Vec<PathBuf> = binary_files
+            .into_iter()
+            .map(|f| f.as_ref().to_path_buf())
+            .collect();
+        let output_path = self.run("--json", &binary_files, true)?;
+        Ok(output_files(&output_path, &binary_files, "json"))
+    }
+
+    /// Produces an annotated dump of each of the flatbuffer binaries provided (by running `flatc`
+    /// with `--annotate`). The dump for `name.bin` gets written to the output directory as
+    /// `name.afb`, and describes every byte of the buffer alongside the schema field it belongs to.
+    /// This is very useful when debugging corrupted buffers.
+    ///
+    /// # Arguments
+    /// * `binary_files` - The flatbuffer binaries to annotate.
+    ///
+    /// # Errors
+    /// Will fail for any of the reasons compilation can fail. See [`Error`] for more details.
+    pub fn annotate<P: AsRef<Path>, I: IntoIterator<Item = P>>(
+        &self,
+        binary_files: I,
+    ) -> Result<Vec<PathBuf>> {
+        let binary_files: Vec<PathBuf> = binary_files
+            .into_iter()
+            .map(|f| f.as_ref().to_path_buf())
+            .collect();
+        let output_path = self.run("--annotate", &binary_files, true)?;
+        Ok(output_files(&output_path, &binary_files, "afb"))
+    }
+
+    /// Generates a JSON document for the root type with every field populated with its default
+    /// value, which makes for a good starting point when writing fixtures by hand. This works by
+    /// converting an empty JSON object into a binary with `--force-defaults`, and then decoding it
+    /// back with `--defaults-json`. The document gets written to the output directory as
+    /// `name.json`. Note that this fails if the root type has any `required` fields.
+    ///
+    /// ```no_run
+    /// use flatbuffers_build::Converter;
+    ///
+    /// let sample = Converter::new("schemas/example.fbs")
+    ///     .set_root_type("MyGame.Sample.Monster")
+    ///     .set_output_path("fixtures")
+    ///     .supress_buildrs_directives()
+    ///     .default_json("monster_defaults")
+    ///     .expect("failed to generate sample JSON");
+    /// ```
+    ///
+    /// # Arguments
+    /// * `name` - File stem of the JSON document to write.
+    ///
+    /// # Errors
+    /// Will fail for any of the reasons compilation can fail. See [`Error`] for more details.
+    pub fn default_json<S: AsRef<str>>(&self, name: S) -> Result<PathBuf> {
+        let scratch_dir = tempfile::tempdir().map_err(Error::StagingDirectoryFailure)?;
+        let empty_json = scratch_dir.path().join(format!("{}.json", name.as_ref()));
+        std::fs::write(&empty_json, "{}").map_err(|source| Error::OutputWriteFailure {
+            path: empty_json.clone(),
+            source,
+        })?;
+
+        let binaries = Converter {
+            output_path: Some(scratch_dir.path().into()),
+            directives: Directives::none(),
+            ..self.clone()
+        }
+        .add_flatc_flag("--force-defaults")
+        .json_to_binary([&empty_json])?;
+        let json_files = Converter {
+            directives: Directives::none(),
+            ..self.clone()
+        }
+        .add_flatc_flag("--defaults-json")
+        .binary_to_json(&binaries)?;
+
+        if let Some(schema) = &self.schema {
+            self.directives.rerun_if_changed(schema);
+        }
+        Ok(json_files.into_iter().next().unwrap_or_default())
+    }
+
+    fn add_flatc_flag(mut self, flag: &'static str) -> Self {
+        if !self.flatc_flags.contains(&flag) {
+            self.flatc_flags.push(flag);
+        }
+        self
+    }
+
+    /// Runs `flatc` in the conversion mode given by `mode_flag` over `data_files`, returning the
+    /// directory the results were written to. Set `binary_inputs` when the data files are
+    /// flatbuffer binaries rather than JSON.
+    fn run(&self, mode_flag: &str, data_files: &[PathBuf], binary_inputs: bool) -> Result<PathBuf> {
+        let compiler = resolve_compiler(self.compiler.as_ref());
+        let output_path = match &self.output_path {
+            Some(output_path) => output_path.clone(),
+            None => std::env::var_os("OUT_DIR")
+                .ok_or(Error::OutputDirNotSet)?
+                .into(),
+        };
+        confirm_flatc_version(&compiler)?;
+
+        let mut args = vec![OsString::from(mode_flag)];
+        args.extend(self.flatc_flags.iter().map(OsString::from));
+        if let Some(root_type) = &self.root_type {
+            args.extend([OsString::from("--root-type"), OsString::from(root_type)]);
+        }
+        args.extend([OsString::from("-o"), output_path.as_os_str().to_owned()]);
+        args.extend(
+            self.include_paths
+                .iter()
+                .flat_map(|path| [OsString::from("-I"), path.as_os_str().to_owned()]),
+        );
+        if let Some(schema) = &self.schema {
+            args.push(schema.as_os_str().to_owned());
+        }
+        // flatc expects binary data files to come after a `--` separator
+        if binary_inputs {
+            args.push(OsString::from("--"));
+        }
+        args.extend(data_files.iter().map(|f| f.as_os_str().to_owned()));
+        run_flatc(&compiler, &args)?;
+
+        if let Some(schema) = &self.schema {
+            self.directives.rerun_if_changed(schema);
+        }
+        for input in data_files {
+            self.directives.rerun_if_changed(input);
+        }
+        Ok(output_path)
+    }
+}
+
+/// Paths of the files `flatc` writes to `output_path` when converting `inputs`, which keep their
+/// file stem but get a new `extension`.
+fn output_files(output_path: &Path, inputs: &[PathBuf], extension: &str) -> Vec<PathBuf> {
+    inputs
+        .iter()
+        .map(|input| {
+            let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+            output_path.join(format!("{stem}.{extension}"))
+        })
+        .collect()
+}
+
+/// The schema declaring `root_type` as a table, among the inputs of `builder_options` and the
+/// schemas they include.
+fn declaring_schema(builder_options: &BuilderOptions, root_type: &str) -> Result<PathBuf> {
+    let (namespace, name) = root_type.rsplit_once('.').unwrap_or(("", root_type));
+    let namespace: Vec<_> = namespace
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .collect();
+    for schema in builder_options.include_graph()?.schemas {
+        if proto::is_proto(&schema) {
+            continue;
+        }
+        let declarations = Schema::from_file(&schema)?.declarations;
+        if declarations.iter().any(|declaration| {
+            declaration.kind == DeclarationKind::Table
+                && declaration.name == name
+                && declaration.namespace == namespace
+        }) {
+            return Ok(schema);
+        }
+    }
+    Err(Error::UnknownRootType(root_type.into()))
+}
+
+/// The root type the inputs of `builder_options` declare with `root_type`, along with the first
+/// input declaring it, as long as they don't declare more than one.
+fn declared_root_type(builder_options: &BuilderOptions) -> Result<(PathBuf, String)> {
+    let mut root_types: Vec<(PathBuf, String)> = Vec::new();
+    for schema in builder_options.files.iter().filter(|f| !proto::is_proto(f)) {
+        if let Some(root_type) = Schema::from_file(schema)?.root_type {
+            let root_type = root_type.join(".");
+            if !root_types.iter().any(|(_, known)| *known == root_type) {
+                root_types.push((schema.clone(), root_type));
+            }
+        }
+    }
+    match root_types.len() {
+        0 => Err(Error::MissingRootType),
+        1 => Ok(root_types.remove(0)),
+        _ => Err(Error::AmbiguousRootType(
+            root_types
+                .into_iter()
+                .map(|(_, root_type)| root_type)
+                .collect(),
+        )),
+    }
+}
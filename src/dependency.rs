@@ -0,0 +1,85 @@
+//! Sharing schemas between crates. A "schema crate" publishes the directories holding its schemas
+//! through cargo's `links` metadata, which the build scripts of the crates depending on it can
+//! then pick up as include paths with [`crate::BuilderOptions::add_dependency_schemas`].
+
+use crate::{Error, Result};
+use std::path::{Path, PathBuf};
+
+/// Key of the `links` metadata the schema directories of a crate get published under. Dependent
+/// build scripts see it as `DEP_<links name>_SCHEMA_DIR`.
+const SCHEMA_DIR_METADATA_KEY: &str = "schema_dir";
+
+/// Publishes the directories in `dirs` to the build scripts of the crates depending on the one
+/// being built, so that they can include the schemas in them. This only works from the build
+/// script of a crate with a `links` key in its `Cargo.toml`, which is what cargo uses to name the
+/// variable these end up in:
+///
+/// ```toml
+/// [package]
+/// name = "my-schemas"
+/// links = "my-schemas"
+/// ```
+///
+/// ```no_run
+/// flatbuffers_build::export_schemas(["schemas"]).expect("failed to export schemas");
+/// ```
+///
+/// Dependent build scripts then get the absolute paths of the directories in the
+/// `DEP_MY_SCHEMAS_SCHEMA_DIR` environment variable, separated the same way as `PATH`. Since
+/// they're absolute, schemas can be shared this way both within a workspace and from crates
+/// published to a registry, as long as the schemas are part of the published package.
+///
+/// # Arguments
+/// * `dirs` - Directories holding the schemas to export, relative to the crate's root directory.
+///
+/// # Errors
+/// Fails if any of the directories doesn't exist or has a path that can't be put in an
+/// environment variable.
+pub fn export_schemas<P: AsRef<Path>, I: IntoIterator<Item = P>>(dirs: I) -> Result {
+    let dirs = dirs
+        .into_iter()
+        .map(|dir| schema_dir(dir.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+    // Every path got checked on its own already
+    let joined = std::env::join_paths(&dirs).unwrap_or_default();
+    println!(
+        "cargo::metadata={SCHEMA_DIR_METADATA_KEY}={}",
+        joined.to_string_lossy()
+    );
+    Ok(())
+}
+
+/// The absolute path of the schema directory `dir`, making sure it is one.
+fn schema_dir(dir: &Path) -> Result<PathBuf> {
+    let failure = |source| Error::SchemaExportFailure {
+        path: dir.into(),
+        source,
+    };
+    let canonical = dir.canonicalize().map_err(failure)?;
+    if !canonical.is_dir() {
+        return Err(failure(std::io::ErrorKind::NotADirectory.into()));
+    }
+    // Paths containing the separator can't be told apart from two paths once joined
+    if let Err(e) = std::env::join_paths([&canonical]) {
+        return Err(failure(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            e,
+        )));
+    }
+    Ok(canonical)
+}
+
+/// The schema directories exported by the dependency whose `links` key is `links`, as found in
+/// the `DEP_<links>_SCHEMA_DIR` environment variable cargo sets for our build script.
+pub(crate) fn schema_dirs(links: &str) -> Result<Vec<PathBuf>> {
+    let env_var = format!(
+        "DEP_{}_{}",
+        links.to_uppercase().replace('-', "_"),
+        SCHEMA_DIR_METADATA_KEY.to_uppercase()
+    );
+    let dirs = std::env::var_os(&env_var).ok_or_else(|| Error::MissingDependencySchemas {
+        dependency: links.into(),
+        env_var,
+    })?;
+    Ok(std::env::split_paths(&dirs).collect())
+}
@@ -0,0 +1,72 @@
+//! Makefile-style dependency files, listing the schemas the generated code depends on. These let
+//! build systems wrapping this crate, like Bazel, Buck2 or ninja, rebuild exactly when any of the
+//! schemas change, including the ones pulled in through `include`.
+
+use crate::{proto, schema, Error, Result};
+use std::{
+    collections::HashSet,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+/// Every schema the compilation of `files` reads: the inputs themselves, followed by everything
+/// they include, directly or not.
+pub(crate) fn schema_dependencies(
+    files: &[PathBuf],
+    include_paths: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    let mut dependencies = files.to_vec();
+    let mut seen: HashSet<_> = files
+        .iter()
+        .map(|file| std::fs::canonicalize(file).unwrap_or_else(|_| file.clone()))
+        .collect();
+    let mut idx = 0;
+    while idx < dependencies.len() {
+        let file = dependencies[idx].clone();
+        idx += 1;
+        if proto::is_proto(&file) {
+            continue;
+        }
+        for include in schema::Schema::from_file(&file)?.includes {
+            // flatc reports includes it can't find, so there's no need to do it twice
+            let Some(resolved) = schema::resolve_include(&file, &include, include_paths) else {
+                continue;
+            };
+            if seen.insert(resolved.clone()) {
+                dependencies.push(resolved);
+            }
+        }
+    }
+    Ok(dependencies)
+}
+
+/// Writes a depfile to `path` with a rule for each of the `outputs`, making each depend on all of
+/// the `dependencies`.
+pub(crate) fn write(path: &Path, outputs: &[PathBuf], dependencies: &[PathBuf]) -> Result {
+    let mut prerequisites = String::new();
+    for dependency in dependencies {
+        let _ = write!(prerequisites, " \\\n  {}", escape(dependency));
+    }
+    let mut contents = String::new();
+    for output in outputs {
+        let _ = writeln!(contents, "{}:{prerequisites}", escape(output));
+    }
+    std::fs::write(path, contents).map_err(|source| Error::OutputWriteFailure {
+        path: path.into(),
+        source,
+    })
+}
+
+/// Escapes `path` the way make and ninja expect paths in depfiles to be.
+fn escape(path: &Path) -> String {
+    let mut escaped = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            ' ' | '#' | '\\' => escaped.push('\\'),
+            '$' => escaped.push('$'),
+            _ => {}
+        }
+        escaped.push(c);
+    }
+    escaped
+}
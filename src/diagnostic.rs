@@ -0,0 +1,264 @@
+//! Parsing of the errors and warnings `flatc` prints into structured values. `flatc` reports
+//! problems in a schema as `flatc: error: path/to/schema.fbs:3: 10: error: some message` (or
+//! `path\to\schema.fbs(3, 10): error: some message` when built with MSVC), with messages that
+//! aren't tied to a schema lacking the location part.
+
+use serde::Serialize;
+use std::{fmt, path::PathBuf};
+
+const FLATC_PROGRAM_PREFIX: &str = "flatc:";
+
+/// How severe a problem reported by `flatc` is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The problem stopped `flatc` from generating code.
+    Error,
+    /// The problem got reported, but didn't stop code generation.
+    Warning,
+}
+
+impl Severity {
+    fn from_label(label: &str) -> Option<Severity> {
+        match label {
+            "error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// A single error or warning reported by `flatc`, such as a syntax error in one of the schemas.
+/// These come attached to [`crate::Error::FlatcErrorCode`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Schema file the problem was found in, as `flatc` printed it. Not set for problems that
+    /// aren't tied to a schema, like invalid command line arguments.
+    pub file: Option<PathBuf>,
+    /// 1-based line number in [`Self::file`] where the problem was found.
+    pub line: Option<u32>,
+    /// Column in [`Self::line`] where the problem was found.
+    pub column: Option<u32>,
+    /// Whether this is an error or a warning.
+    pub severity: Severity,
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+            if let Some(line) = self.line {
+                write!(f, "{line}:")?;
+                if let Some(column) = self.column {
+                    write!(f, "{column}:")?;
+                }
+            }
+            f.write_str(" ")?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Extracts every diagnostic from the output of a `flatc` run. Lines that aren't errors or
+/// warnings, like the usage text `flatc` prints after some errors, are skipped.
+pub(crate) fn parse(stdout: &str, stderr: &str) -> Vec<Diagnostic> {
+    stderr
+        .lines()
+        .chain(stdout.lines())
+        .filter_map(parse_line)
+        .collect()
+}
+
+fn parse_line(line: &str) -> Option<Diagnostic> {
+    let line = line.trim();
+    let line = line
+        .strip_prefix(FLATC_PROGRAM_PREFIX)
+        .map_or(line, str::trim_start);
+
+    // The parser's own messages repeat the severity after the location, so prefer that one
+    for (idx, _) in line.match_indices(": ") {
+        let rest = &line[idx + 2..];
+        let Some((label, message)) = rest.split_once(": ") else {
+            continue;
+        };
+        let Some(severity) = Severity::from_label(label) else {
+            continue;
+        };
+        let location = &line[..idx];
+        let location = location
+            .split_once(": ")
+            .filter(|(outer, _)| Severity::from_label(outer).is_some())
+            .map_or(location, |(_, location)| location);
+        if let Some((file, line, column)) = parse_location(location) {
+            return Some(Diagnostic {
+                file: Some(file),
+                line: Some(line),
+                column,
+                severity,
+                message: message.trim().to_owned(),
+            });
+        }
+    }
+
+    let (label, message) = line.split_once(": ")?;
+    Some(Diagnostic {
+        file: None,
+        line: None,
+        column: None,
+        severity: Severity::from_label(label)?,
+        message: message.trim().to_owned(),
+    })
+}
+
+/// Parses either `file:line: column`, `file:line` or `file(line, column)`.
+fn parse_location(location: &str) -> Option<(PathBuf, u32, Option<u32>)> {
+    if let Some(location) = location.strip_suffix(')') {
+        let (file, position) = location.rsplit_once('(')?;
+        let (line, column) = position.split_once(',')?;
+        return Some((
+            file.into(),
+            line.trim().parse().ok()?,
+            Some(column.trim().parse().ok()?),
+        ));
+    }
+
+    let (rest, last) = location.rsplit_once(':')?;
+    let last = last.trim().parse().ok()?;
+    if let Some((file, line)) = rest.rsplit_once(':') {
+        if let Ok(line) = line.trim().parse() {
+            return (!file.is_empty()).then(|| (file.into(), line, Some(last)));
+        }
+    }
+    (!rest.is_empty()).then(|| (rest.into(), last, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(
+        file: Option<&str>,
+        line: Option<u32>,
+        column: Option<u32>,
+        severity: Severity,
+        message: &str,
+    ) -> Diagnostic {
+        Diagnostic {
+            file: file.map(PathBuf::from),
+            line,
+            column,
+            severity,
+            message: message.into(),
+        }
+    }
+
+    #[test]
+    fn parse_line_reads_file_line_and_column() {
+        assert_eq!(
+            parse_line(
+                "flatc: error: schemas/monster.fbs:3: 10: error: expecting: ; instead got: }"
+            ),
+            Some(diagnostic(
+                Some("schemas/monster.fbs"),
+                Some(3),
+                Some(10),
+                Severity::Error,
+                "expecting: ; instead got: }"
+            ))
+        );
+        assert_eq!(
+            parse_line("warning: schemas/monster.fbs:7: 2: warning: field id not set"),
+            Some(diagnostic(
+                Some("schemas/monster.fbs"),
+                Some(7),
+                Some(2),
+                Severity::Warning,
+                "field id not set"
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_line_reads_windows_paths() {
+        assert_eq!(
+            parse_line(r"flatc: error: C:\schemas\monster.fbs:3: 10: error: unknown type: Foo"),
+            Some(diagnostic(
+                Some(r"C:\schemas\monster.fbs"),
+                Some(3),
+                Some(10),
+                Severity::Error,
+                "unknown type: Foo"
+            ))
+        );
+        assert_eq!(
+            parse_line(r"C:\schemas\monster.fbs(3, 10): error: unknown type: Foo"),
+            Some(diagnostic(
+                Some(r"C:\schemas\monster.fbs"),
+                Some(3),
+                Some(10),
+                Severity::Error,
+                "unknown type: Foo"
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_line_reads_messages_without_location() {
+        assert_eq!(
+            parse_line("flatc: error: unknown commandline argument: --foo"),
+            Some(diagnostic(
+                None,
+                None,
+                None,
+                Severity::Error,
+                "unknown commandline argument: --foo"
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_line_skips_other_lines() {
+        assert_eq!(
+            parse_line("Usage: flatc [OPTION]... FILE... [-- BINARY_FILE...]"),
+            None
+        );
+        assert_eq!(
+            parse_line("  --rust  Generate Rust files for tables/structs."),
+            None
+        );
+        assert_eq!(parse_line(""), None);
+    }
+
+    #[test]
+    fn parse_location_reads_every_form() {
+        assert_eq!(
+            parse_location("schemas/monster.fbs:3: 10"),
+            Some(("schemas/monster.fbs".into(), 3, Some(10)))
+        );
+        assert_eq!(
+            parse_location("schemas/monster.fbs:3"),
+            Some(("schemas/monster.fbs".into(), 3, None))
+        );
+        assert_eq!(
+            parse_location(r"C:\schemas\monster.fbs:3"),
+            Some((r"C:\schemas\monster.fbs".into(), 3, None))
+        );
+        assert_eq!(
+            parse_location(r"C:\schemas\monster.fbs(3, 10)"),
+            Some((r"C:\schemas\monster.fbs".into(), 3, Some(10)))
+        );
+        assert_eq!(parse_location("schemas/monster.fbs"), None);
+        assert_eq!(parse_location(":3"), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! The directives we print for cargo to pick up when running from a build script.
+
+use std::{fmt, path::Path};
+
+/// Prefix of the directives cargo understands.
+const CARGO_DIRECTIVE_PREFIX: &str = "cargo::";
+
+/// Which directives to print, and how.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Directives {
+    /// Print `rerun-if-changed` and `rerun-if-env-changed` directives.
+    pub rerun: bool,
+    /// Print warnings, along with anything else meant for the user, as `warning` directives.
+    pub warnings: bool,
+    /// Print `rustc-env` and `metadata` directives publishing where the generated code is.
+    pub publish: bool,
+    /// Printed in front of every directive in place of `cargo::`.
+    pub prefix: String,
+}
+
+impl Default for Directives {
+    fn default() -> Self {
+        Directives {
+            rerun: true,
+            warnings: true,
+            publish: true,
+            prefix: CARGO_DIRECTIVE_PREFIX.into(),
+        }
+    }
+}
+
+impl Directives {
+    /// Directives that don't print anything.
+    pub fn none() -> Self {
+        Directives {
+            rerun: false,
+            warnings: false,
+            publish: false,
+            ..Directives::default()
+        }
+    }
+
+    pub fn rerun_if_changed(&self, path: &Path) {
+        if self.rerun {
+            println!("{}rerun-if-changed={}", self.prefix, path.display());
+        }
+    }
+
+    pub fn rerun_if_env_changed(&self, var: &str) {
+        if self.rerun {
+            println!("{}rerun-if-env-changed={var}", self.prefix);
+        }
+    }
+
+    /// Sets the environment variable `name` to `value` when compiling the crate, and publishes it
+    /// as `key` to the build scripts of dependents.
+    pub fn publish(&self, name: &str, key: &str, value: &Path) {
+        if self.publish {
+            println!("{}rustc-env={name}={}", self.prefix, value.display());
+            println!("{}metadata={key}={}", self.prefix, value.display());
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", allow(dead_code))]
+    pub fn warning(&self, message: fmt::Arguments<'_>) {
+        if self.warnings {
+            println!("{}warning={message}", self.prefix);
+        }
+    }
+}
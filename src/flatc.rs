@@ -0,0 +1,102 @@
+//! The different ways of running `flatc`.
+
+use crate::directives::Directives;
+#[cfg(feature = "wasi")]
+use std::path::PathBuf;
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Environment variable turning on verbose mode, same as [`crate::BuilderOptions::verbose`].
+pub(crate) const VERBOSE_ENV_VAR: &str = "FLATBUFFERS_BUILD_VERBOSE";
+/// Environment variable turning on offline mode, same as [`crate::BuilderOptions::offline`]. The
+/// build script honours it too, refusing to download anything when vendoring `flatc`.
+pub(crate) const OFFLINE_ENV_VAR: &str = "FLATBUFFERS_BUILD_OFFLINE";
+
+/// A `flatc` compiler, along with how to run it.
+#[derive(Clone, Debug)]
+pub(crate) struct Flatc {
+    pub backend: Backend,
+    /// Print every command line we run, and the environment it runs in, as cargo warnings.
+    pub verbose: bool,
+    /// How long to let a single `flatc` invocation run before killing it.
+    pub timeout: Option<Duration>,
+    /// Never reach out to the network, e.g. to pull a container image.
+    pub offline: bool,
+    /// Where verbose output goes when not using `tracing`.
+    pub directives: Directives,
+    /// Every run of this compiler so far, shared with its clones.
+    pub runs: Arc<Runs>,
+}
+
+impl Flatc {
+    pub fn new(backend: Backend) -> Flatc {
+        Flatc {
+            backend,
+            verbose: env_flag(VERBOSE_ENV_VAR),
+            timeout: None,
+            offline: env_flag(OFFLINE_ENV_VAR),
+            directives: Directives::default(),
+            runs: Arc::default(),
+        }
+    }
+
+    /// Whether there's a `flatc` to run. There's no telling whether a container image has one
+    /// without running it, so those always count as having one.
+    pub fn is_available(&self) -> bool {
+        match &self.backend {
+            Backend::Native(program) => {
+                crate::resolve_program(program.as_ref()).is_some_and(|binary| binary.is_file())
+            }
+            Backend::Container(_) => true,
+            #[cfg(feature = "wasi")]
+            Backend::Wasi(module) => module.is_file(),
+        }
+    }
+}
+
+/// How many times a compiler was run and how long those runs took altogether. Runs can happen on
+/// several threads at once, so their times can add up to more than the time that passed.
+#[derive(Debug, Default)]
+pub(crate) struct Runs {
+    count: AtomicUsize,
+    nanos: AtomicU64,
+}
+
+impl Runs {
+    /// Records a run that took `duration`.
+    pub fn record(&self, duration: Duration) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// Whether the environment variable `name` is set to `1` or `true`.
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+/// Where the `flatc` binary comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Backend {
+    /// A native binary, given either as a path or as a name to resolve through the `PATH`.
+    Native(String),
+    /// A container image with `flatc` in it, run with docker or podman.
+    Container(String),
+    /// A `flatc` compiled to a WASI module, run through an embedded wasmtime runtime.
+    #[cfg(feature = "wasi")]
+    Wasi(PathBuf),
+}
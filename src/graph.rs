@@ -0,0 +1,222 @@
+//! The graph of which schemas include which, for tools that need to show or untangle the include
+//! relationships of the schemas of a compilation.
+
+use crate::{proto, schema, visit_dependencies_first, Result};
+use std::path::PathBuf;
+
+/// The schemas a compilation reads and the includes between them, as returned by
+/// [`crate::BuilderOptions::include_graph`]. Includes `flatc` wouldn't find are left out, since
+/// compiling reports them anyway.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IncludeGraph {
+    /// Every schema in the graph: the inputs, as given to the builder, followed by the canonical
+    /// paths of every schema they include, directly or not.
+    pub schemas: Vec<PathBuf>,
+    /// Every include, as the indices in [`Self::schemas`] of the including schema and of the
+    /// included one, in the order the including schemas list them.
+    pub includes: Vec<(usize, usize)>,
+}
+
+impl IncludeGraph {
+    /// Builds the graph of the schemas `files` include, resolving includes against
+    /// `include_paths` the same way `flatc` does.
+    pub(crate) fn new(files: &[PathBuf], include_paths: &[PathBuf]) -> Result<IncludeGraph> {
+        let mut schemas = files.to_vec();
+        let mut canonical: Vec<_> = files
+            .iter()
+            .map(|file| std::fs::canonicalize(file).unwrap_or_else(|_| file.clone()))
+            .collect();
+        let mut includes = Vec::new();
+        let mut idx = 0;
+        while idx < schemas.len() {
+            let file = schemas[idx].clone();
+            if !proto::is_proto(&file) {
+                for include in schema::Schema::from_file(&file)?.includes {
+                    let Some(resolved) = schema::resolve_include(&file, &include, include_paths)
+                    else {
+                        continue;
+                    };
+                    let target = canonical
+                        .iter()
+                        .position(|c| *c == resolved)
+                        .unwrap_or_else(|| {
+                            schemas.push(resolved.clone());
+                            canonical.push(resolved);
+                            schemas.len() - 1
+                        });
+                    includes.push((idx, target));
+                }
+            }
+            idx += 1;
+        }
+        Ok(IncludeGraph { schemas, includes })
+    }
+
+    /// Indices of the schemas, ordered so that every schema comes after the ones it includes.
+    /// Schemas in a cycle can't all be, so they're ordered as they were first reached instead.
+    /// Schemas compiled together have to be listed in this order, see the section on file
+    /// ordering in the crate documentation.
+    #[must_use]
+    pub fn compile_order(&self) -> Vec<usize> {
+        let dependencies = self.dependencies();
+        let mut visited = vec![false; self.schemas.len()];
+        let mut order = Vec::with_capacity(self.schemas.len());
+        for idx in 0..self.schemas.len() {
+            visit_dependencies_first(idx, &dependencies, &mut visited, &mut order);
+        }
+        order
+    }
+
+    /// Every set of schemas including each other, directly or not, as the indices of the schemas
+    /// in it. A schema including itself is a cycle of its own.
+    #[must_use]
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let dependencies = self.dependencies();
+        let mut search = CycleSearch {
+            dependencies: &dependencies,
+            index: vec![None; self.schemas.len()],
+            low_link: vec![0; self.schemas.len()],
+            on_stack: vec![false; self.schemas.len()],
+            stack: Vec::new(),
+            next_index: 0,
+            cycles: Vec::new(),
+        };
+        for idx in 0..self.schemas.len() {
+            if search.index[idx].is_none() {
+                search.visit(idx);
+            }
+        }
+        let mut cycles = search.cycles;
+        for cycle in &mut cycles {
+            cycle.sort_unstable();
+        }
+        cycles.sort();
+        cycles
+    }
+
+    /// Indices of the schemas each schema includes.
+    fn dependencies(&self) -> Vec<Vec<usize>> {
+        let mut dependencies = vec![Vec::new(); self.schemas.len()];
+        for &(from, to) in &self.includes {
+            dependencies[from].push(to);
+        }
+        dependencies
+    }
+}
+
+/// State of Tarjan's search for the strongly connected components of the graph, of which the ones
+/// with more than one schema, or a schema including itself, are cycles.
+struct CycleSearch<'a> {
+    dependencies: &'a [Vec<usize>],
+    /// Order in which each schema was first visited.
+    index: Vec<Option<usize>>,
+    /// Lowest index reachable from each schema through the ones still on the stack.
+    low_link: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    cycles: Vec<Vec<usize>>,
+}
+
+impl CycleSearch<'_> {
+    fn visit(&mut self, idx: usize) {
+        self.index[idx] = Some(self.next_index);
+        self.low_link[idx] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(idx);
+        self.on_stack[idx] = true;
+
+        for &dep in &self.dependencies[idx] {
+            match self.index[dep] {
+                None => {
+                    self.visit(dep);
+                    self.low_link[idx] = self.low_link[idx].min(self.low_link[dep]);
+                }
+                Some(dep_index) if self.on_stack[dep] => {
+                    self.low_link[idx] = self.low_link[idx].min(dep_index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.low_link[idx]) != self.index[idx] {
+            return;
+        }
+        let mut component = Vec::new();
+        while let Some(member) = self.stack.pop() {
+            self.on_stack[member] = false;
+            component.push(member);
+            if member == idx {
+                break;
+            }
+        }
+        if component.len() > 1 || self.dependencies[idx].contains(&idx) {
+            self.cycles.push(component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn write_schema(path: &Path, contents: &str) -> PathBuf {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+        std::fs::canonicalize(path).unwrap()
+    }
+
+    #[test]
+    fn finds_include_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_schema(&dir.path().join("a.fbs"), "include \"b.fbs\";\n");
+        let b = write_schema(&dir.path().join("b.fbs"), "include \"c.fbs\";\n");
+        let c = write_schema(&dir.path().join("c.fbs"), "include \"a.fbs\";\n");
+        let d = write_schema(&dir.path().join("d.fbs"), "include \"d.fbs\";\n");
+
+        let graph = IncludeGraph::new(&[a.clone(), d.clone()], &[]).unwrap();
+
+        assert_eq!(graph.schemas, [a, d, b, c]);
+        assert_eq!(graph.includes, [(0, 2), (1, 1), (2, 3), (3, 0)]);
+        assert_eq!(graph.cycles(), [vec![0, 2, 3], vec![1]]);
+        // Every schema still shows up once, in the order they were first reached
+        assert_eq!(graph.compile_order(), [3, 2, 0, 1]);
+    }
+
+    #[test]
+    fn orders_schemas_after_their_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let monster = write_schema(
+            &dir.path().join("monster.fbs"),
+            "include \"weapon.fbs\";\ninclude \"color.fbs\";\n",
+        );
+        let weapon = write_schema(&dir.path().join("weapon.fbs"), "include \"color.fbs\";\n");
+        let color = write_schema(&dir.path().join("color.fbs"), "");
+
+        let graph = IncludeGraph::new(std::slice::from_ref(&monster), &[]).unwrap();
+
+        assert_eq!(graph.schemas, [monster, weapon, color]);
+        assert!(graph.cycles().is_empty());
+        assert_eq!(graph.compile_order(), [2, 1, 0]);
+    }
+
+    #[test]
+    fn resolves_includes_through_include_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let monster = write_schema(
+            &dir.path().join("schemas/monster.fbs"),
+            "include \"common/weapon.fbs\";\ninclude \"missing.fbs\";\n",
+        );
+        let weapon = write_schema(&dir.path().join("vendor/common/weapon.fbs"), "");
+
+        let without = IncludeGraph::new(std::slice::from_ref(&monster), &[]).unwrap();
+        assert_eq!(without.schemas, std::slice::from_ref(&monster));
+        assert!(without.includes.is_empty());
+
+        let with = IncludeGraph::new(std::slice::from_ref(&monster), &[dir.path().join("vendor")])
+            .unwrap();
+        assert_eq!(with.schemas, [monster, weapon]);
+        assert_eq!(with.includes, [(0, 1)]);
+    }
+}
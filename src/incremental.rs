@@ -0,0 +1,103 @@
+//! Regenerating only the code of the inputs whose schemas changed since the last compilation,
+//! directly or through one of the schemas they include, rather than the code of every input.
+
+use crate::{
+    generate_inputs, log, output, proto, schema::OutputLayout, stamp, BuilderOptions, Error, Flatc,
+    Report, Result,
+};
+use std::path::{Path, PathBuf};
+
+/// Inputs whose code has to be regenerated, with the code of every other input still current.
+pub(crate) struct Changes {
+    /// The stamp of the code in the output directory.
+    previous: stamp::Stamp,
+    /// The inputs whose code has to be regenerated.
+    inputs: Vec<PathBuf>,
+}
+
+impl Changes {
+    /// The inputs whose code has to be regenerated to bring the code in `output_dir` in line
+    /// with `fingerprint`, if that can be done without regenerating the code of every input.
+    /// That takes the code in there to have been generated with the same options, and each
+    /// changed input to still generate the same files, since the module root tying them together
+    /// only gets generated along with the code of every input.
+    pub fn find(
+        builder_options: &BuilderOptions,
+        layout: &OutputLayout,
+        fingerprint: &stamp::Fingerprint,
+        output_dir: &Path,
+    ) -> Option<Changes> {
+        // A manifest covers every input at once, as do the prelude and aliases next to the module
+        // root and the schemas translated from `.proto` files
+        if builder_options.generate_manifest
+            || ((builder_options.generate_prelude || builder_options.generate_aliases)
+                && !layout.one_file)
+            || builder_options.files.iter().any(|f| proto::is_proto(f))
+        {
+            return None;
+        }
+        let previous = stamp::Stamp::read(output_dir)?;
+        if previous.fingerprint.compiler != fingerprint.compiler
+            || previous.fingerprint.options != fingerprint.options
+        {
+            return None;
+        }
+        let inputs: Vec<_> = builder_options
+            .files
+            .iter()
+            .filter(|input| {
+                let key = stamp::input_key(input);
+                previous.fingerprint.inputs.get(&key) != fingerprint.inputs.get(&key)
+            })
+            .cloned()
+            .collect();
+        if inputs.is_empty() || inputs.len() == builder_options.files.len() {
+            return None;
+        }
+        for input in &inputs {
+            let generated_files = layout.generated_files_for(input).ok()?;
+            if previous.outputs.get(&stamp::input_key(input)) != Some(&generated_files) {
+                return None;
+            }
+        }
+        Some(Changes { previous, inputs })
+    }
+
+    /// Generates the code of the changed inputs into `staging_dir`, and copies the code of every
+    /// other input over from `output_dir`.
+    pub fn regenerate(
+        self,
+        builder_options: &BuilderOptions,
+        compiler: &Flatc,
+        layout: &OutputLayout,
+        output_dir: &Path,
+        staging_dir: &Path,
+        report: &mut Report,
+    ) -> Result {
+        log::step!("only regenerating the code of {:?}", self.inputs);
+        for file in self.previous.files.keys() {
+            output::copy_file(&output_dir.join(file), &staging_dir.join(file))?;
+        }
+
+        let changed_dir = tempfile::tempdir().map_err(Error::StagingDirectoryFailure)?;
+        let changed_options = BuilderOptions {
+            files: self.inputs,
+            ..builder_options.clone()
+        };
+        generate_inputs(
+            &changed_options,
+            compiler,
+            layout,
+            changed_dir.path(),
+            report,
+        )?;
+        for file in output::list_files(changed_dir.path())? {
+            // It would only cover the changed inputs, and the one of every input is still current
+            if !layout.one_file && file == Path::new(output::MOD_ROOT_FILE_NAME) {
+                continue;
+            }
+            output::copy_file(&changed_dir.path().join(&file), &staging_dir.join(&file))?;
+        }
+        Ok(())
+    }
+}
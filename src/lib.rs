@@ -1,4 +1,6 @@
 #![warn(clippy::all, clippy::pedantic, clippy::cargo)]
+// wasmtime pulls in its own versions of some widely used crates
+#![allow(clippy::multiple_crate_versions)]
 
 //! This crate provides a set of functions to facilitate compiling flatbuffers to Rust from within
 //! Rust. This is particularly helpful for use in `build.rs` scripts. Please note that for
@@ -6,6 +8,9 @@
 //! check what version that is against whatever version is installed on your system.That said, due
 //! to flatbuffers' versioning policy, it could be ok to mix patch and even minor versions.
 //!
+//! The minimum supported Rust version is 1.89, the first with the file locks used to keep
+//! concurrent builds from stepping on each other's output.
+//!
 //! ## Usage
 //!
 //! If you're not sure where to start, take a look at [`BuilderOptions`]. Please also look at the
@@ -67,6 +72,414 @@
 //! Note that since this will generate a symlink under `src/gen_flatbuffers`, you need to add this
 //! file to your gitignore as this symlink will dynamically change at runtime.
 //!
+//! Instead of the `#[allow(warnings)]` on the module, the builder can add attributes to the
+//! generated code itself: `add_root_attribute("#![allow(clippy::all)]")` adds one to the top of the
+//! generated `mod.rs`, and `add_file_attribute()` to the top of every generated file, which also
+//! keeps workspace-wide clippy runs quiet about the generated code. Likewise,
+//! `add_derive("my_game::sample::Vec*", "Hash")` adds a derive to every generated struct and enum
+//! matching the pattern, and `add_post_processing_hook()` runs a closure over every generated file
+//! for any other change.
+//!
+//! With `generate_prelude()`, the generated code also gets a `prelude` module re-exporting the
+//! generated types, so that `use gen_flatbuffers::prelude::*;` brings `Monster`, `MonsterArgs` and
+//! the rest into scope without spelling out the modules of their namespaces. Similarly,
+//! `generate_aliases()` adds an `aliases` module with a type alias for each root type, like
+//! `gen_flatbuffers::aliases::Monster<'a>`.
+//!
+//! For `no_std` crates, `make_no_std()` turns the paths into `std` in the generated code into paths
+//! into `core` and `alloc`, and `set_object_api_feature("alloc")` generates the object API, which
+//! allocates, but only compiles it with the `alloc` feature of the crate enabled.
+//!
+//! To keep the generated code out of the public API of your crate,
+//! `set_visibility(Visibility::Crate)` makes its modules and types `pub(crate)` rather than `pub`.
+//!
+//! Alternatively, `compile()` sets the `FLATBUFFERS_GENERATED_DIR` environment variable for the
+//! crate being built to the absolute path of the output directory, and publishes it to the build
+//! scripts of dependents as `DEP_<links>_GENERATED_DIR` for crates with a `links` key. Since the
+//! `mod.rs` generated by default declares its submodules as separate files, `include!`ing the
+//! generated code works best together with `gen_onefile()`:
+//!
+//! ```rust,ignore
+//! #[allow(warnings)]
+//! mod generated {
+//!     include!(concat!(env!("FLATBUFFERS_GENERATED_DIR"), "/example_generated.rs"));
+//! }
+//! ```
+//!
+//! ## Checking in generated code
+//!
+//! If you'd rather commit the generated code to your repository instead of relying on a symlink
+//! into `OUT_DIR`, you can generate straight into a directory under `src`:
+//! ```no_run
+//! use flatbuffers_build::BuilderOptions;
+//!
+//! BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+//!     .set_output_path("src/generated")
+//!     .compile()
+//!     .expect("flatbuffer compilation failed");
+//! ```
+//!
+//! Only files whose contents changed get rewritten, so this won't cause spurious diffs, and files
+//! that are no longer generated, e.g. after removing a schema, get deleted. Only files an earlier
+//! build generated count as such: anything else in the directory is left alone. To make sure the
+//! committed code doesn't drift from the schemas, you can run in check mode in CI, either by
+//! calling [`BuilderOptions::check_generated_code`] on the builder or by setting the
+//! `FLATBUFFERS_BUILD_CHECK=1` environment variable. In check mode nothing gets written, and the
+//! build fails if the committed code differs from what `flatc` would generate now.
+//!
+//! Next to the generated code, the output directory holds a `.flatbuffers-build-stamp.json` file
+//! recording what it was generated from, which lets later builds skip running `flatc` while nothing
+//! changed. When only some of the schemas changed, `flatc` only runs for the inputs that are, or
+//! include, one of them, unless that changes which types get generated, and with them `mod.rs`.
+//! It's fine to either commit it or ignore it, and check mode doesn't look at it.
+//!
+//! Calling `format_generated_code()` on the builder formats the generated code with `rustfmt`, the
+//! one set in the `RUSTFMT` environment variable or else the one in the `PATH`, which keeps diffs
+//! of the committed code readable and hooks checking its formatting happy.
+//!
+//! Contributors who don't have `flatc` installed can still build a crate with checked in code
+//! when its builder calls `reuse_output_without_flatc()`: as long as the stamp file is checked in
+//! too and says the code got generated from the same schemas and options, the code gets used as it
+//! is, with a warning, rather than failing the build for lack of `flatc`.
+//!
+//! ## Reproducible output
+//!
+//! The generated code only depends on the schemas, the builder options and the version of `flatc`,
+//! not on the machine, platform or directory it got generated on, so generating it on different CI
+//! runners gives the same files byte for byte, and comparing them only turns up actual drift. Since
+//! `flatc` writes `\r\n` line endings on Windows and can write out the paths it gets passed,
+//! generated code and JSON Schemas get `\n` line endings, and any paths into the directory of the
+//! crate or into the directory `flatc` generates into made relative to it. The paths in
+//! `manifest.json` are relative to the directory of the crate too, with `/` separators. Nothing
+//! generated carries timestamps, and everything this crate generates itself, like the prelude,
+//! comes in a fixed order.
+//!
+//! The `.flatbuffers-build-stamp.json` file recording what the code got generated from is the same
+//! on every machine too, as it identifies `flatc` by its version and records paths relative to the
+//! directory of the crate. Those paths use the separators of the platform though, so the stamp
+//! written on Windows differs from the one written elsewhere. Check mode leaves it out when
+//! comparing.
+//!
+//! ## Vendoring flatc
+//!
+//! If you don't want to depend on a `flatc` installed on the system, you can have this crate
+//! provide its own by enabling one of two features:
+//! - `vendored` downloads the flatbuffers source release and builds `flatc` from it with cmake.
+//!   This needs cmake and a C++ toolchain (MSVC works on Windows), and can take a few minutes.
+//! - `vendored-prebuilt` downloads the official `flatc` release binary for the host platform
+//!   instead. Release binaries are available for x86-64 Linux, macOS and Windows. Since upstream
+//!   doesn't publish checksums for them, set `FLATC_PREBUILT_SHA256` to have the download
+//!   verified.
+//!
+//! If both are enabled, the prebuilt binary is used.
+//!
+//! Enabling `vendored-fallback` alongside either feature first looks for a `flatc` on the system
+//! (through `FLATC_PATH` or the `PATH`), and only vendors one if it's missing or isn't the
+//! expected version. That keeps builds fast for anyone with the right `flatc` installed, while
+//! still working everywhere else. On its own, without `vendored` or `vendored-prebuilt`, it does
+//! nothing.
+//!
+//! Either way, the resulting binary is cached per user (under `~/.cache/flatbuffers-build` on
+//! Linux) keyed by `flatc` version and host, so it only gets vendored once per machine rather than
+//! once per crate. Set `FLATBUFFERS_BUILD_CACHE_DIR` to use a different directory, or set it to an
+//! empty string to disable the cache.
+//!
+//! Once vendored, `flatc` gets a quick self-test compiling a tiny schema, so a broken binary
+//! (built for the wrong architecture, missing shared libraries, ...) fails the build right away
+//! with a clear error rather than with confusing ones later on.
+//!
+//! If you already have the flatbuffers sources around, e.g. as a git submodule, point
+//! `FLATC_SOURCE_DIR` at them to build `flatc` from there instead. Nothing gets downloaded or
+//! verified in that case, and the resulting binary isn't cached.
+//!
+//! The build scripts of crates depending on `flatbuffers-build` can find the vendored binary and
+//! its version in the `DEP_FLATBUFFERS_BUILD_FLATC_PATH` and `DEP_FLATBUFFERS_BUILD_FLATC_VERSION`
+//! environment variables, e.g. to run it themselves.
+//!
+//! To vendor a different flatbuffers release than the one this crate supports, e.g. to match an
+//! older `flatbuffers` runtime, set `FLATC_VENDORED_VERSION` to its version. Source builds verify
+//! the tarball against a known checksum, which this crate currently only has for 24.3.25. For any
+//! other release, or when building from a custom source, set `FLATC_SOURCE_SHA256` to the checksum
+//! you expect. The build fails if that disagrees with the checksum this crate knows about. Keep in
+//! mind that older releases might not support every flag this crate passes to `flatc`.
+//!
+//! To build `flatc` from a specific commit instead of a release, e.g. one carrying your own
+//! patches, set `FLATC_SOURCE_REV` to its full commit SHA. The sources get downloaded from GitHub's
+//! archive of that commit, or from `FLATC_SOURCE_URL` if set, where `{rev}` gets replaced by the
+//! commit. Since there's no known checksum for arbitrary commits, set `FLATC_SOURCE_SHA256` to have
+//! the download verified. You'll also want to set `FLATC_VENDORED_VERSION` to the version that
+//! commit reports.
+//!
+//! To download from an internal mirror rather than GitHub, set `FLATC_SOURCE_URL` (for
+//! `vendored`) or `FLATC_PREBUILT_URL` (for `vendored-prebuilt`) to the URL to fetch. Any
+//! `{version}` in it gets replaced by the `flatc` version, and any `{asset}` by the name of the
+//! release binary archive for the host, e.g. `Linux.flatc.binary.g++-13.zip`.
+//!
+//! Downloads go through the proxy configured in the standard `HTTP_PROXY`, `HTTPS_PROXY`,
+//! `ALL_PROXY` and `NO_PROXY` environment variables. To use a proxy for these downloads only, set
+//! `FLATC_DOWNLOAD_PROXY` instead.
+//!
+//! Source builds use the Ninja generator when `ninja` is installed, which makes them noticeably
+//! faster than with the default Makefile generator. Set `CMAKE_GENERATOR` to pick a different one.
+//!
+//! Enabling `vendored-static` as well makes source builds link `flatc` statically on Linux, so the
+//! binary can be reused across machines with different glibc versions. If a musl toolchain is
+//! installed (e.g. `x86_64-linux-musl-g++`), it gets built against musl.
+//!
+//! When cross compiling, `vendored` builds `flatc` for the host rather than the target since it
+//! runs as part of the build. The generic `CC`, `CXX`, `CFLAGS`, `CXXFLAGS`, `AR` and
+//! `CMAKE_TOOLCHAIN_FILE` environment variables are ignored for it in that case, so use their
+//! `HOST_` prefixed variants (e.g. `HOST_CXX`) to configure the host toolchain.
+//!
+//! When vendoring from source without network access, download the flatbuffers source tarball
+//! ahead of time and point `FLATC_SOURCE_TARBALL` at it. It still gets checked against the expected
+//! checksum.
+//!
+//! ## Offline builds
+//!
+//! For air-gapped builds or `cargo vendor` setups, set `FLATBUFFERS_BUILD_OFFLINE=1` (or
+//! `CARGO_NET_OFFLINE=true`). The `vendored` features then fail instead of downloading anything,
+//! so `flatc` has to come from the cache, from `FLATC_SOURCE_TARBALL` or from `FLATC_SOURCE_DIR`.
+//! `FLATBUFFERS_BUILD_OFFLINE` also turns on `offline()` on every builder, which keeps compilation
+//! itself off the network too, e.g. by never pulling the image when running `flatc` in a container.
+//!
+//! ## Compiling many schema groups
+//!
+//! Build scripts compiling several independent groups of schemas, each with its own builder and
+//! output directory, can hand all of them to `flatbuffers_build::compile_all()`, which compiles
+//! them in parallel rather than one after the other. It runs as many compilations at once as cargo
+//! runs jobs, unless `FLATBUFFERS_BUILD_JOBS` says otherwise, and `compile_all_with_jobs()` takes
+//! the limit as an argument instead. Within a single compilation, the binary and JSON schemas get
+//! generated alongside the Rust code too, and the generated files get compared against and copied
+//! into the output directory on that many threads.
+//!
+//! ## Caching generated code
+//!
+//! Compiling the same schemas again after switching branches, running `cargo clean` or building in
+//! a fresh target directory normally means running `flatc` again. Setting
+//! `FLATBUFFERS_BUILD_OUTPUT_CACHE_DIR` (e.g. to `~/.cache/flatbuffers-build`), or calling
+//! `set_output_cache_dir()` on the builder, keeps a copy of all generated code in that directory,
+//! keyed by a hash of the schemas, the options and the version of `flatc`. Whenever a compilation
+//! matches an entry, the code gets restored from there with nothing more than asking `flatc` for
+//! its version. Nothing ever gets evicted from the cache, so delete it once it grows too large. The
+//! cache isn't used when running `flatc` in a container, since finding out which `flatc` an image
+//! has means starting a container.
+//!
+//! By default any change to a schema counts, even to a comment. Calling
+//! `ignore_schema_formatting()` on the builder, or listing it among the `options` in `Cargo.toml`,
+//! leaves out comments and formatting, so that reformatting the schemas neither invalidates the
+//! cache nor has `flatc` run again. Documentation comments (`///`) still count, since they end up
+//! in the generated code.
+//!
+//! ## Remote caching
+//!
+//! With the `remote-cache` feature enabled, the cached code can also be shared between machines,
+//! e.g. CI runners, by setting `FLATBUFFERS_BUILD_REMOTE_CACHE` or calling `set_remote_cache()` on
+//! the builder. Before running `flatc`, the code gets pulled from there, and freshly generated code
+//! gets pushed back, keyed by the same hash as the local cache. The remote cache can be:
+//!
+//! - An `http://` or `https://` URL of a server storing whatever gets `PUT` under it and serving it
+//!   back on `GET`. `FLATBUFFERS_BUILD_REMOTE_CACHE_TOKEN` gets sent as a bearer token, if set.
+//! - An `s3://bucket/prefix` URL of an S3-compatible bucket. Requests get signed with the
+//!   credentials in the usual `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`
+//!   variables, for the region in `AWS_REGION`. Set `AWS_ENDPOINT_URL` to use something other than
+//!   AWS, like `MinIO` or R2.
+//!
+//! Setting `FLATBUFFERS_BUILD_REMOTE_CACHE_READ_ONLY=1` only ever pulls from the cache, which is
+//! handy for builds that shouldn't be trusted to push to it. Failing to reach the cache only ever
+//! results in a warning, and the cache isn't used at all in offline mode.
+//!
+//! ## Running flatc in a container
+//!
+//! If running `flatc` directly on the build host isn't an option,
+//! [`BuilderOptions::set_container_image`] runs it inside a container image instead, with docker or
+//! whatever `FLATC_CONTAINER_ENGINE` is set to (e.g. `podman`). The image needs `flatc` on its
+//! `PATH`. The current directory and the directories of the schemas and output get mounted at the
+//! same paths inside the container, so make sure any included schemas live under one of those.
+//!
+//! ## Running flatc through WASI
+//!
+//! With the `wasi` feature enabled, `flatc` can also be a module compiled to WASI rather than a
+//! native binary. It then runs through an embedded [wasmtime](https://wasmtime.dev) runtime, which
+//! produces identical output on Linux, macOS and Windows and doesn't need cmake or a native
+//! `flatc` at all. This crate doesn't ship such a module, so you'll need to build `flatc` for
+//! `wasm32-wasi` yourself, then point the builder at it with `set_wasm_compiler()`, or set the
+//! `FLATC_WASM` environment variable to its path.
+//!
+//! ## Configuring through Cargo.toml
+//!
+//! Instead of listing everything in `build.rs`, the schemas and builder options can live in a
+//! `[package.metadata.flatbuffers]` table of your `Cargo.toml`:
+//!
+//! ```toml
+//! [package.metadata.flatbuffers]
+//! schemas = ["schemas/weapon.fbs", "schemas/example.fbs"]
+//! include-dirs = ["schemas/common"]
+//! symlink-directory = "src/gen_flatbuffers"
+//! options = ["gen-onefile", "warnings-as-errors"]
+//! ```
+//!
+//! Your build script then boils down to
+//! `BuilderOptions::from_cargo_metadata()?.compile()`. Paths are relative to the manifest, schema
+//! entries can be glob patterns, and `options` takes the names of the builder's flag methods in
+//! kebab-case. The returned builder can still be tweaked further before compiling.
+//!
+//! With several packages in a workspace configured this way, a single call to
+//! `flatbuffers_build::compile_workspace()` compiles the schemas of all of them, letting each
+//! package include the schemas of the others.
+//!
+//! ## Without a build script
+//!
+//! Small crates can skip the build script altogether with the `include_fbs!` macro of the
+//! `flatbuffers-build-macros` crate, which runs `flatc` while the crate compiles and expands to the
+//! generated code, along with that of every schema it includes:
+//!
+//! ```rust,ignore
+//! mod monster {
+//!     flatbuffers_build_macros::include_fbs!("schemas/monster.fbs");
+//! }
+//!
+//! use monster::my_game::sample::Monster;
+//! ```
+//!
+//! The path is relative to the directory of the crate. `flatc` gets found the same way as with a
+//! build script, and only runs again once one of the schemas changed, which is also when the crate
+//! gets recompiled. Give each schema a module of its own, as expanding two of them side by side
+//! would declare the same imports twice.
+//!
+//! To have editors like rust-analyzer see the generated code, bind a module to the schema with the
+//! `flatbuffers_schema` attribute instead. It fills the module with the generated code itself
+//! rather than an `include!` of it, keeping any items already in there, and re-exports the
+//! generated types at the top of the module:
+//!
+//! ```rust,ignore
+//! #[flatbuffers_build_macros::flatbuffers_schema("schemas/monster.fbs")]
+//! mod monster {}
+//!
+//! use monster::Monster;
+//! ```
+//!
+//! Types declared under the same name in more than one namespace aren't re-exported, but are still
+//! there in the modules of their namespaces.
+//!
+//! ## Command line
+//!
+//! Code generation can also run outside of cargo, for other build systems or consumers of the
+//! schemas in other languages, with the `cargo flatbuffers` command of the `cargo-flatbuffers`
+//! crate. It reads the configuration from a `flatbuffers-build.toml` holding the keys of the
+//! `[package.metadata.flatbuffers]` table at its top level, with paths relative to it:
+//!
+//! ```toml
+//! schemas = ["schemas/*.fbs"]
+//! output-path = "src/generated"
+//! options = ["gen-onefile"]
+//! ```
+//!
+//! ```bash
+//! cargo install cargo-flatbuffers
+//! cargo flatbuffers compile --config flatbuffers-build.toml
+//! ```
+//!
+//! Pointing `--config` to a `Cargo.toml` reads its `[package.metadata.flatbuffers]` table instead,
+//! and `--output-path` overrides where the code goes. Warnings get printed to stderr, and
+//! `--verbose` prints every step along with every `flatc` invocation.
+//!
+//! `cargo flatbuffers check` generates the code into a temporary directory instead, and compares it
+//! against the code in the output path, or with only a `symlink-directory` configured, the code the
+//! symlink points at. It prints a diff of every file that drifted from the schemas and exits with
+//! an error if there's any, which makes for a CI gate in repositories committing their generated
+//! code.
+//!
+//! While editing schemas, `cargo flatbuffers watch` compiles them again every time one of them, or
+//! the configuration, changes, printing the errors `flatc` reports along the way.
+//!
+//! `cargo flatbuffers graph` prints which of the configured schemas include which, as Graphviz DOT
+//! or with `--format mermaid` as a Mermaid flowchart. Each schema is numbered with its place in the
+//! order they compile in, and schemas including each other are highlighted in red, along with a
+//! warning for each such cycle. The same graph is available to build scripts and other tools
+//! through `BuilderOptions::include_graph()`.
+//!
+//! `cargo flatbuffers lint` checks the configured schemas against conventions `flatc` doesn't
+//! enforce: a `file_identifier` next to every `root_type`, an explicit `id` on every table field,
+//! and names following the flatbuffers style guide. It prints each problem pointing at the line
+//! it's on and fails if there's any, so it can run as a pre-commit hook. Rules can be turned off
+//! with `--allow`, e.g. `--allow implicit-field-id`, and build scripts can run the same checks
+//! through `BuilderOptions::lint_schemas()`.
+//!
+//! To set a new package up, run `cargo flatbuffers init` from its root. It creates a `schemas`
+//! directory with an example schema, a `build.rs` compiling it into a symlink at
+//! `src/gen_flatbuffers`, the gitignore entry for that symlink, and the `flatbuffers` and
+//! `flatbuffers-build` dependencies in `Cargo.toml`, pinned to matching versions. Files that
+//! already exist are left alone.
+//!
+//! Installing `cargo-flatbuffers` with the `vendored` or `vendored-prebuilt` feature vendors
+//! `flatc` into the shared cache described under "Vendoring flatc", and `cargo flatbuffers vendor`
+//! then prints its path. That warms up CI images ahead of any build, and lets air-gapped
+//! environments stage the binary and point `FLATC_PATH` at it. Build scripts and other tools can
+//! find the same binary through `vendored_flatc()`.
+//!
+//! ```bash
+//! cargo install cargo-flatbuffers --features vendored-prebuilt
+//! export FLATC_PATH=$(cargo flatbuffers vendor)
+//! ```
+//!
+//! `cargo flatbuffers json2bin` and `cargo flatbuffers bin2json` convert payload files between JSON
+//! and binary with the configured schemas, e.g. to decode a captured buffer. They take the root
+//! type from `--root-type`, the configured `root-type` or the `root_type` the schemas declare, in
+//! that order, and write the converted files to the current directory or to `--output-path`. Build
+//! scripts and other tools can set up the same conversions through `BuilderOptions::converter()`.
+//!
+//! ```bash
+//! cargo flatbuffers bin2json captured/monster.bin --output-path decoded
+//! ```
+//!
+//! ## Sharing schemas between crates
+//!
+//! A crate can publish its schemas to the build scripts of the crates depending on it, so that they
+//! can include them without relying on relative paths into another crate. Give the schema crate a
+//! `links` key in its `Cargo.toml` and call `export_schemas()` from its build script:
+//!
+//! ```toml
+//! [package]
+//! name = "my-schemas"
+//! links = "my-schemas"
+//! ```
+//!
+//! ```rust,no_run
+//! flatbuffers_build::export_schemas(["schemas"]).expect("failed to export schemas");
+//! ```
+//!
+//! The build scripts of its dependents then find the absolute paths of the exported directories in
+//! `DEP_MY_SCHEMAS_SCHEMA_DIR`, which is what `add_dependency_schemas()` reads to add them as
+//! include paths:
+//!
+//! ```rust,no_run
+//! # use flatbuffers_build::BuilderOptions;
+//! BuilderOptions::new_with_files(["schemas/service.fbs"])
+//!     .add_dependency_schemas("my-schemas")
+//!     .compile()
+//!     .expect("flatbuffer compilation failed");
+//! ```
+//!
+//! ## Logging through tracing
+//!
+//! Warnings, verbose output and timings get printed as cargo warnings by default, since that's the
+//! only way for a build script to show them. If you're compiling schemas outside of a build script,
+//! e.g. in a CLI or a server generating code at runtime, enable the `tracing` feature to have them
+//! emitted as [`tracing`](https://docs.rs/tracing) events instead, along with debug events for each
+//! step of the compilation. They then go through whatever subscriber you have set up.
+//!
+//! ## Building on docs.rs
+//!
+//! docs.rs builds have no `flatc` available (nor network access to vendor one), so when the
+//! `DOCS_RS` environment variable is set, [`BuilderOptions::compile`] skips running `flatc` and
+//! writes a stub `mod.rs` with no items in it instead, and the `vendored` features don't vendor
+//! anything. This keeps the documentation of crates including the generated code building, as long
+//! as any code referring to the generated types is gated behind `#[cfg(not(docsrs))]` or similar.
+//! Other environment variables can trigger the same behaviour through [`BuilderOptions::skip_if`].
+//! If the output directory already holds generated code, e.g. code committed to the repository and
+//! generated into `src/generated`, that code is kept as is rather than replaced by the stub.
+//!
 //! ## On file ordering
 //!
 //! Unfortunately due to a quirk in the `flatc` compiler the order you provide the `fbs` files does
@@ -75,30 +488,125 @@
 //! `weapon.fbs` and `example.fbs`. Since the latter has an `include` directive for `weapon.fbs`,
 //! it should go after in the list. If you were to put `example.fbs` _before_ `weapon.fbs`, you'd
 //! end up only being able to import the contents of `weapon.fbs` and with compilation errors if
-//! you tried to use any other components.
+//! you tried to use any other components. To save you the head scratching, compilation fails
+//! early with [`Error::IncorrectFileOrdering`] when a schema is listed before one it includes,
+//! suggesting an order that works. Schemas including each other have no such order, so they can be
+//! listed in any order.
+//!
+//! Alternatively, you can sidestep the issue entirely by only listing your root schema and calling
+//! [`BuilderOptions::gen_all`], which also generates code for every schema it includes.
 
 use std::{
+    collections::HashSet,
     ffi::{OsStr, OsString},
+    io::Read,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Output, Stdio},
+    sync::Arc,
+    thread::JoinHandle,
+    time::{Duration, Instant},
 };
 
+mod aliases;
+mod cache;
+mod conform;
+mod container;
+mod convert;
+mod dependency;
+mod depfile;
+mod diagnostic;
+mod directives;
+mod flatc;
+mod graph;
+mod incremental;
+mod lint;
+mod log;
+mod manifest;
+mod metadata;
+mod output;
+mod parallel;
+mod plan;
+mod postprocess;
+mod prelude;
+mod proto;
+#[cfg(feature = "remote-cache")]
+mod remote_cache;
+mod report;
+mod schema;
+mod stamp;
+#[cfg(feature = "wasi")]
+mod wasi;
+mod workspace;
+
+pub use conform::{check_conformance, check_conformance_with_includes};
+pub use convert::Converter;
+pub use dependency::export_schemas;
+pub use diagnostic::{Diagnostic, Severity};
+pub use graph::IncludeGraph;
+pub use lint::{Lint, LintRule};
+pub use parallel::{compile_all, compile_all_with_jobs};
+pub use plan::{CompilationPlan, PlannedCommand};
+pub use postprocess::Visibility;
+pub use proto::convert_proto;
+pub use report::Counters;
+pub use workspace::{compile_workspace, compile_workspace_with_manifest};
+
+use directives::Directives;
+use flatc::{Backend, Flatc};
+use report::{Phases, Report};
+
 const FLATC_VERSION_PREFIX: &str = "flatc version ";
 const FLATC_BUILD_PATH: Option<&str> = option_env!("FLATC_PATH");
+const FLATC_VENDORED_VERSION: Option<&str> = option_env!("FLATC_VENDORED_VERSION");
+#[cfg(feature = "wasi")]
+const WASM_ENV_VAR: &str = "FLATC_WASM";
+const CHECK_ENV_VAR: &str = "FLATBUFFERS_BUILD_CHECK";
+const DOCS_RS_ENV_VAR: &str = "DOCS_RS";
+const GENERATED_DIR_ENV_VAR: &str = "FLATBUFFERS_GENERATED_DIR";
+const GENERATED_DIR_METADATA_KEY: &str = "generated_dir";
+const GEN_ONEFILE_FLAG: &str = "--gen-onefile";
+const GEN_ALL_FLAG: &str = "--gen-all";
+const WARNINGS_AS_ERRORS_FLAG: &str = "--warnings-as-errors";
+const OBJECT_API_FLAG: &str = "--gen-object-api";
+const FLATC_WARNING_MARKER: &str = "warning:";
+/// How often to check whether a `flatc` invocation with a timeout has finished.
+const TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 /// Version of `flatc` supported by this library. Make sure this matches exactly with the `flatc`
 /// binary you're using and the version of the `flatbuffers` rust library.
 pub const SUPPORTED_FLATC_VERSION: &str = "24.3.25";
 
+/// The `flatc` vendored when building this crate with one of the `vendored` features, along with its
+/// version. That's the binary compilations run unless told otherwise, and the one the build scripts
+/// of dependent crates find in `DEP_FLATBUFFERS_BUILD_FLATC_PATH`. Returns `None` when building
+/// without vendoring.
+///
+/// ```no_run
+/// if let Some((path, version)) = flatbuffers_build::vendored_flatc() {
+///     println!("flatc {version} vendored at {}", path.display());
+/// }
+/// ```
+#[must_use]
+pub fn vendored_flatc() -> Option<(&'static Path, &'static str)> {
+    Some((Path::new(FLATC_BUILD_PATH?), FLATC_VENDORED_VERSION?))
+}
+
 /// Primary error type returned when you compile your flatbuffer specifications to Rust.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     /// Returned when `flatc` returns with an non-zero status code for a reason not covered
     /// elsewhere in this enum.
-    #[error("flatc exited unexpectedly with status code {status_code:?}\n-- stdout:\n{stdout}\n-- stderr:\n{stderr}\n")]
+    #[error(
+        "flatc exited unexpectedly with status code {status_code:?}\n{}",
+        flatc_error_details(.diagnostics, .stdout, .stderr)
+    )]
     FlatcErrorCode {
         /// Status code returned by `flatc` (none if program was terminated by a signal).
         status_code: Option<i32>,
+        /// Errors and warnings parsed out of the output of `flatc`, with the location in the
+        /// schemas they refer to. Empty if `flatc` didn't print anything we could recognise, in
+        /// which case the raw output is still available in `stdout` and `stderr`.
+        diagnostics: Vec<Diagnostic>,
         /// Standard output stream contents of the program
         stdout: String,
         /// Standard error stream contents of the program
@@ -116,6 +624,10 @@ pub enum Error {
     /// flatc does not exist.
     #[error("flatc failed to spawn: {0}")]
     FlatcSpawnFailure(#[source] std::io::Error),
+    /// Returned when a `flatc` invocation runs for longer than the timeout set with
+    /// [`BuilderOptions::set_timeout`], after killing it.
+    #[error("flatc did not finish within {0:?} and was killed")]
+    Timeout(Duration),
     /// Returned if you failed to set either the output path or the `OUT_DIR` environment variable.
     #[error(
         "output directory was not set. Either call .set_output_path() or set the `OUT_DIR` env var"
@@ -126,6 +638,185 @@ pub enum Error {
     /// errors.
     #[error("failed to create symlink path requested: {0}")]
     SymlinkCreationFailure(#[source] std::io::Error),
+    /// Returned if we fail to create the temporary directory `flatc` writes into before the
+    /// generated files are copied over to the output directory.
+    #[error("failed to create staging directory for flatc output: {0}")]
+    StagingDirectoryFailure(#[source] std::io::Error),
+    /// Returned when reading or writing one of the generated files fails while copying them into
+    /// the output directory.
+    #[error("failed to write generated file {}: {source}", .path.display())]
+    OutputWriteFailure {
+        /// Path of the file or directory we failed to access.
+        path: PathBuf,
+        /// Underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+    /// Returned in check mode when the code in the output directory does not match what `flatc`
+    /// generates from the current schemas. See [`BuilderOptions::check_generated_code`].
+    #[error(
+        "generated code in {} is out of date. Differing files: {}. Re-run without check mode to regenerate it",
+        .output_path.display(),
+        .files.iter().map(|f| f.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    OutdatedGeneratedCode {
+        /// The output directory that was checked.
+        output_path: PathBuf,
+        /// Files, relative to the output directory, that differ from the freshly generated code.
+        /// This includes files that are missing and files that are no longer generated.
+        files: Vec<PathBuf>,
+    },
+    /// Returned when `flatc` printed warnings while [`BuilderOptions::warnings_as_errors`] is set.
+    #[error("flatc reported warnings, which are treated as errors:\n{}", .0.join("\n"))]
+    FlatcWarnings(Vec<String>),
+    /// Returned when the schemas are not a valid evolution of the baseline schema they were
+    /// checked against. See [`check_conformance`] and [`BuilderOptions::check_conformance`].
+    #[error(
+        "schemas do not conform to baseline schema {}:\n{}",
+        .baseline.display(),
+        .violations.join("\n")
+    )]
+    ConformanceViolation {
+        /// The baseline schema that was checked against.
+        baseline: PathBuf,
+        /// Each of the violations reported by `flatc`.
+        violations: Vec<String>,
+    },
+    /// Returned when one of the input schema files can't be read while gathering information
+    /// about it, such as when generating the manifest.
+    #[error("failed to read schema file {}: {source}", .path.display())]
+    SchemaReadFailure {
+        /// Path of the schema we failed to read.
+        path: PathBuf,
+        /// Underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+    /// Returned when one of the files passed to [`BuilderOptions::new_with_files`] doesn't exist.
+    #[error("input file {} does not exist", .0.display())]
+    MissingInputFile(PathBuf),
+    /// Returned when one of the files passed to [`BuilderOptions::new_with_files`] is neither a
+    /// flatbuffer schema (`.fbs`) nor a protobuf schema (`.proto`).
+    #[error("input file {} is not a .fbs or .proto schema", .0.display())]
+    InvalidInputExtension(PathBuf),
+    /// Returned when a schema is passed to [`BuilderOptions::new_with_files`] before a schema it
+    /// includes, which would leave the generated `mod.rs` unusable. See the section on file
+    /// ordering in the crate documentation.
+    #[error(
+        "{} includes {}, so it must be listed after it. Try this order instead: {}",
+        .file.display(),
+        .include.display(),
+        .suggested_order.iter().map(|f| f.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    IncorrectFileOrdering {
+        /// The schema listed too early.
+        file: PathBuf,
+        /// The schema it includes, listed after it.
+        include: PathBuf,
+        /// All the input files, reordered so that every schema comes after the ones it includes.
+        suggested_order: Vec<PathBuf>,
+    },
+    /// Returned by [`BuilderOptions::from_cargo_metadata`] when `CARGO_MANIFEST_DIR` isn't set,
+    /// which usually means we're not running from a build script.
+    #[error("CARGO_MANIFEST_DIR is not set. Use BuilderOptions::from_cargo_manifest() outside of build scripts")]
+    ManifestDirNotSet,
+    /// Returned when the `Cargo.toml` to read flatbuffers configuration from can't be read.
+    #[error("failed to read manifest {}: {source}", .path.display())]
+    ManifestReadFailure {
+        /// Path of the manifest we failed to read.
+        path: PathBuf,
+        /// Underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+    /// Returned when the file to read flatbuffers configuration from, passed to
+    /// [`BuilderOptions::from_config_file`], can't be read.
+    #[error("failed to read configuration file {}: {source}", .path.display())]
+    ConfigReadFailure {
+        /// Path of the configuration file we failed to read.
+        path: PathBuf,
+        /// Underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+    /// Returned when a `Cargo.toml` has no `[package.metadata.flatbuffers]` table to read the
+    /// configuration from.
+    #[error("manifest {} has no [package.metadata.flatbuffers] table", .0.display())]
+    MissingCargoMetadata(PathBuf),
+    /// Returned when the `[package.metadata.flatbuffers]` table of a `Cargo.toml`, or a
+    /// configuration file, is invalid, e.g. because of an unknown option or a schema pattern that
+    /// matches no files.
+    #[error("invalid flatbuffers configuration in {}: {reason}", .path.display())]
+    InvalidCargoMetadata {
+        /// Path of the manifest holding the configuration.
+        path: PathBuf,
+        /// What's wrong with the configuration.
+        reason: String,
+    },
+    /// Returned when the same file is passed to [`BuilderOptions::new_with_files`] more than once,
+    /// possibly through different paths.
+    #[error("input file {} is listed more than once", .0.display())]
+    DuplicateInput(PathBuf),
+    /// Returned by [`compile_workspace`] when running `cargo metadata` to find the packages in the
+    /// workspace fails.
+    #[error("failed to read workspace metadata: {0}")]
+    CargoMetadataFailure(String),
+    /// Returned by [`export_schemas`] when one of the directories to export isn't usable.
+    #[error("failed to export schema directory {}: {source}", .path.display())]
+    SchemaExportFailure {
+        /// The directory we failed to export.
+        path: PathBuf,
+        /// Underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+    /// Returned when a dependency passed to [`BuilderOptions::add_dependency_schemas`] didn't
+    /// export any schemas, either because it doesn't call [`export_schemas`] or because the crate
+    /// being built doesn't depend on it.
+    #[error("dependency {dependency} exported no schemas ({env_var} is not set)")]
+    MissingDependencySchemas {
+        /// The `links` name of the dependency.
+        dependency: String,
+        /// The environment variable the schema directories should have been in.
+        env_var: String,
+    },
+    /// Returned when a pattern passed to [`BuilderOptions::add_derive`] isn't valid.
+    #[error("invalid type pattern {pattern}: {reason}")]
+    InvalidTypePattern {
+        /// The invalid pattern.
+        pattern: String,
+        /// What's wrong with it.
+        reason: String,
+    },
+    /// Returned by [`BuilderOptions::converter`] when there's no root type to convert data with,
+    /// as none was set with [`BuilderOptions::set_root_type`] and none of the schemas declare one.
+    #[error("no root type to convert data with, set one or declare it with root_type in a schema")]
+    MissingRootType,
+    /// Returned by [`BuilderOptions::converter`] when the schemas declare more than one root type
+    /// and none was picked with [`BuilderOptions::set_root_type`].
+    #[error("schemas declare more than one root type ({}), set the one to convert data with", .0.join(", "))]
+    AmbiguousRootType(Vec<String>),
+    /// Returned by [`BuilderOptions::converter`] when the root type set with
+    /// [`BuilderOptions::set_root_type`] isn't a table declared in any of the schemas.
+    #[error("root type {0} isn't a table declared in any of the schemas")]
+    UnknownRootType(String),
+    /// Returned if we fail to spawn `rustfmt` to format the generated code with. Usually means
+    /// it isn't installed. See [`BuilderOptions::format_generated_code`].
+    #[error("rustfmt failed to spawn: {0}")]
+    RustfmtSpawnFailure(#[source] std::io::Error),
+    /// Returned when `rustfmt` fails to format one of the generated files.
+    #[error(
+        "rustfmt failed to format {} with status code {status_code:?}:\n{stderr}",
+        .path.display()
+    )]
+    RustfmtErrorCode {
+        /// The generated file, relative to the output directory.
+        path: PathBuf,
+        /// Status code returned by `rustfmt` (none if it was terminated by a signal).
+        status_code: Option<i32>,
+        /// Standard error stream contents of `rustfmt`.
+        stderr: String,
+    },
 }
 
 /// Alias for a Result that uses [`Error`] as the default error type.
@@ -153,12 +844,52 @@ pub type Result<T = (), E = Error> = std::result::Result<T, E>;
 ///
 /// Consult the functions bellow for more details.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct BuilderOptions {
     files: Vec<PathBuf>,
-    compiler: Option<String>,
+    compiler: Option<Backend>,
     output_path: Option<PathBuf>,
     symlink_path: Option<PathBuf>,
-    supress_buildrs_directives: bool,
+    shared_output: bool,
+    reuse_output_without_flatc: bool,
+    ignore_schema_formatting: bool,
+    output_cache_dir: Option<PathBuf>,
+    #[cfg(feature = "remote-cache")]
+    remote_cache: Option<String>,
+    directives: Directives,
+    verbose: bool,
+    offline: bool,
+    timeout: Option<Duration>,
+    report_path: Option<PathBuf>,
+    depfile_path: Option<PathBuf>,
+    print_timings: bool,
+    print_counters: bool,
+    generate_manifest: bool,
+    generate_prelude: bool,
+    generate_aliases: bool,
+    check_generated_code: bool,
+    generate_binary_schemas: bool,
+    generate_json_schemas: bool,
+    format_generated_code: bool,
+    root_attributes: Vec<String>,
+    file_attributes: Vec<String>,
+    derives: Vec<(String, String)>,
+    no_std: bool,
+    object_api_feature: Option<String>,
+    visibility: Visibility,
+    post_processing_hooks: Vec<postprocess::Hook>,
+    flatc_flags: Vec<&'static str>,
+    binary_schema_flags: Vec<&'static str>,
+    filename_suffix: Option<String>,
+    filename_extension: Option<String>,
+    include_prefix: Option<PathBuf>,
+    root_type: Option<String>,
+    conform_baseline: Option<PathBuf>,
+    conform_include_paths: Vec<PathBuf>,
+    include_paths: Vec<PathBuf>,
+    dependency_schemas: Vec<String>,
+    skip_env_vars: Vec<String>,
+    rerun_paths: Vec<PathBuf>,
 }
 
 impl BuilderOptions {
@@ -176,8 +907,9 @@ impl BuilderOptions {
     ///
     /// # Arguments
     /// * `files` - An iterable of files that should be compiled into rust code. No glob resolution
-    ///             happens here, and all paths MUST match to real files, either as absolute paths
-    ///             or relative to the current working directory.
+    ///   happens here, and all paths MUST match to real files, either as absolute paths or
+    ///   relative to the current working directory. Protobuf (`.proto`) files are also accepted,
+    ///   and get translated into flatbuffer schemas before compiling.
     #[must_use]
     pub fn new_with_files<P: AsRef<Path>, I: IntoIterator<Item = P>>(files: I) -> Self {
         BuilderOptions {
@@ -185,21 +917,169 @@ impl BuilderOptions {
             compiler: None,
             output_path: None,
             symlink_path: None,
-            supress_buildrs_directives: false,
+            shared_output: false,
+            reuse_output_without_flatc: false,
+            ignore_schema_formatting: false,
+            output_cache_dir: None,
+            #[cfg(feature = "remote-cache")]
+            remote_cache: None,
+            directives: Directives::default(),
+            verbose: false,
+            offline: false,
+            timeout: None,
+            report_path: None,
+            depfile_path: None,
+            print_timings: false,
+            print_counters: false,
+            generate_manifest: false,
+            generate_prelude: false,
+            generate_aliases: false,
+            check_generated_code: false,
+            generate_binary_schemas: false,
+            generate_json_schemas: false,
+            format_generated_code: false,
+            root_attributes: Vec::new(),
+            file_attributes: Vec::new(),
+            derives: Vec::new(),
+            no_std: false,
+            object_api_feature: None,
+            visibility: Visibility::Public,
+            post_processing_hooks: Vec::new(),
+            flatc_flags: Vec::new(),
+            binary_schema_flags: Vec::new(),
+            filename_suffix: None,
+            filename_extension: None,
+            include_prefix: None,
+            root_type: None,
+            conform_baseline: None,
+            conform_include_paths: Vec::new(),
+            include_paths: Vec::new(),
+            dependency_schemas: Vec::new(),
+            skip_env_vars: vec![DOCS_RS_ENV_VAR.into()],
+            rerun_paths: Vec::new(),
         }
     }
 
+    /// Create a builder configured by the `[package.metadata.flatbuffers]` table in the
+    /// `Cargo.toml` of the package being built. This keeps the list of schemas next to the rest of
+    /// the package definition:
+    ///
+    /// ```toml
+    /// [package.metadata.flatbuffers]
+    /// schemas = ["schemas/weapon.fbs", "schemas/example.fbs"]
+    /// include-dirs = ["schemas/common"]
+    /// symlink-directory = "src/gen_flatbuffers"
+    /// options = ["gen-onefile", "warnings-as-errors"]
+    /// ```
+    ///
+    /// Entries in `schemas` can also be glob patterns like `"schemas/*.fbs"`, whose matches get
+    /// compiled in alphabetical order. Besides `include-dirs` and `symlink-directory`, the table
+    /// accepts `output-path`, `root-type`, `filename-suffix`, `filename-extension` and
+    /// `include-prefix`, which map to the builder functions of the same name. `dependency-schemas`
+    /// lists dependencies to call [`Self::add_dependency_schemas`] with, and `options` turns on any
+    /// of the builder functions without arguments, named in kebab-case. All paths are relative to
+    /// the directory of `Cargo.toml`. The returned builder can still be customised further before
+    /// compiling:
+    ///
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// BuilderOptions::from_cargo_metadata()
+    ///     .expect("invalid flatbuffers configuration")
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// ```
+    ///
+    /// # Errors
+    /// Fails if not running from a build script, or if the `Cargo.toml` of the package can't be
+    /// read, has no `[package.metadata.flatbuffers]` table, or has an invalid one.
+    pub fn from_cargo_metadata() -> Result<Self> {
+        let manifest_dir =
+            std::env::var_os("CARGO_MANIFEST_DIR").ok_or(Error::ManifestDirNotSet)?;
+        Self::from_cargo_manifest(Path::new(&manifest_dir).join(metadata::MANIFEST_FILE_NAME))
+    }
+
+    /// Same as [`Self::from_cargo_metadata`], but reading the configuration from the
+    /// `Cargo.toml` at `manifest_path` rather than the one of the package being built.
+    ///
+    /// # Arguments
+    /// * `manifest_path` - Path of the `Cargo.toml` to read the configuration from.
+    ///
+    /// # Errors
+    /// Fails if the manifest can't be read, has no `[package.metadata.flatbuffers]` table, or has
+    /// an invalid one.
+    pub fn from_cargo_manifest<P: AsRef<Path>>(manifest_path: P) -> Result<Self> {
+        let manifest_path = manifest_path.as_ref();
+        let config = metadata::read_config(manifest_path)?
+            .ok_or_else(|| Error::MissingCargoMetadata(manifest_path.into()))?;
+        metadata::builder_from_config(manifest_path, &config)
+    }
+
+    /// Same as [`Self::from_cargo_manifest`], but reading the configuration from a file of its
+    /// own, which holds the keys of the `[package.metadata.flatbuffers]` table at its top level.
+    /// This is what the `cargo flatbuffers` command reads, so that the same configuration can
+    /// drive code generation outside of cargo:
+    ///
+    /// ```toml
+    /// schemas = ["schemas/weapon.fbs", "schemas/example.fbs"]
+    /// output-path = "generated"
+    /// options = ["gen-onefile"]
+    /// ```
+    ///
+    /// # Arguments
+    /// * `config_path` - Path of the configuration file. Paths in it are relative to its
+    ///   directory.
+    ///
+    /// # Errors
+    /// Fails if the file can't be read, or holds an invalid configuration.
+    pub fn from_config_file<P: AsRef<Path>>(config_path: P) -> Result<Self> {
+        let config_path = config_path.as_ref();
+        let config = metadata::read_config_file(config_path)?;
+        metadata::builder_from_config(config_path, &config)
+    }
+
     /// Set the path of the `flatc` binary to use as a compiler. If no such path is provided, we
     /// will default to first using whatever's set in the `FLATC_PATH` environment variable, or if
     /// that's not set, we will let the system resolve using standard `PATH` resolution.
     ///
     /// # Arguments
     /// * `compiler` - Path to the compiler to run. This can also be a name that we should resolve
-    ///                using standard `PATH` resolution.
+    ///   using standard `PATH` resolution.
     #[must_use]
     pub fn set_compiler<S: AsRef<str>>(self, compiler: S) -> Self {
         BuilderOptions {
-            compiler: Some(compiler.as_ref().into()),
+            compiler: Some(Backend::Native(compiler.as_ref().into())),
+            ..self
+        }
+    }
+
+    /// Run `flatc` inside a container image rather than directly on the host, using docker or
+    /// whatever engine is set in the `FLATC_CONTAINER_ENGINE` environment variable (e.g.
+    /// `podman`). The image needs to have `flatc` on its `PATH`. The current directory and the
+    /// directories of the schemas and output get mounted at the same paths inside the container,
+    /// so any included schemas need to live under one of those too.
+    ///
+    /// # Arguments
+    /// * `image` - The container image to run `flatc` in.
+    #[must_use]
+    pub fn set_container_image<S: AsRef<str>>(self, image: S) -> Self {
+        BuilderOptions {
+            compiler: Some(Backend::Container(image.as_ref().into())),
+            ..self
+        }
+    }
+
+    /// Use a `flatc` compiled to WASI as the compiler, running it through an embedded wasmtime
+    /// runtime rather than as a native binary. The same module produces the same output on every
+    /// platform, which makes code generation fully hermetic. If no compiler is set, the
+    /// `FLATC_WASM` environment variable can also point to such a module.
+    ///
+    /// # Arguments
+    /// * `module` - Path to the `flatc` WASI module.
+    #[cfg(feature = "wasi")]
+    #[must_use]
+    pub fn set_wasm_compiler<P: AsRef<Path>>(self, module: P) -> Self {
+        BuilderOptions {
+            compiler: Some(Backend::Wasi(module.as_ref().into())),
             ..self
         }
     }
@@ -220,6 +1100,8 @@ impl BuilderOptions {
     /// Set a path to create a symlink that points to the output files. This is commonly used to
     /// symlink to a folder under `src` so you can normally pull in the generated code as a module.
     /// We recommend always calling this and setting it to `src/generated` or something similar.
+    /// A symlink left dangling by `cargo clean`, or pointing at the output of an older build, gets
+    /// re-pointed at the current output on every compilation.
     ///
     /// # Arguments
     /// * `symlink_path` - Path to generate the symlink to.
@@ -231,129 +1113,2176 @@ impl BuilderOptions {
         }
     }
 
-    /// Set this if you're not running from a `build.rs` script and don't want us to print the
-    /// build.rs instructions/directives that we would otherwise print in stdout.
+    /// Set this when several crates compile the same schemas with the same options into the same
+    /// output directory, set with [`Self::set_output_path`]. The output directory then stays
+    /// locked for the whole compilation rather than just while writing to it, so that only the
+    /// first crate to get there runs `flatc`, and the others find the code it generated already
+    /// up to date.
     #[must_use]
-    pub fn supress_buildrs_directives(self) -> Self {
+    pub fn shared_output(self) -> Self {
         BuilderOptions {
-            supress_buildrs_directives: true,
+            shared_output: true,
             ..self
         }
     }
 
-    /// Call this function to trigger compilation. Will write the compiled protobufs to the
-    /// specified directory, or to `${OUT_DIR}/flatbuffers` by default.
-    ///
-    /// # Errors
-    /// Will fail if any error happens during compilation, including:
-    /// - Invalid protoc files
-    /// - Unsupported flatc version
-    /// - flatc exiting with a non-zero error code
-    /// For more details, see [`Error`].
-    pub fn compile(self) -> Result {
-        compile(self)
+    /// Set this to keep building when `flatc` can't be found, as long as the output directory
+    /// holds code generated from the same schemas with the same options, as recorded in its
+    /// `.flatbuffers-build-stamp.json` file. The code gets used as it is, with a warning. This
+    /// lets contributors without `flatc` installed build a crate whose generated code is checked
+    /// in along with the stamp file, or was generated before `flatc` went missing. As long as
+    /// `flatc` can be found, this has no effect.
+    #[must_use]
+    pub fn reuse_output_without_flatc(self) -> Self {
+        BuilderOptions {
+            reuse_output_without_flatc: true,
+            ..self
+        }
     }
-}
 
-fn compile(builder_options: BuilderOptions) -> Result {
-    let files_str: Vec<_> = builder_options
-        .files
-        .iter()
-        .map(|p| p.clone().into_os_string())
-        .collect();
-    let compiler = builder_options.compiler.unwrap_or_else(|| {
-        if let Some(build_flatc) = FLATC_BUILD_PATH {
-            build_flatc.to_owned()
-        } else {
-            std::env::var("FLATC_PATH").unwrap_or("flatc".into())
+    /// Ignore changes to the schemas that can't affect the generated code, like changes to
+    /// comments or formatting, when deciding whether the code needs generating again and which
+    /// cached code to use. Reformatting the schemas then doesn't trigger running `flatc` again.
+    /// `///` documentation comments still count, since they end up in the generated code.
+    #[must_use]
+    pub fn ignore_schema_formatting(self) -> Self {
+        BuilderOptions {
+            ignore_schema_formatting: true,
+            ..self
         }
-    });
-    let output_path = builder_options.output_path.map_or_else(
-        || {
-            std::env::var_os("OUT_DIR")
-                .ok_or(Error::OutputDirNotSet)
-                .map(|mut s| {
-                    s.push(OsString::from("/flatbuffers"));
-                    s
-                })
-        },
-        |p| Ok(p.into_os_string()),
-    )?;
-
-    confirm_flatc_version(&compiler)?;
-
-    let mut args = vec![
-        OsString::from("--rust"),
-        OsString::from("--rust-module-root-file"),
-        OsString::from("-o"),
-        output_path.clone(),
-    ];
-    args.extend(files_str);
-    run_flatc(&compiler, &args)?;
+    }
 
-    if let Some(symlink_path) = builder_options.symlink_path {
-        generate_symlink(&symlink_path, PathBuf::from(output_path))?;
-        if !builder_options.supress_buildrs_directives {
-            println!("cargo::rerun-if-changed={}", symlink_path.display());
+    /// Cache the generated code in `output_cache_dir`, keyed by a hash of everything it got
+    /// generated from, and restore it from there instead of running `flatc` whenever the same
+    /// schemas get compiled with the same options again. Unlike the output directory, the cache
+    /// survives switching branches back and forth, `cargo clean` and fresh target directories, and
+    /// can be shared between checkouts. The `FLATBUFFERS_BUILD_OUTPUT_CACHE_DIR` environment
+    /// variable sets this for every builder that doesn't set it itself.
+    ///
+    /// # Arguments
+    /// * `output_cache_dir` - Directory to keep the cache in. It gets created if it doesn't exist.
+    #[must_use]
+    pub fn set_output_cache_dir<P: AsRef<Path>>(self, output_cache_dir: P) -> Self {
+        BuilderOptions {
+            output_cache_dir: Some(output_cache_dir.as_ref().into()),
+            ..self
         }
     }
 
-    if !builder_options.supress_buildrs_directives {
-        for file in builder_options.files {
-            println!("cargo::rerun-if-changed={}", file.display());
+    /// Share the generated code through a remote cache, on top of the local one set with
+    /// [`Self::set_output_cache_dir`], so that machines like CI runners only run `flatc` for
+    /// schemas and options none of them compiled before. Entries get pulled before running
+    /// `flatc`, and pushed after. The `FLATBUFFERS_BUILD_REMOTE_CACHE` environment variable sets
+    /// this for every builder that doesn't set it itself. See the "Remote caching" section of the
+    /// crate documentation for how to authenticate.
+    ///
+    /// # Arguments
+    /// * `url` - Either an `http://` or `https://` URL that entries get read from with `GET` and
+    ///   written to with `PUT`, or an `s3://bucket/prefix` URL of an S3-compatible bucket.
+    #[cfg(feature = "remote-cache")]
+    #[must_use]
+    pub fn set_remote_cache<S: AsRef<str>>(self, url: S) -> Self {
+        BuilderOptions {
+            remote_cache: Some(url.as_ref().into()),
+            ..self
         }
     }
-    Ok(())
-}
 
-fn generate_symlink<P: AsRef<Path>, Q: AsRef<Path>>(symlink_path: P, output_path: Q) -> Result {
-    if symlink_path.as_ref().exists() {
-        std::fs::remove_file(&symlink_path).map_err(Error::SymlinkCreationFailure)?;
+    /// Set this if you're not running from a `build.rs` script and don't want us to print the
+    /// build.rs instructions/directives that we would otherwise print in stdout. To only turn off
+    /// some of them, see [`Self::supress_rerun_directives`] and
+    /// [`Self::supress_warning_directives`].
+    #[must_use]
+    pub fn supress_buildrs_directives(self) -> Self {
+        BuilderOptions {
+            directives: Directives::none(),
+            ..self
+        }
     }
-    std::os::unix::fs::symlink(output_path, symlink_path).map_err(Error::SymlinkCreationFailure)?;
-    Ok(())
-}
 
-fn confirm_flatc_version(compiler: &str) -> Result {
-    // Output shows up in stdout
-    let output = run_flatc(compiler, ["--version"])?;
-    if output.stdout.starts_with(FLATC_VERSION_PREFIX) {
-        let version_str = output.stdout[FLATC_VERSION_PREFIX.len()..].trim_end();
-        if version_str == SUPPORTED_FLATC_VERSION {
-            Ok(())
-        } else {
-            Err(Error::UnsupportedFlatcVersion(version_str.into()))
+    /// Don't print the `rerun-if-changed` and `rerun-if-env-changed` directives telling cargo
+    /// when to run the build script again, while still printing everything else.
+    #[must_use]
+    pub fn supress_rerun_directives(self) -> Self {
+        BuilderOptions {
+            directives: Directives {
+                rerun: false,
+                ..self.directives
+            },
+            ..self
         }
-    } else {
-        Err(Error::InvalidFlatcOutput(output.stdout))
     }
-}
 
-struct ProgramOutput {
+    /// Don't print warnings, verbose output or timings as `warning` directives, while still
+    /// printing everything else. With the `tracing` feature these are emitted as events
+    /// regardless.
+    #[must_use]
+    pub fn supress_warning_directives(self) -> Self {
+        BuilderOptions {
+            directives: Directives {
+                warnings: false,
+                ..self.directives
+            },
+            ..self
+        }
+    }
+
+    /// Print directives with `prefix` in front of them instead of `cargo::`. This is useful when
+    /// running outside of a build script, e.g. from a CLI tool that parses the directives
+    /// itself, or to use the single colon `cargo:` syntax understood by older cargo versions.
+    ///
+    /// # Arguments
+    /// * `prefix` - What to print in front of every directive.
+    #[must_use]
+    pub fn set_directive_prefix<S: AsRef<str>>(self, prefix: S) -> Self {
+        BuilderOptions {
+            directives: Directives {
+                prefix: prefix.as_ref().into(),
+                ..self.directives
+            },
+            ..self
+        }
+    }
+
+    /// Print the fully resolved path of `flatc`, the arguments of every invocation and the
+    /// environment variables affecting it as cargo warnings. Handy to figure out why generated
+    /// code differs between machines. This can also be turned on without touching the build
+    /// script by setting the `FLATBUFFERS_BUILD_VERBOSE` environment variable to `1`.
+    #[must_use]
+    pub fn verbose(self) -> Self {
+        BuilderOptions {
+            verbose: true,
+            ..self
+        }
+    }
+
+    /// Guarantee compilation doesn't reach out to the network, failing instead of fetching
+    /// anything. This matters when running `flatc` in a container, whose image then has to be
+    /// available locally already. For air-gapped builds and `cargo vendor` setups, you'll also
+    /// want to set the `FLATBUFFERS_BUILD_OFFLINE` environment variable to `1`, which turns this
+    /// on and also stops the `vendored` features from downloading anything when building this
+    /// crate. See the "Offline builds" section of the crate documentation.
+    #[must_use]
+    pub fn offline(self) -> Self {
+        BuilderOptions {
+            offline: true,
+            ..self
+        }
+    }
+
+    /// Kill any `flatc` invocation that runs for longer than `timeout`, failing compilation with
+    /// [`Error::Timeout`]. Without one, a hung `flatc` (e.g. one reading from a FIFO passed by
+    /// mistake) hangs the whole build. When running `flatc` in a container, this kills the
+    /// container engine's client rather than the container itself.
+    ///
+    /// # Arguments
+    /// * `timeout` - How long a single `flatc` invocation is allowed to run for.
+    #[must_use]
+    pub fn set_timeout(self, timeout: Duration) -> Self {
+        BuilderOptions {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Write a JSON report of the compilation to `report_path`, whether it succeeds or not. The
+    /// report holds whether compilation succeeded, the error it failed with if any, the errors
+    /// and warnings reported by `flatc` with their location in the schemas, the generated files
+    /// and how long compilation and each of its phases took. This is meant for CI systems, e.g.
+    /// to render schema errors as annotations without scraping cargo's output.
+    ///
+    /// # Arguments
+    /// * `report_path` - Path of the JSON file to write the report to.
+    #[must_use]
+    pub fn set_report_path<P: AsRef<Path>>(self, report_path: P) -> Self {
+        BuilderOptions {
+            report_path: Some(report_path.as_ref().into()),
+            ..self
+        }
+    }
+
+    /// Write a Makefile-style dependency file to `depfile_path` after compiling, with a rule for
+    /// each generated file listing every schema it depends on, including the ones pulled in
+    /// through `include`. This is meant for build systems wrapping this crate, like Bazel, Buck2
+    /// or ninja, to know when the code needs generating again.
+    ///
+    /// # Arguments
+    /// * `depfile_path` - Path of the dependency file to write.
+    #[must_use]
+    pub fn set_depfile_path<P: AsRef<Path>>(self, depfile_path: P) -> Self {
+        BuilderOptions {
+            depfile_path: Some(depfile_path.as_ref().into()),
+            ..self
+        }
+    }
+
+    /// Print how long compilation took as a cargo warning, broken down into checking the version
+    /// of `flatc`, running it, copying the generated files into the output directory and creating
+    /// the symlink. The same breakdown is always part of the report written with
+    /// [`Self::set_report_path`].
+    #[must_use]
+    pub fn print_timings(self) -> Self {
+        BuilderOptions {
+            print_timings: true,
+            ..self
+        }
+    }
+
+    /// Print the counters [`Self::compile_with_counters`] returns as a cargo warning: how many
+    /// schemas got compiled into how many files, how many bytes were written, whether the code
+    /// came from a cache and how long `flatc` ran for.
+    #[must_use]
+    pub fn print_counters(self) -> Self {
+        BuilderOptions {
+            print_counters: true,
+            ..self
+        }
+    }
+
+    /// Set or override the root type of the schemas (passed to `flatc` as `--root-type`). This is
+    /// needed for schemas that don't declare a `root_type` themselves.
+    ///
+    /// # Arguments
+    /// * `root_type` - Fully-qualified name of the root table, such as `MyGame.Sample.Monster`.
+    #[must_use]
+    pub fn set_root_type<S: AsRef<str>>(self, root_type: S) -> Self {
+        BuilderOptions {
+            root_type: Some(root_type.as_ref().into()),
+            ..self
+        }
+    }
+
+    /// Set the suffix appended to the name of every generated file (passed to `flatc` as
+    /// `--filename-suffix`). If you don't set this, `flatc` defaults to `_generated`.
+    ///
+    /// # Arguments
+    /// * `suffix` - The suffix to use. This can be empty.
+    #[must_use]
+    pub fn set_filename_suffix<S: AsRef<str>>(self, suffix: S) -> Self {
+        BuilderOptions {
+            filename_suffix: Some(suffix.as_ref().into()),
+            ..self
+        }
+    }
+
+    /// Set the extension of every generated file (passed to `flatc` as `--filename-ext`). If you
+    /// don't set this, `flatc` defaults to `rs`.
+    ///
+    /// # Arguments
+    /// * `extension` - The extension to use, without the leading dot.
+    #[must_use]
+    pub fn set_filename_extension<S: AsRef<str>>(self, extension: S) -> Self {
+        BuilderOptions {
+            filename_extension: Some(extension.as_ref().into()),
+            ..self
+        }
+    }
+
+    /// Set a path to prefix to every include statement in the generated code (passed to `flatc` as
+    /// `--include-prefix`). Combined with [`Self::keep_prefix`], this keeps the directory
+    /// structure of your schemas in generated include references, so identically named schemas
+    /// living in different directories don't collide.
+    ///
+    /// # Arguments
+    /// * `include_prefix` - The path to prefix include statements with.
+    #[must_use]
+    pub fn set_include_prefix<P: AsRef<Path>>(self, include_prefix: P) -> Self {
+        BuilderOptions {
+            include_prefix: Some(include_prefix.as_ref().into()),
+            ..self
+        }
+    }
+
+    /// Add a directory to look for included schemas in (passed to `flatc` as `-I`), on top of the
+    /// directory of the including schema. Can be called multiple times to add more directories.
+    ///
+    /// # Arguments
+    /// * `include_path` - Directory to resolve `include` statements against.
+    #[must_use]
+    pub fn add_include_path<P: AsRef<Path>>(mut self, include_path: P) -> Self {
+        self.include_paths.push(include_path.as_ref().into());
+        self
+    }
+
+    /// Add the schemas exported by a dependency through [`export_schemas`] as include paths, so
+    /// the schemas being compiled can include them. Can be called multiple times to add the
+    /// schemas of more dependencies.
+    ///
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// BuilderOptions::new_with_files(["schemas/service.fbs"])
+    ///     .add_dependency_schemas("my-schemas")
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// ```
+    ///
+    /// # Arguments
+    /// * `dependency` - The `links` name of the dependency exporting the schemas, as set in its
+    ///   `Cargo.toml`.
+    #[must_use]
+    pub fn add_dependency_schemas<S: AsRef<str>>(mut self, dependency: S) -> Self {
+        self.dependency_schemas.push(dependency.as_ref().into());
+        self
+    }
+
+    /// Keep the original prefix of schema include statements in the generated code (by passing
+    /// `--keep-prefix` to `flatc`), instead of only keeping the file name.
+    #[must_use]
+    pub fn keep_prefix(self) -> Self {
+        self.add_flatc_flag("--keep-prefix")
+    }
+
+    /// Also generate binary schema (`.bfbs`) files for every schema, by running `flatc` a second
+    /// time with `--schema --binary`. They get written at the root of the output directory, next
+    /// to the generated Rust code, and are useful for reflection-based tooling that needs the
+    /// schemas at runtime.
+    #[must_use]
+    pub fn generate_binary_schemas(self) -> Self {
+        BuilderOptions {
+            generate_binary_schemas: true,
+            ..self
+        }
+    }
+
+    /// Also generate a [JSON Schema](https://json-schema.org/) file for every schema, by running
+    /// `flatc` an extra time with `--jsonschema`. The JSON Schema for `name.fbs` gets written at
+    /// the root of the output directory as `name.schema.json`, and can be used to validate JSON
+    /// payloads before converting them.
+    #[must_use]
+    pub fn generate_json_schemas(self) -> Self {
+        BuilderOptions {
+            generate_json_schemas: true,
+            ..self
+        }
+    }
+
+    /// Embed each schema's binary representation in the generated code as a constant (by passing
+    /// `--bfbs-gen-embed` to `flatc`). This makes the generated code self-describing without having
+    /// to ship `.bfbs` files separately.
+    #[must_use]
+    pub fn bfbs_gen_embed(self) -> Self {
+        self.add_flatc_flag("--bfbs-gen-embed")
+    }
+
+    /// Output UTF-8 strings as human-readable text instead of `\uXXXX` escapes (by passing
+    /// `--natural-utf8` to `flatc`), matching what other language bindings do.
+    #[must_use]
+    pub fn natural_utf8(self) -> Self {
+        self.add_flatc_flag("--natural-utf8")
+    }
+
+    /// Treat any warning reported by `flatc` as an error (by passing `--warnings-as-errors` to
+    /// `flatc`). On top of that, any warning that still makes it into `flatc`'s output fails
+    /// compilation with [`Error::FlatcWarnings`]. Use this to enforce schema hygiene at build time.
+    #[must_use]
+    pub fn warnings_as_errors(self) -> Self {
+        self.add_flatc_flag(WARNINGS_AS_ERRORS_FLAG)
+    }
+
+    /// Silence all warnings reported by `flatc` (by passing `--no-warnings` to `flatc`). This keeps
+    /// noise out of your build logs, like deprecation warnings coming from third-party schemas you
+    /// can't modify. Setting this alongside [`Self::warnings_as_errors`] makes little sense.
+    #[must_use]
+    pub fn no_warnings(self) -> Self {
+        self.add_flatc_flag("--no-warnings")
+    }
+
+    /// Add minimal type reflection information to the generated code (by passing
+    /// `--reflect-types` to `flatc`).
+    #[must_use]
+    pub fn reflect_types(self) -> Self {
+        self.add_flatc_flag("--reflect-types")
+    }
+
+    /// Add minimal type and name reflection information to the generated code (by passing
+    /// `--reflect-names` to `flatc`). This preserves field and type names on top of what
+    /// [`Self::reflect_types`] provides.
+    #[must_use]
+    pub fn reflect_names(self) -> Self {
+        self.add_flatc_flag("--reflect-names")
+    }
+
+    /// Preserve documentation comments from the schemas in generated binary schemas (by passing
+    /// `--bfbs-comments` to `flatc`). Only has an effect when binary schemas are generated, either
+    /// with [`Self::generate_binary_schemas`] or [`Self::bfbs_gen_embed`].
+    #[must_use]
+    pub fn bfbs_comments(self) -> Self {
+        self.add_binary_schema_flag("--bfbs-comments")
+    }
+
+    /// Include built-in attributes in generated binary schemas (by passing `--bfbs-builtins` to
+    /// `flatc`). Only has an effect when binary schemas are generated, either with
+    /// [`Self::generate_binary_schemas`] or [`Self::bfbs_gen_embed`].
+    #[must_use]
+    pub fn bfbs_builtins(self) -> Self {
+        self.add_binary_schema_flag("--bfbs-builtins")
+    }
+
+    /// Set this to write a `manifest.json` file at the root of the output directory. It lists every
+    /// input schema along with its SHA-256 hash and the generated files it produced, as well as
+    /// the hash of every generated file. This is useful for external build systems and caching
+    /// layers that need to keep track of the generated artifacts.
+    #[must_use]
+    pub fn generate_manifest(self) -> Self {
+        BuilderOptions {
+            generate_manifest: true,
+            ..self
+        }
+    }
+
+    /// Generate a `prelude` module re-exporting the types generated from the schemas, so they can
+    /// be imported all at once rather than from the modules of their namespaces. That's every table
+    /// along with its `*Args`, every struct, enum and union, and with
+    /// [`Self::set_object_api_feature`], their `*T` object API types, only re-exported with that
+    /// feature enabled. Types named the same in different namespaces are left out, with a warning.
+    ///
+    /// The prelude is a `prelude.rs` declared in the generated `mod.rs`, or with
+    /// [`Self::gen_onefile`], a `prelude` module at the end of each generated file, covering the
+    /// types of its schema:
+    /// ```ignore
+    /// mod gen_flatbuffers;
+    ///
+    /// use gen_flatbuffers::prelude::*;
+    /// ```
+    #[must_use]
+    pub fn generate_prelude(self) -> Self {
+        BuilderOptions {
+            generate_prelude: true,
+            ..self
+        }
+    }
+
+    /// Generate an `aliases` module with type aliases for the root types of the schemas, so they
+    /// can be named without the modules of their namespaces, as in
+    /// `pub type Monster<'a> = super::my_game::sample::Monster<'a>;`. Those are the types declared
+    /// with `root_type` in the schemas, or the one set with [`Self::set_root_type`]. Root types
+    /// named the same in different namespaces are left out, with a warning.
+    ///
+    /// The aliases are in an `aliases.rs` declared in the generated `mod.rs`, or with
+    /// [`Self::gen_onefile`], an `aliases` module at the end of each generated file, covering
+    /// the root type of its schema:
+    /// ```ignore
+    /// mod gen_flatbuffers;
+    ///
+    /// use gen_flatbuffers::aliases::Monster;
+    ///
+    /// fn hp(monster: Monster<'_>) -> i16 {
+    ///     monster.hp()
+    /// }
+    /// ```
+    #[must_use]
+    pub fn generate_aliases(self) -> Self {
+        BuilderOptions {
+            generate_aliases: true,
+            ..self
+        }
+    }
+
+    /// Generate accessors that allow mutating scalar fields of a buffer in place (by passing
+    /// `--gen-mutable` to `flatc`). This lets you patch values in a received buffer without having
+    /// to rebuild the whole message.
+    #[must_use]
+    pub fn gen_mutable(self) -> Self {
+        self.add_flatc_flag("--gen-mutable")
+    }
+
+    /// Have the generated types carry their fully-qualified schema names (by passing
+    /// `--gen-name-strings` to `flatc`). Useful if you want to report the flatbuffer type name
+    /// generically, like in logs or telemetry.
+    #[must_use]
+    pub fn gen_name_strings(self) -> Self {
+        self.add_flatc_flag("--gen-name-strings")
+    }
+
+    /// Generate comparison support for the generated object API types (by passing
+    /// `--gen-compare` to `flatc`), so decoded messages can be compared directly.
+    #[must_use]
+    pub fn gen_compare(self) -> Self {
+        self.add_flatc_flag("--gen-compare")
+    }
+
+    /// Generate a single Rust file per schema instead of a tree of per-namespace modules (by
+    /// passing `--gen-onefile` to `flatc`). The file for `schema.fbs` is named
+    /// `schema_generated.rs` and contains every namespace as nested modules.
+    ///
+    /// When this results in a single generated file, the symlink set with
+    /// [`Self::set_symlink_directory`] points straight at that file rather than at the output
+    /// directory, so you'll want the symlink path to end in `.rs`:
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// BuilderOptions::new_with_files(["schemas/example.fbs"])
+    ///     .gen_onefile()
+    ///     .set_symlink_directory("src/gen_flatbuffers.rs")
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// ```
+    #[must_use]
+    pub fn gen_onefile(self) -> Self {
+        self.add_flatc_flag(GEN_ONEFILE_FLAG)
+    }
+
+    /// Also generate code for every schema that gets `include`d by the files provided (by passing
+    /// `--gen-all` to `flatc`). This lets you list only your root schema instead of every file it
+    /// transitively depends on.
+    #[must_use]
+    pub fn gen_all(self) -> Self {
+        self.add_flatc_flag(GEN_ALL_FLAG)
+    }
+
+    /// Don't generate references to the code of `include`d schemas (by passing `--no-includes` to
+    /// `flatc`). This is meant for setups where the included schemas get compiled separately, like
+    /// by a different crate, and only the code for the schemas listed here should be emitted.
+    #[must_use]
+    pub fn no_includes(self) -> Self {
+        self.add_flatc_flag("--no-includes")
+    }
+
+    /// Always write scalar fields into buffers, even when they're equal to their default value (by
+    /// passing `--force-defaults` to `flatc`). Use this if consumers of your buffers rely on fields
+    /// being physically present.
+    #[must_use]
+    pub fn force_defaults(self) -> Self {
+        self.add_flatc_flag("--force-defaults")
+    }
+
+    /// Before generating any code, check that the schemas are a valid evolution of an older
+    /// baseline schema (by running `flatc` with `--conform`). If they're not, compilation fails with
+    /// [`Error::ConformanceViolation`]. Use this to have your build fail whenever someone makes a
+    /// backwards incompatible change to a schema. See also the standalone [`check_conformance`].
+    ///
+    /// # Arguments
+    /// * `baseline` - Path to the older schema to check against.
+    #[must_use]
+    pub fn check_conformance<P: AsRef<Path>>(self, baseline: P) -> Self {
+        BuilderOptions {
+            conform_baseline: Some(baseline.as_ref().into()),
+            ..self
+        }
+    }
+
+    /// Set the include paths used to resolve the `include` directives of the baseline schema set
+    /// with [`Self::check_conformance`] (passed to `flatc` as `--conform-includes`). This is needed
+    /// when the baseline schemas live in a different directory tree than the working copy.
+    ///
+    /// # Arguments
+    /// * `include_paths` - Directories to look for the baseline schema's includes in.
+    #[must_use]
+    pub fn set_conform_include_paths<P: AsRef<Path>, I: IntoIterator<Item = P>>(
+        self,
+        include_paths: I,
+    ) -> Self {
+        BuilderOptions {
+            conform_include_paths: include_paths
+                .into_iter()
+                .map(|p| p.as_ref().into())
+                .collect(),
+            ..self
+        }
+    }
+
+    /// Set this to run in check mode. Instead of writing to the output directory, we'll compare
+    /// its contents against what `flatc` generates from the current schemas and fail with
+    /// [`Error::OutdatedGeneratedCode`] if anything differs. Check mode can also be enabled by
+    /// setting the `FLATBUFFERS_BUILD_CHECK` environment variable to `1` or `true`.
+    ///
+    /// This is meant to be used alongside generating directly into a directory committed to your
+    /// repository, so CI can catch generated code that drifted from the schemas:
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .set_output_path("src/generated")
+    ///     .check_generated_code()
+    ///     .compile()
+    ///     .expect("generated code is out of date");
+    /// ```
+    ///
+    /// Files an earlier compilation generated that would no longer be, e.g. those of a removed
+    /// schema, are reported as differences too. Compiling without check mode deletes them, so after
+    /// removing or renaming a schema or type, regenerating the code gets rid of what it used to
+    /// generate and brings check mode back in line. Files put in the output directory by anything
+    /// else are left alone either way:
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// // `schemas/weapon.fbs` was removed, which leaves its code behind in `src/generated`
+    /// let builder = BuilderOptions::new_with_files(["schemas/example.fbs"])
+    ///     .set_output_path("src/generated");
+    /// builder
+    ///     .clone()
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// builder
+    ///     .check_generated_code()
+    ///     .compile()
+    ///     .expect("generated code is out of date");
+    /// ```
+    #[must_use]
+    pub fn check_generated_code(self) -> Self {
+        BuilderOptions {
+            check_generated_code: true,
+            ..self
+        }
+    }
+
+    /// Format the generated code with `rustfmt`, using the one set in the `RUSTFMT` environment
+    /// variable or else the one in the `PATH`. This keeps diffs of generated code committed to
+    /// your repository readable and pre-commit hooks checking formatting happy. Any `rustfmt.toml`
+    /// in the directory the build script runs in applies.
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .set_output_path("src/generated")
+    ///     .format_generated_code()
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// ```
+    #[must_use]
+    pub fn format_generated_code(self) -> Self {
+        BuilderOptions {
+            format_generated_code: true,
+            ..self
+        }
+    }
+
+    /// Add an attribute to the top of the generated module root: the `mod.rs`, or every generated
+    /// file with [`Self::gen_onefile`]. Can be called multiple times to add more attributes.
+    ///
+    /// Inner attributes such as `#![allow(clippy::all)]` apply to all of the generated code this
+    /// way, sparing you from wrapping the module in `#[allow(warnings)]` by hand. Note that files
+    /// brought in with `include!` can't have inner attributes, so this is meant for code used as a
+    /// module, e.g. through [`Self::set_symlink_directory`].
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .set_symlink_directory("src/gen_flatbuffers")
+    ///     .add_root_attribute("#![allow(clippy::all, clippy::pedantic)]")
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// ```
+    ///
+    /// # Arguments
+    /// * `attribute` - The attribute, as written in Rust code, e.g. `#![rustfmt::skip]`.
+    #[must_use]
+    pub fn add_root_attribute<S: AsRef<str>>(mut self, attribute: S) -> Self {
+        self.root_attributes.push(attribute.as_ref().into());
+        self
+    }
+
+    /// Add an attribute to the top of every generated Rust file, like
+    /// [`Self::add_root_attribute`] does for the module root. Lints that get checked per module,
+    /// like the ones `#![cfg_attr(...)]` turns on or off, need this rather than an attribute on
+    /// the module root. Can be called multiple times to add more attributes.
+    ///
+    /// # Arguments
+    /// * `attribute` - The attribute, as written in Rust code, e.g. `#![allow(unused_imports)]`.
+    #[must_use]
+    pub fn add_file_attribute<S: AsRef<str>>(mut self, attribute: S) -> Self {
+        self.file_attributes.push(attribute.as_ref().into());
+        self
+    }
+
+    /// Add a derive to every generated struct and enum matching `pattern`, on top of the ones
+    /// `flatc` derives. Patterns can use `*` as a wildcard, and match the name of the type, or
+    /// its whole path when they contain `::`. Can be called multiple times to add more derives.
+    ///
+    /// Paths go by the modules the generated code puts types in, so `MyGame.Sample.Monster`
+    /// becomes `my_game::sample::Monster`. Keep in mind that `flatc` generates several types per
+    /// table, such as `Monster` to read it and `MonsterArgs` and `MonsterBuilder` to build it,
+    /// and that enums and structs become tuple structs wrapping their value and bytes:
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .add_derive("my_game::sample::Color", "serde::Serialize")
+    ///     .add_derive("Vec3", "Hash")
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// ```
+    ///
+    /// Deriving a trait `flatc` already derives for a type fails to compile.
+    ///
+    /// # Arguments
+    /// * `pattern` - The types to add the derive to.
+    /// * `derive` - The derive to add, e.g. `Hash` or `arbitrary::Arbitrary`.
+    #[must_use]
+    pub fn add_derive<S: AsRef<str>, T: AsRef<str>>(mut self, pattern: S, derive: T) -> Self {
+        self.derives
+            .push((pattern.as_ref().into(), derive.as_ref().into()));
+        self
+    }
+
+    /// Make the generated code usable from `no_std` crates, by turning its paths into `std` into
+    /// paths into `core`, or `alloc` for the likes of `String` and `Vec`, declaring `alloc` where
+    /// needed. The crate still needs an allocator for the builders of the `flatbuffers` crate,
+    /// along with `flatbuffers` itself built without its default `std` feature. See
+    /// [`Self::set_object_api_feature`] to also build without the object API, which allocates.
+    #[must_use]
+    pub fn make_no_std(self) -> Self {
+        BuilderOptions {
+            no_std: true,
+            ..self
+        }
+    }
+
+    /// Generate the object API (by passing `--gen-object-api` to `flatc`), but only compile it
+    /// with the cargo feature `feature` of the crate enabled, by adding `#[cfg(feature = "...")]`
+    /// to its types, their impls and the `unpack` methods of the tables and structs. Its types hold
+    /// strings and vectors, so this keeps crates for targets without allocations from paying for
+    /// it.
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .make_no_std()
+    ///     .set_object_api_feature("alloc")
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// ```
+    ///
+    /// # Arguments
+    /// * `feature` - The feature of the crate the object API needs, declared in its `Cargo.toml`.
+    #[must_use]
+    pub fn set_object_api_feature<S: AsRef<str>>(self, feature: S) -> Self {
+        BuilderOptions {
+            object_api_feature: Some(feature.as_ref().into()),
+            ..self.add_flatc_flag(OBJECT_API_FLAG)
+        }
+    }
+
+    /// Set the visibility of the generated modules and types, and the rest of the items in them,
+    /// which `flatc` makes `pub`. With [`Visibility::Crate`], none of them can end up in the
+    /// public API of the crate, even through a `pub use`. With [`Visibility::Super`], only the
+    /// items at the root of the generated code get made `pub(super)`, leaving the ones nested in
+    /// its modules `pub`, so they're only visible to the module declaring the generated code.
+    /// ```no_run
+    /// use flatbuffers_build::{BuilderOptions, Visibility};
+    ///
+    /// BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .set_visibility(Visibility::Crate)
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// ```
+    ///
+    /// # Arguments
+    /// * `visibility` - The visibility of the generated items.
+    #[must_use]
+    pub fn set_visibility(self, visibility: Visibility) -> Self {
+        BuilderOptions { visibility, ..self }
+    }
+
+    /// Add a hook rewriting the generated code, for changes to it this crate has no option for.
+    /// Every generated file goes through each hook in the order they got added, after the
+    /// attributes and derives get added and before [`Self::format_generated_code`] formats it. A
+    /// hook gets called with the path of the file relative to the output directory and its
+    /// contents, and returns the new contents. Hooks get called on several threads at once.
+    ///
+    /// ```no_run
+    /// use flatbuffers_build::BuilderOptions;
+    ///
+    /// BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .add_post_processing_hook(|_path, code| code.replace("pub struct", "pub(crate) struct"))
+    ///     .compile()
+    ///     .expect("flatbuffer compilation failed");
+    /// ```
+    ///
+    /// There's no telling whether a hook changed from one compilation to the next, so with any
+    /// hooks added the code always gets regenerated, rather than being skipped when up to date or
+    /// taken from the output cache.
+    ///
+    /// # Arguments
+    /// * `hook` - Closure taking the path and contents of a generated file, and returning its new
+    ///   contents.
+    #[must_use]
+    pub fn add_post_processing_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path, String) -> String + Send + Sync + 'static,
+    {
+        self.post_processing_hooks
+            .push(postprocess::Hook::new(hook));
+        self
+    }
+
+    /// Skip running `flatc` whenever the environment variable `env_var` is set, writing stub output
+    /// in place of the generated code instead. This is already the case for `DOCS_RS`, so that
+    /// crates depending on generated code still get their documentation built on docs.rs, where
+    /// `flatc` isn't available. Can be called multiple times to add more variables.
+    ///
+    /// The stub output is a `mod.rs` (or, with [`Self::gen_onefile`], one file per schema) with no
+    /// items in it, so any code referring to the generated types won't compile against it. Gate
+    /// such code behind `#[cfg(not(docsrs))]` or similar if you need its documentation built.
+    /// When the output directory already holds generated code, e.g. code committed to the
+    /// repository, it's left alone instead of being replaced by the stub.
+    ///
+    /// # Arguments
+    /// * `env_var` - Name of the environment variable that, when set, skips compilation.
+    #[must_use]
+    pub fn skip_if<S: AsRef<str>>(mut self, env_var: S) -> Self {
+        let env_var = env_var.as_ref().to_owned();
+        if !self.skip_env_vars.contains(&env_var) {
+            self.skip_env_vars.push(env_var);
+        }
+        self
+    }
+
+    /// Call this function to trigger compilation. Will write the compiled protobufs to the
+    /// specified directory, or to `${OUT_DIR}/flatbuffers` by default. Files whose contents did not
+    /// change since the last compilation are left untouched, so their modification times are
+    /// preserved and nothing depending on them gets needlessly rebuilt, and files an earlier
+    /// compilation generated that no longer are, e.g. those of a removed schema, get deleted. Other
+    /// files in the output directory are left alone. What the code got generated from is recorded
+    /// in a `.flatbuffers-build-stamp.json` file in the output directory, and as long as none of
+    /// the schemas, options or the version of `flatc` changed, further compilations only run
+    /// `flatc` to ask for its version. When only some of the schemas changed, only the inputs
+    /// affected by them usually get compiled again. The absolute path of the output directory gets
+    /// published in the `FLATBUFFERS_GENERATED_DIR` environment variable of the crate being built,
+    /// and as `generated_dir` metadata to the build scripts of dependents.
+    ///
+    /// # Errors
+    /// Will fail if any error happens during compilation, including:
+    /// - Input files that are missing, unreadable, duplicated or not schemas
+    /// - Dependencies added with [`Self::add_dependency_schemas`] that exported no schemas
+    /// - Invalid protoc files
+    /// - Unsupported flatc version
+    /// - flatc exiting with a non-zero error code
+    /// - Schemas not conforming to the baseline set with [`Self::check_conformance`]
+    /// - Generated code being out of date when running in check mode
+    ///
+    /// For more details, see [`Error`].
+    pub fn compile(self) -> Result {
+        compile(&self).map(drop)
+    }
+
+    /// Same as [`Self::compile`], but returns counters describing the work compilation did, e.g.
+    /// to track the cost of code generation over time.
+    ///
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// let counters = BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .compile_with_counters()
+    ///     .expect("flatbuffer compilation failed");
+    /// println!(
+    ///     "wrote {} bytes, flatc ran for {:.3}s",
+    ///     counters.bytes_written, counters.subprocess_secs
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Fails the same way as [`Self::compile`].
+    pub fn compile_with_counters(self) -> Result<Counters> {
+        compile(&self)
+    }
+
+    /// Work out everything [`Self::compile`] would do without actually running anything: the
+    /// exact `flatc` commands, the schemas they read and the files they're expected to generate.
+    /// This is meant for build systems that want to inspect the compilation or replay it with
+    /// their own executors.
+    ///
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// let plan = BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .set_output_path("target/flatbuffers/")
+    ///     .plan()
+    ///     .expect("failed to plan compilation");
+    /// for command in &plan.commands {
+    ///     println!("{:?} {:?}", command.program, command.args);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Fails if any of the input files are missing, unreadable, duplicated or not schemas, if any of
+    /// the dependencies added with [`Self::add_dependency_schemas`] exported no schemas, or if no
+    /// output directory is set.
+    pub fn plan(&self) -> Result<CompilationPlan> {
+        if !self.dependency_schemas.is_empty() {
+            return self.with_dependency_schemas()?.plan();
+        }
+        validate_inputs(&self.files)?;
+        check_file_order(&self.files, &self.include_paths)?;
+        let layout = self.output_layout();
+        let compiler = self.flatc();
+        let output_path = self.resolve_output_path()?;
+        let dependencies = depfile::schema_dependencies(&self.files, &self.include_paths)?;
+        if self.skip_env_var().is_some() {
+            return Ok(CompilationPlan {
+                inputs: self.files.clone(),
+                dependencies,
+                outputs: output::stub_files(&layout, &self.files)?,
+                output_path,
+                symlink_path: self.symlink_path.clone(),
+                commands: Vec::new(),
+            });
+        }
+
+        let mut commands = vec![PlannedCommand::new(
+            &compiler,
+            vec![OsString::from("--version")],
+        )?];
+        let proto_dir = plan::proto_dir(&output_path);
+        let proto_files: Vec<_> = self
+            .files
+            .iter()
+            .filter(|f| proto::is_proto(f))
+            .cloned()
+            .collect();
+        if !proto_files.is_empty() {
+            commands.push(PlannedCommand::new(
+                &compiler,
+                proto::conversion_args(&proto_files, &proto_dir),
+            )?);
+        }
+        let schemas: Vec<_> = self
+            .files
+            .iter()
+            .map(|f| {
+                if proto::is_proto(f) {
+                    proto::translated_path(f, &proto_dir)
+                } else {
+                    f.clone()
+                }
+            })
+            .collect();
+        if let Some(baseline) = &self.conform_baseline {
+            commands.push(PlannedCommand::new(
+                &compiler,
+                conform::conformance_args(baseline, &self.conform_include_paths, &schemas),
+            )?);
+        }
+        commands.push(PlannedCommand::new(
+            &compiler,
+            self.rust_args(&schemas, &output_path),
+        )?);
+        if self.generate_binary_schemas {
+            commands.push(PlannedCommand::new(
+                &compiler,
+                self.binary_schema_args(&schemas, &output_path),
+            )?);
+        }
+        if self.generate_json_schemas {
+            commands.push(PlannedCommand::new(
+                &compiler,
+                self.json_schema_args(&schemas, &output_path),
+            )?);
+        }
+
+        let outputs = self.planned_outputs(&layout, &schemas)?;
+
+        Ok(CompilationPlan {
+            inputs: self.files.clone(),
+            dependencies,
+            output_path,
+            symlink_path: self.symlink_path.clone(),
+            outputs,
+            commands,
+        })
+    }
+
+    /// Work out which of the schemas of the compilation include which, without running `flatc`.
+    /// The graph covers the inputs and every schema they include, directly or not, and can tell
+    /// the order the inputs have to be listed in and whether any of them include each other:
+    ///
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// let graph = BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .include_graph()
+    ///     .expect("failed to read the schemas");
+    /// for idx in graph.compile_order() {
+    ///     println!("{}", graph.schemas[idx].display());
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Fails if any of the schemas can't be read.
+    pub fn include_graph(&self) -> Result<IncludeGraph> {
+        IncludeGraph::new(&self.files, &self.include_paths)
+    }
+
+    /// Check the schemas against conventions `flatc` doesn't enforce, listed in [`LintRule`]:
+    /// every schema with a `root_type` should have a `file_identifier`, every table field an
+    /// explicit `id`, and names should follow the flatbuffers style guide. Only the inputs get
+    /// checked, not the schemas they include, and `.proto` inputs are skipped.
+    ///
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// let lints = BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .lint_schemas()
+    ///     .expect("failed to read the schemas");
+    /// for lint in &lints {
+    ///     println!("{lint}");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Fails if any of the schemas can't be read.
+    pub fn lint_schemas(&self) -> Result<Vec<Lint>> {
+        let mut lints = Vec::new();
+        for file in self.files.iter().filter(|file| !proto::is_proto(file)) {
+            lints.extend(lint::lint_file(file)?);
+        }
+        Ok(lints)
+    }
+
+    /// Set up a [`Converter`] for data of the root type of the schemas, so that JSON and binary
+    /// payloads can be converted with the same configuration the code gets generated with. The root
+    /// type is the one set with [`Self::set_root_type`], or else the one the inputs declare with
+    /// `root_type`, and the converter reads the schema declaring it with the same include paths and
+    /// `flatc`:
+    ///
+    /// ```no_run
+    /// # use flatbuffers_build::BuilderOptions;
+    /// let json_files = BuilderOptions::new_with_files(["schemas/weapon.fbs", "schemas/example.fbs"])
+    ///     .set_root_type("MyGame.Sample.Monster")
+    ///     .supress_buildrs_directives()
+    ///     .converter()
+    ///     .expect("failed to find the root type")
+    ///     .set_output_path("decoded")
+    ///     .binary_to_json(["captured/monster.bin"])
+    ///     .expect("conversion failed");
+    /// ```
+    ///
+    /// # Errors
+    /// Fails if any of the schemas can't be read, or if there's no single root type to convert
+    /// data with.
+    pub fn converter(&self) -> Result<Converter> {
+        Converter::for_builder(self)
+    }
+
+    /// The files, relative to the output directory, that compiling the inputs generates with
+    /// `layout`, given `schemas`, the schema of each input.
+    fn planned_outputs(
+        &self,
+        layout: &schema::OutputLayout,
+        schemas: &[PathBuf],
+    ) -> Result<Vec<PathBuf>> {
+        let mut outputs = Vec::new();
+        if !layout.one_file {
+            outputs.push(PathBuf::from(output::MOD_ROOT_FILE_NAME));
+            if self.generate_prelude {
+                outputs.push(format!("{}.rs", prelude::PRELUDE_MODULE_NAME).into());
+            }
+            if self.generate_aliases {
+                outputs.push(format!("{}.rs", aliases::ALIASES_MODULE_NAME).into());
+            }
+        }
+        for (input, schema) in self.files.iter().zip(schemas) {
+            if layout.one_file || !proto::is_proto(input) {
+                outputs.extend(layout.generated_files_for(schema)?);
+            }
+            let stem = schema.file_stem().unwrap_or_default().to_string_lossy();
+            if layout.binary_schemas {
+                outputs.push(format!("{stem}.bfbs").into());
+            }
+            if layout.json_schemas {
+                outputs.push(format!("{stem}.schema.json").into());
+            }
+        }
+        if self.generate_manifest {
+            outputs.push(manifest::MANIFEST_FILE_NAME.into());
+        }
+        outputs.sort();
+        outputs.dedup();
+        Ok(outputs)
+    }
+
+    /// The directory generated code gets written to: the one set with [`Self::set_output_path`],
+    /// or `${OUT_DIR}/flatbuffers`.
+    fn resolve_output_path(&self) -> Result<PathBuf> {
+        match &self.output_path {
+            Some(output_path) => Ok(output_path.clone()),
+            None => std::env::var_os("OUT_DIR")
+                .ok_or(Error::OutputDirNotSet)
+                .map(|out_dir| PathBuf::from(out_dir).join("flatbuffers")),
+        }
+    }
+
+    /// The first of the environment variables skipping compilation that's set, if any.
+    fn skip_env_var(&self) -> Option<&String> {
+        self.skip_env_vars
+            .iter()
+            .find(|var| std::env::var_os(var).is_some())
+    }
+
+    fn output_layout(&self) -> schema::OutputLayout {
+        let default = schema::OutputLayout::default();
+        schema::OutputLayout {
+            one_file: self.flatc_flags.contains(&GEN_ONEFILE_FLAG),
+            binary_schemas: self.generate_binary_schemas,
+            json_schemas: self.generate_json_schemas,
+            suffix: self.filename_suffix.clone().unwrap_or(default.suffix),
+            extension: self.filename_extension.clone().unwrap_or(default.extension),
+            gen_all: self
+                .flatc_flags
+                .contains(&GEN_ALL_FLAG)
+                .then(|| self.include_paths.clone()),
+        }
+    }
+
+    /// Arguments for the `flatc` invocation generating Rust code for `schemas` into `output_dir`.
+    fn rust_args(&self, schemas: &[PathBuf], output_dir: &Path) -> Vec<OsString> {
+        let mut args = vec![OsString::from("--rust")];
+        // There are no modules to tie together when everything lands in a single file
+        if !self.flatc_flags.contains(&GEN_ONEFILE_FLAG) {
+            args.push(OsString::from("--rust-module-root-file"));
+        }
+        if let Some(suffix) = &self.filename_suffix {
+            args.extend([OsString::from("--filename-suffix"), OsString::from(suffix)]);
+        }
+        if let Some(extension) = &self.filename_extension {
+            args.extend([OsString::from("--filename-ext"), OsString::from(extension)]);
+        }
+        if let Some(root_type) = &self.root_type {
+            args.extend([OsString::from("--root-type"), OsString::from(root_type)]);
+        }
+        if let Some(include_prefix) = &self.include_prefix {
+            args.extend([
+                OsString::from("--include-prefix"),
+                include_prefix.as_os_str().to_owned(),
+            ]);
+        }
+        args.extend([OsString::from("-o"), output_dir.as_os_str().to_owned()]);
+        args.extend(self.include_path_args());
+        args.extend(self.flatc_flags.iter().map(OsString::from));
+        args.extend(self.binary_schema_flags.iter().map(OsString::from));
+        args.extend(schemas.iter().map(|f| f.as_os_str().to_owned()));
+        args
+    }
+
+    /// Arguments for the `flatc` invocation generating binary schemas for `schemas` into
+    /// `output_dir`.
+    fn binary_schema_args(&self, schemas: &[PathBuf], output_dir: &Path) -> Vec<OsString> {
+        let mut args = vec![
+            OsString::from("--binary"),
+            OsString::from("--schema"),
+            OsString::from("-o"),
+            output_dir.as_os_str().to_owned(),
+        ];
+        args.extend(self.include_path_args());
+        args.extend(self.binary_schema_flags.iter().map(OsString::from));
+        args.extend(schemas.iter().map(|f| f.as_os_str().to_owned()));
+        args
+    }
+
+    /// Arguments for the `flatc` invocation generating JSON Schemas for `schemas` into
+    /// `output_dir`.
+    fn json_schema_args(&self, schemas: &[PathBuf], output_dir: &Path) -> Vec<OsString> {
+        let mut args = vec![
+            OsString::from("--jsonschema"),
+            OsString::from("-o"),
+            output_dir.as_os_str().to_owned(),
+        ];
+        if let Some(root_type) = &self.root_type {
+            args.extend([OsString::from("--root-type"), OsString::from(root_type)]);
+        }
+        args.extend(self.include_path_args());
+        args.extend(schemas.iter().map(|f| f.as_os_str().to_owned()));
+        args
+    }
+
+    /// The `flatc` to run, set up with the options affecting how it runs.
+    fn flatc(&self) -> Flatc {
+        let mut compiler = resolve_compiler(self.compiler.as_ref());
+        compiler.verbose |= self.verbose;
+        compiler.offline |= self.offline;
+        compiler.timeout = self.timeout;
+        compiler.directives = self.directives.clone();
+        compiler
+    }
+
+    /// The directory to cache generated code in, if any.
+    fn output_cache_dir(&self) -> Option<PathBuf> {
+        self.output_cache_dir
+            .clone()
+            .or_else(cache::cache_dir_from_env)
+    }
+
+    /// The URL of the remote cache, if any.
+    #[cfg(feature = "remote-cache")]
+    fn remote_cache(&self) -> Option<String> {
+        self.remote_cache
+            .clone()
+            .or_else(|| std::env::var(remote_cache::REMOTE_CACHE_ENV_VAR).ok())
+            .filter(|url| !url.is_empty())
+    }
+
+    /// These options with the schema directories of every dependency added with
+    /// [`Self::add_dependency_schemas`] turned into include paths.
+    fn with_dependency_schemas(&self) -> Result<BuilderOptions> {
+        let mut resolved = self.clone();
+        for dependency in std::mem::take(&mut resolved.dependency_schemas) {
+            resolved
+                .include_paths
+                .extend(dependency::schema_dirs(&dependency)?);
+        }
+        Ok(resolved)
+    }
+
+    /// `-I` arguments for every include path.
+    fn include_path_args(&self) -> Vec<OsString> {
+        self.include_paths
+            .iter()
+            .flat_map(|path| [OsString::from("-I"), path.as_os_str().to_owned()])
+            .collect()
+    }
+
+    fn add_flatc_flag(mut self, flag: &'static str) -> Self {
+        if !self.flatc_flags.contains(&flag) {
+            self.flatc_flags.push(flag);
+        }
+        self
+    }
+
+    fn add_binary_schema_flag(mut self, flag: &'static str) -> Self {
+        if !self.binary_schema_flags.contains(&flag) {
+            self.binary_schema_flags.push(flag);
+        }
+        self
+    }
+}
+
+fn compile(builder_options: &BuilderOptions) -> Result<Counters> {
+    let report_path = builder_options.report_path.clone();
+    let started = Instant::now();
+    let mut report = Report::default();
+    let result = run_compilation(builder_options, &mut report);
+    report.finish(&result, started.elapsed());
+    if builder_options.print_timings {
+        report.print_timings(&builder_options.directives);
+    }
+    if builder_options.print_counters {
+        report.print_counters(&builder_options.directives);
+    }
+    let result = match report_path {
+        // Failing to write the report shouldn't hide why compilation failed
+        Some(report_path) => result.and(report.write(&report_path)),
+        None => result,
+    };
+    result.map(|()| report.counters)
+}
+
+fn run_compilation(builder_options: &BuilderOptions, report: &mut Report) -> Result {
+    if !builder_options.dependency_schemas.is_empty() {
+        return run_compilation(&builder_options.with_dependency_schemas()?, report);
+    }
+    log::step!("validating input files {:?}", builder_options.files);
+    validate_inputs(&builder_options.files)?;
+    check_file_order(&builder_options.files, &builder_options.include_paths)?;
+    let layout = builder_options.output_layout();
+    let compiler = Flatc {
+        runs: Arc::clone(&report.flatc_runs),
+        ..builder_options.flatc()
+    };
+    let output_path = builder_options.resolve_output_path()?.into_os_string();
+    report.counters.schemas = builder_options.files.len();
+
+    let skip_env_var = builder_options.skip_env_var();
+    let output_dir = Path::new(&output_path);
+    let check_mode = builder_options.check_generated_code || check_mode_from_env();
+    // Held throughout with a shared output directory, so that only the first of the crates
+    // compiling the same schemas runs flatc and the others find its output up to date
+    let shared_lock = if builder_options.shared_output && skip_env_var.is_none() && !check_mode {
+        Some(output::lock_directory(output_dir)?)
+    } else {
+        None
+    };
+    // Check mode has to compare against what flatc generates, and a stub isn't generated code
+    let fingerprint = if skip_env_var.is_none() && !check_mode {
+        stamp::fingerprint(builder_options, &compiler)?
+    } else {
+        None
+    };
+    let stamp = fingerprint
+        .as_ref()
+        .and_then(|fingerprint| current_stamp(builder_options, &compiler, fingerprint, output_dir));
+    let up_to_date = stamp.is_some();
+    let mut kept_files = Vec::new();
+
+    // flatc writes into a staging directory first so we only touch output files that changed
+    let staging_dir = tempfile::tempdir().map_err(Error::StagingDirectoryFailure)?;
+    if let Some(stamp) = stamp {
+        log::step!("{} is up to date, skipping flatc", output_dir.display());
+        report.generated_files = stamp.files.into_keys().collect();
+        report.counters.cache_hits += 1;
+    } else if let Some(var) = skip_env_var {
+        kept_files = skip_compilation(
+            builder_options,
+            var,
+            &layout,
+            output_dir,
+            staging_dir.path(),
+        )?;
+        report.generated_files.clone_from(&kept_files);
+    } else {
+        generate_or_restore(
+            builder_options,
+            &compiler,
+            &layout,
+            fingerprint.as_ref(),
+            output_dir,
+            staging_dir.path(),
+            report,
+        )?;
+    }
+
+    if !up_to_date && kept_files.is_empty() {
+        log::step!("writing generated files to {}", output_dir.display());
+        let started = Instant::now();
+        let result = shared_lock
+            .map_or_else(|| output::lock_directory(output_dir), Ok)
+            .and_then(|_lock| {
+                sync_output(
+                    // A stub can't be compared against real generated code
+                    check_mode && skip_env_var.is_none(),
+                    fingerprint,
+                    &layout,
+                    staging_dir.path(),
+                    output_dir,
+                    report,
+                )
+            });
+        report.phase_secs.post_processing += started.elapsed().as_secs_f64();
+        result?;
+    }
+
+    if let Some(depfile_path) = &builder_options.depfile_path {
+        write_depfile(
+            builder_options,
+            depfile_path,
+            output_dir,
+            &report.generated_files,
+        )?;
+    }
+
+    // `include!` resolves relative paths against the including file, so only absolute ones work
+    let absolute_output_path =
+        std::path::absolute(&output_path).unwrap_or_else(|_| PathBuf::from(&output_path));
+    builder_options.directives.publish(
+        GENERATED_DIR_ENV_VAR,
+        GENERATED_DIR_METADATA_KEY,
+        &absolute_output_path,
+    );
+
+    if let Some(symlink_path) = &builder_options.symlink_path {
+        log::step!("linking {} to the output", symlink_path.display());
+        Phases::time(&mut report.phase_secs.symlink, || {
+            symlink_output(symlink_path, output_dir, &layout, &report.generated_files)
+        })?;
+        builder_options.directives.rerun_if_changed(symlink_path);
+    }
+
+    print_rerun_directives(builder_options);
+    Ok(())
+}
+
+/// Stands in for the generated code when compilation gets skipped because `var` is set. Code
+/// already in `output_dir`, e.g. committed to the repository, beats a stub, so that gets kept and
+/// its files returned. Otherwise a stub gets written into `staging_dir`.
+fn skip_compilation(
+    builder_options: &BuilderOptions,
+    var: &str,
+    layout: &schema::OutputLayout,
+    output_dir: &Path,
+    staging_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let kept_files = output::existing_files(output_dir)?;
+    if kept_files.is_empty() {
+        log::warning!(
+            builder_options.directives,
+            "{var} is set, writing stub output instead of running flatc"
+        );
+        output::write_stub(layout, &builder_options.files, staging_dir)?;
+    } else {
+        log::warning!(
+            builder_options.directives,
+            "{var} is set, keeping the code in {} instead of running flatc",
+            output_dir.display()
+        );
+    }
+    Ok(kept_files)
+}
+
+/// The stamp of the code in `output_dir`, if that code is what compiling `builder_options` would
+/// generate as described by `fingerprint`. With [`BuilderOptions::reuse_output_without_flatc`]
+/// and no `flatc` to be found, code generated by any `flatc` does.
+fn current_stamp(
+    builder_options: &BuilderOptions,
+    compiler: &Flatc,
+    fingerprint: &stamp::Fingerprint,
+    output_dir: &Path,
+) -> Option<stamp::Stamp> {
+    if let Some(stamp) = stamp::Stamp::read_current(output_dir, fingerprint) {
+        return Some(stamp);
+    }
+    if !builder_options.reuse_output_without_flatc || compiler.is_available() {
+        return None;
+    }
+    let stamp = stamp::Stamp::read(output_dir).filter(|stamp| {
+        stamp.fingerprint.options == fingerprint.options
+            && stamp.fingerprint.inputs == fingerprint.inputs
+    })?;
+    log::warning!(
+        builder_options.directives,
+        "flatc can't be found, using the code in {} generated from the same schemas before",
+        output_dir.display()
+    );
+    Some(stamp)
+}
+
+/// Writes the dependency file set with [`BuilderOptions::set_depfile_path`] for the
+/// `generated_files` in `output_dir`.
+fn write_depfile(
+    builder_options: &BuilderOptions,
+    depfile_path: &Path,
+    output_dir: &Path,
+    generated_files: &[PathBuf],
+) -> Result {
+    log::step!("writing dependency file {}", depfile_path.display());
+    let outputs: Vec<_> = generated_files
+        .iter()
+        .map(|file| output_dir.join(file))
+        .collect();
+    let dependencies =
+        depfile::schema_dependencies(&builder_options.files, &builder_options.include_paths)?;
+    depfile::write(depfile_path, &outputs, &dependencies)
+}
+
+/// Tells cargo to run the build script again whenever anything the compilation depends on
+/// changes.
+fn print_rerun_directives(builder_options: &BuilderOptions) {
+    let directives = &builder_options.directives;
+    directives.rerun_if_env_changed(CHECK_ENV_VAR);
+    directives.rerun_if_env_changed(flatc::VERBOSE_ENV_VAR);
+    directives.rerun_if_env_changed(flatc::OFFLINE_ENV_VAR);
+    directives.rerun_if_env_changed(cache::CACHE_DIR_ENV_VAR);
+    if builder_options.format_generated_code {
+        directives.rerun_if_env_changed(postprocess::RUSTFMT_ENV_VAR);
+    }
+    #[cfg(feature = "remote-cache")]
+    directives.rerun_if_env_changed(remote_cache::REMOTE_CACHE_ENV_VAR);
+    for var in &builder_options.skip_env_vars {
+        directives.rerun_if_env_changed(var);
+    }
+    if let Some(baseline) = &builder_options.conform_baseline {
+        directives.rerun_if_changed(baseline);
+    }
+    for file in builder_options
+        .files
+        .iter()
+        .chain(&builder_options.rerun_paths)
+    {
+        directives.rerun_if_changed(file);
+    }
+}
+
+/// Brings the output directory in line with the generated code in `staging_dir`, or checks that
+/// it already is when `check_only` is set. With a `fingerprint`, a stamp recording what the code
+/// was generated from gets written too. Expects the output directory to be locked already.
+fn sync_output(
+    check_only: bool,
+    fingerprint: Option<stamp::Fingerprint>,
+    layout: &schema::OutputLayout,
+    staging_dir: &Path,
+    output_path: &Path,
+    report: &mut Report,
+) -> Result {
+    if check_only {
+        report.generated_files = output::list_files(staging_dir)?;
+        let files = output::diff_directory(staging_dir, output_path)?;
+        if !files.is_empty() {
+            return Err(Error::OutdatedGeneratedCode {
+                output_path: output_path.into(),
+                files,
+            });
+        }
+    } else {
+        let summary = output::sync_directory(staging_dir, output_path)?;
+        if !summary.removed.is_empty() {
+            log::step!("removed files no longer generated: {:?}", summary.removed);
+        }
+        for file in &summary.written {
+            report.counters.bytes_written += std::fs::metadata(output_path.join(file))
+                .map_err(|source| Error::OutputWriteFailure {
+                    path: output_path.join(file),
+                    source,
+                })?
+                .len();
+        }
+        report.generated_files = [summary.written, summary.unchanged].concat();
+        report.generated_files.sort();
+        if let Some(fingerprint) = fingerprint {
+            stamp::Stamp::new(fingerprint, layout, output_path, &report.generated_files)?
+                .write(output_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Points `symlink_path` at the output directory, or at the generated file itself when there's
+/// only one.
+fn symlink_output(
+    symlink_path: &Path,
+    output_path: &Path,
+    layout: &schema::OutputLayout,
+    generated_files: &[PathBuf],
+) -> Result {
+    let mut symlink_target = output_path.to_path_buf();
+    if layout.one_file {
+        let generated_code: Vec<_> = generated_files
+            .iter()
+            .filter(|f| layout.is_generated_code(f))
+            .collect();
+        if let [generated_file] = &generated_code[..] {
+            symlink_target.push(generated_file);
+        }
+    }
+    generate_symlink(symlink_path, symlink_target)
+}
+
+/// Checks that every input exists, is readable, is a schema and shows up only once, so that
+/// mistakes in the list of inputs get reported as such rather than as `flatc` failing.
+fn validate_inputs(files: &[PathBuf]) -> Result {
+    let mut seen = HashSet::new();
+    for file in files {
+        if !file
+            .extension()
+            .is_some_and(|ext| ext == "fbs" || ext == "proto")
+        {
+            return Err(Error::InvalidInputExtension(file.clone()));
+        }
+        let canonical = std::fs::canonicalize(file).map_err(|source| {
+            if source.kind() == std::io::ErrorKind::NotFound {
+                Error::MissingInputFile(file.clone())
+            } else {
+                Error::SchemaReadFailure {
+                    path: file.clone(),
+                    source,
+                }
+            }
+        })?;
+        std::fs::File::open(&canonical)
+            .and_then(|f| {
+                if f.metadata()?.is_dir() {
+                    Err(std::io::ErrorKind::IsADirectory.into())
+                } else {
+                    Ok(())
+                }
+            })
+            .map_err(|source| Error::SchemaReadFailure {
+                path: file.clone(),
+                source,
+            })?;
+        if !seen.insert(canonical) {
+            return Err(Error::DuplicateInput(file.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that no schema among `files` comes before a schema it includes, which is the ordering
+/// `flatc` needs to generate a usable `mod.rs`. Schemas including each other have no such order,
+/// so any order of them passes. Expects `files` to have been validated already.
+fn check_file_order(files: &[PathBuf], include_paths: &[PathBuf]) -> Result {
+    let canonical: Vec<_> = files
+        .iter()
+        .map(|file| std::fs::canonicalize(file).unwrap_or_else(|_| file.clone()))
+        .collect();
+    // Indices of the inputs each input includes
+    let mut dependencies = Vec::with_capacity(files.len());
+    for file in files {
+        let includes = if proto::is_proto(file) {
+            Vec::new()
+        } else {
+            schema::Schema::from_file(file)?.includes
+        };
+        dependencies.push(
+            includes
+                .iter()
+                .filter_map(|include| schema::resolve_include(file, include, include_paths))
+                .filter_map(|include| canonical.iter().position(|c| *c == include))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    let Some((file, include)) = dependencies
+        .iter()
+        .enumerate()
+        .find_map(|(idx, deps)| deps.iter().find(|&&dep| dep > idx).map(|&dep| (idx, dep)))
+    else {
+        return Ok(());
+    };
+
+    let mut visited = vec![false; files.len()];
+    let mut order = Vec::with_capacity(files.len());
+    for idx in 0..files.len() {
+        visit_dependencies_first(idx, &dependencies, &mut visited, &mut order);
+    }
+    // Schemas including each other can't all come after the ones they include whatever the
+    // order, so there's no better one to suggest and `flatc` has to make do with the given one
+    let mut position = vec![0; files.len()];
+    for (pos, &idx) in order.iter().enumerate() {
+        position[idx] = pos;
+    }
+    if dependencies
+        .iter()
+        .enumerate()
+        .any(|(idx, deps)| deps.iter().any(|&dep| position[dep] > position[idx]))
+    {
+        return Ok(());
+    }
+
+    Err(Error::IncorrectFileOrdering {
+        file: files[file].clone(),
+        include: files[include].clone(),
+        suggested_order: order.into_iter().map(|idx| files[idx].clone()).collect(),
+    })
+}
+
+/// Depth-first topological sort step, pushing `idx` onto `order` after everything it depends on
+/// while keeping the original order where the dependencies allow it.
+fn visit_dependencies_first(
+    idx: usize,
+    dependencies: &[Vec<usize>],
+    visited: &mut [bool],
+    order: &mut Vec<usize>,
+) {
+    if visited[idx] {
+        return;
+    }
+    visited[idx] = true;
+    for &dep in &dependencies[idx] {
+        visit_dependencies_first(dep, dependencies, visited, order);
+    }
+    order.push(idx);
+}
+
+/// Generates the Rust code for `schemas` into `staging_dir`, checking the diagnostics `flatc`
+/// prints along the way.
+fn generate_rust(
+    builder_options: &BuilderOptions,
+    compiler: &Flatc,
+    schemas: &[PathBuf],
+    staging_dir: &Path,
+    report: &mut Report,
+) -> Result {
+    let output = run_flatc(compiler, builder_options.rust_args(schemas, staging_dir))?;
+    report
+        .diagnostics
+        .extend(diagnostic::parse(&output.stdout, &output.stderr));
+    if builder_options
+        .flatc_flags
+        .contains(&WARNINGS_AS_ERRORS_FLAG)
+    {
+        let warnings = output.warnings();
+        if !warnings.is_empty() {
+            return Err(Error::FlatcWarnings(warnings));
+        }
+    }
+    Ok(())
+}
+
+/// Generates the code for `builder_options` into `staging_dir`, unless the cache set with
+/// [`BuilderOptions::set_output_cache_dir`] or the remote one has it already, in which case it's
+/// restored from there. When only some of the inputs changed since the code in `output_dir` got
+/// generated, only their code gets regenerated. Freshly generated code gets added to the caches.
+fn generate_or_restore(
+    builder_options: &BuilderOptions,
+    compiler: &Flatc,
+    layout: &schema::OutputLayout,
+    fingerprint: Option<&stamp::Fingerprint>,
+    output_dir: &Path,
+    staging_dir: &Path,
+    report: &mut Report,
+) -> Result {
+    // Without a fingerprint there's no telling whether cached code is still current
+    let digest = fingerprint.map(stamp::Fingerprint::digest);
+    let cache = digest.as_deref().zip(builder_options.output_cache_dir());
+    if let Some((digest, cache_dir)) = &cache {
+        if cache::restore(cache_dir, digest, staging_dir)? {
+            log::step!("restored the generated code from {}", cache_dir.display());
+            report.counters.cache_hits += 1;
+            return Ok(());
+        }
+    }
+    #[cfg(feature = "remote-cache")]
+    if let Some(digest) = &digest {
+        if remote_cache::fetch(builder_options, compiler.offline, digest, staging_dir) {
+            log::step!("fetched the generated code from the remote cache");
+            report.counters.cache_hits += 1;
+            store_in_cache(builder_options, cache.as_ref(), staging_dir);
+            return Ok(());
+        }
+    }
+
+    report.counters.cache_misses += 1;
+    log::step!("checking the version of flatc");
+    Phases::time(&mut report.phase_secs.version_check, || {
+        confirm_flatc_version(compiler)
+    })?;
+    let changes = fingerprint.and_then(|fingerprint| {
+        incremental::Changes::find(builder_options, layout, fingerprint, output_dir)
+    });
+    let started = Instant::now();
+    let post_processing_secs = report.phase_secs.post_processing;
+    let result = match changes {
+        Some(changes) => changes.regenerate(
+            builder_options,
+            compiler,
+            layout,
+            output_dir,
+            staging_dir,
+            report,
+        ),
+        None => generate_inputs(builder_options, compiler, layout, staging_dir, report),
+    };
+    // Post-processing the generated code happens along the way, but has a phase of its own
+    report.phase_secs.flatc += started.elapsed().as_secs_f64()
+        - (report.phase_secs.post_processing - post_processing_secs);
+    result?;
+
+    store_in_cache(builder_options, cache.as_ref(), staging_dir);
+    #[cfg(feature = "remote-cache")]
+    if let Some(digest) = &digest {
+        remote_cache::push(builder_options, compiler.offline, digest, staging_dir);
+    }
+    Ok(())
+}
+
+/// Adds the code in `staging_dir` to the `cache` directory under the fingerprint it's paired
+/// with, if there's one.
+fn store_in_cache(
+    builder_options: &BuilderOptions,
+    cache: Option<&(&str, PathBuf)>,
+    staging_dir: &Path,
+) {
+    let Some((fingerprint, cache_dir)) = cache else {
+        return;
+    };
+    // The cache only saves time, so there's no point failing the build over it
+    if let Err(e) = cache::store(cache_dir, fingerprint, staging_dir) {
+        log::warning!(
+            builder_options.directives,
+            "failed to cache the generated code in {}: {e}",
+            cache_dir.display()
+        );
+    }
+}
+
+/// Translates any `.proto` inputs and generates code for all of them into `staging_dir`.
+fn generate_inputs(
+    builder_options: &BuilderOptions,
+    compiler: &Flatc,
+    layout: &schema::OutputLayout,
+    staging_dir: &Path,
+    report: &mut Report,
+) -> Result {
+    let proto_dir = tempfile::tempdir().map_err(Error::StagingDirectoryFailure)?;
+    let schemas = proto::schemas_for_inputs(compiler, &builder_options.files, proto_dir.path())?;
+    generate(
+        builder_options,
+        compiler,
+        &schemas,
+        layout,
+        staging_dir,
+        report,
+    )
+}
+
+fn generate(
+    builder_options: &BuilderOptions,
+    compiler: &Flatc,
+    schemas: &[PathBuf],
+    layout: &schema::OutputLayout,
+    staging_dir: &Path,
+    report: &mut Report,
+) -> Result {
+    if let Some(baseline) = &builder_options.conform_baseline {
+        conform::run_conformance_check(
+            compiler,
+            baseline,
+            &builder_options.conform_include_paths,
+            schemas,
+        )?;
+    }
+
+    // The binary and JSON schemas don't depend on the Rust code, so flatc can generate all of
+    // them at once
+    std::thread::scope(|scope| {
+        let binary_schemas = builder_options.generate_binary_schemas.then(|| {
+            scope.spawn(|| {
+                run_flatc(
+                    compiler,
+                    builder_options.binary_schema_args(schemas, staging_dir),
+                )
+            })
+        });
+        let json_schemas = builder_options.generate_json_schemas.then(|| {
+            scope.spawn(|| {
+                run_flatc(
+                    compiler,
+                    builder_options.json_schema_args(schemas, staging_dir),
+                )
+            })
+        });
+        generate_rust(builder_options, compiler, schemas, staging_dir, report)?;
+        for handle in [binary_schemas, json_schemas].into_iter().flatten() {
+            handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+        }
+        Ok::<_, Error>(())
+    })?;
+    if builder_options.generate_prelude {
+        prelude::write(builder_options, schemas, layout, staging_dir)?;
+    }
+    if builder_options.generate_aliases {
+        aliases::write(builder_options, schemas, layout, staging_dir)?;
+    }
+    log::step!("post-processing the generated code");
+    Phases::time(&mut report.phase_secs.post_processing, || {
+        postprocess::run(builder_options, schemas, layout, staging_dir)
+    })?;
+    if builder_options.generate_manifest {
+        manifest::Manifest::new(
+            expected_flatc_version(compiler),
+            &builder_options.files,
+            schemas,
+            staging_dir,
+            layout,
+        )?
+        .write(staging_dir.join(manifest::MANIFEST_FILE_NAME))?;
+    }
+    Ok(())
+}
+
+/// Figures out which `flatc` to run: the one explicitly requested if any, otherwise the WASI
+/// module in `FLATC_WASM` (with the `wasi` feature), then the one we vendored, then whatever's in
+/// `FLATC_PATH`, and finally `flatc` from the `PATH`.
+pub(crate) fn resolve_compiler(compiler: Option<&Backend>) -> Flatc {
+    if let Some(compiler) = compiler {
+        return Flatc::new(compiler.clone());
+    }
+    #[cfg(feature = "wasi")]
+    if let Some(module) = std::env::var_os(WASM_ENV_VAR) {
+        return Flatc::new(Backend::Wasi(module.into()));
+    }
+    Flatc::new(Backend::Native(
+        if let Some(build_flatc) = FLATC_BUILD_PATH {
+            build_flatc.to_owned()
+        } else {
+            std::env::var("FLATC_PATH").unwrap_or("flatc".into())
+        },
+    ))
+}
+
+fn check_mode_from_env() -> bool {
+    std::env::var(CHECK_ENV_VAR).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+fn generate_symlink<P: AsRef<Path>, Q: AsRef<Path>>(symlink_path: P, output_path: Q) -> Result {
+    let (symlink_path, output_path) = (symlink_path.as_ref(), output_path.as_ref());
+    // `exists()` follows the link, so it misses links left dangling by a `cargo clean`
+    if let Ok(metadata) = std::fs::symlink_metadata(symlink_path) {
+        if metadata.file_type().is_symlink() {
+            let target = std::fs::read_link(symlink_path).map_err(Error::SymlinkCreationFailure)?;
+            if target == output_path && target.exists() {
+                return Ok(());
+            }
+            // Either dangling, or pointing at the output of an older build, e.g. one in an
+            // `OUT_DIR` with a different hash
+            log::step!(
+                "re-pointing {} from {} to {}",
+                symlink_path.display(),
+                target.display(),
+                output_path.display()
+            );
+        }
+        remove_symlink(symlink_path, &metadata).map_err(Error::SymlinkCreationFailure)?;
+    }
+    create_symlink(output_path, symlink_path).map_err(Error::SymlinkCreationFailure)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, symlink_path: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, symlink_path)
+}
+
+/// Windows has separate links for directories and files, so the target has to exist already.
+#[cfg(windows)]
+fn create_symlink(target: &Path, symlink_path: &Path) -> std::io::Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, symlink_path)
+    } else {
+        std::os::windows::fs::symlink_file(target, symlink_path)
+    }
+}
+
+#[cfg(unix)]
+fn remove_symlink(symlink_path: &Path, _metadata: &std::fs::Metadata) -> std::io::Result<()> {
+    std::fs::remove_file(symlink_path)
+}
+
+/// Links to directories get removed like directories on Windows, not like files.
+#[cfg(windows)]
+fn remove_symlink(symlink_path: &Path, metadata: &std::fs::Metadata) -> std::io::Result<()> {
+    use std::os::windows::fs::FileTypeExt;
+    if metadata.file_type().is_symlink_dir() {
+        std::fs::remove_dir(symlink_path)
+    } else {
+        std::fs::remove_file(symlink_path)
+    }
+}
+
+/// The version of `flatc` we expect `compiler` to be. That's [`SUPPORTED_FLATC_VERSION`], unless
+/// it's the binary we vendored, which might be a different release.
+fn expected_flatc_version(compiler: &Flatc) -> &'static str {
+    match (&compiler.backend, FLATC_BUILD_PATH, FLATC_VENDORED_VERSION) {
+        (Backend::Native(compiler), Some(build_flatc), Some(version))
+            if build_flatc == compiler =>
+        {
+            version
+        }
+        _ => SUPPORTED_FLATC_VERSION,
+    }
+}
+
+pub(crate) fn confirm_flatc_version(compiler: &Flatc) -> Result {
+    // Output shows up in stdout
+    let output = run_flatc(compiler, ["--version"])?;
+    if output.stdout.starts_with(FLATC_VERSION_PREFIX) {
+        let version_str = output.stdout[FLATC_VERSION_PREFIX.len()..].trim_end();
+        if version_str == expected_flatc_version(compiler) {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedFlatcVersion(version_str.into()))
+        }
+    } else {
+        Err(Error::InvalidFlatcOutput(output.stdout))
+    }
+}
+
+pub(crate) struct ProgramOutput {
     pub stdout: String,
-    pub _stderr: String,
+    pub stderr: String,
+}
+
+impl ProgramOutput {
+    /// Lines in the output of `flatc` that contain a warning.
+    fn warnings(&self) -> Vec<String> {
+        self.stderr
+            .lines()
+            .chain(self.stdout.lines())
+            .filter(|line| line.contains(FLATC_WARNING_MARKER))
+            .map(|line| line.trim().to_owned())
+            .collect()
+    }
 }
 
-fn run_flatc<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
-    compiler: &str,
+pub(crate) fn run_flatc<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
+    compiler: &Flatc,
     args: I,
 ) -> Result<ProgramOutput> {
-    let output = Command::new(compiler)
-        .args(args)
-        .output()
-        .map_err(Error::FlatcSpawnFailure)?;
-    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
-    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
-    if output.status.success() {
-        Ok(ProgramOutput {
-            stdout,
-            _stderr: stderr,
-        })
+    let started = Instant::now();
+    let (status_code, stdout, stderr) = match &compiler.backend {
+        Backend::Native(program) => {
+            let mut command = Command::new(program);
+            command.args(args);
+            log::step!("running {command:?}");
+            if compiler.verbose {
+                log_command(&compiler.directives, &command);
+            }
+            let output = command_output(command, compiler.timeout)?;
+            (output.status.code(), output.stdout, output.stderr)
+        }
+        Backend::Container(image) => {
+            let args = args.into_iter().map(|a| a.as_ref().to_owned()).collect();
+            let command = container::command(image, args, compiler.offline)
+                .map_err(Error::FlatcSpawnFailure)?;
+            log::step!("running {command:?}");
+            if compiler.verbose {
+                log_command(&compiler.directives, &command);
+            }
+            let output = command_output(command, compiler.timeout)?;
+            (output.status.code(), output.stdout, output.stderr)
+        }
+        #[cfg(feature = "wasi")]
+        Backend::Wasi(module) => {
+            let args: Vec<_> = args.into_iter().map(|a| a.as_ref().to_owned()).collect();
+            log::step!("running WASI module {} with {args:?}", module.display());
+            if compiler.verbose {
+                log::notice!(
+                    compiler.directives,
+                    "running WASI module {} with arguments {args:?}",
+                    module.display()
+                );
+            }
+            let output = wasi::run(module, args, compiler.timeout)?;
+            (output.status_code, output.stdout, output.stderr)
+        }
+    };
+    compiler.runs.record(started.elapsed());
+    let stdout = String::from_utf8_lossy(&stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&stderr).into_owned();
+    if status_code == Some(0) {
+        Ok(ProgramOutput { stdout, stderr })
     } else {
         Err(Error::FlatcErrorCode {
-            status_code: output.status.code(),
+            status_code,
+            diagnostics: diagnostic::parse(&stdout, &stderr),
             stdout,
             stderr,
         })
     }
 }
+
+/// Runs `command` to completion like [`Command::output`] does, but kills it and fails with
+/// [`Error::Timeout`] if it's still running after `timeout`.
+fn command_output(mut command: Command, timeout: Option<Duration>) -> Result<Output> {
+    let Some(timeout) = timeout else {
+        return command.output().map_err(Error::FlatcSpawnFailure);
+    };
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(Error::FlatcSpawnFailure)?;
+    // Drain both pipes as we go, or flatc could block forever on a full one
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(Error::FlatcSpawnFailure)? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(Error::Timeout(timeout));
+        }
+        std::thread::sleep(TIMEOUT_POLL_INTERVAL);
+    };
+    let collect = |reader: Option<JoinHandle<Vec<u8>>>| {
+        reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default()
+    };
+    Ok(Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    })
+}
+
+/// Reads `pipe` to the end on a separate thread.
+fn read_in_background<R: Read + Send + 'static>(mut pipe: R) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut contents = Vec::new();
+        let _ = pipe.read_to_end(&mut contents);
+        contents
+    })
+}
+
+/// Prints the fully resolved command line of `command`, along with the environment variables
+/// that affect how `flatc` gets picked and run.
+fn log_command(directives: &Directives, command: &Command) {
+    let program = command.get_program();
+    match resolve_program(program) {
+        Some(resolved) => log::notice!(directives, "resolved compiler: {}", resolved.display()),
+        None => log::notice!(
+            directives,
+            "resolved compiler: {} (not found in PATH)",
+            program.to_string_lossy()
+        ),
+    }
+    let args: Vec<_> = command.get_args().collect();
+    log::notice!(directives, "running with arguments {args:?}");
+    for (name, value) in std::env::vars_os() {
+        let name = name.to_string_lossy();
+        if name == "PATH" || name.starts_with("FLATC_") || name.starts_with("FLATBUFFERS_BUILD_") {
+            log::notice!(directives, "  {name}={}", value.to_string_lossy());
+        }
+    }
+}
+
+/// Where `program` is, looking it up in the `PATH` unless it's a path already.
+pub(crate) fn resolve_program(program: &OsStr) -> Option<PathBuf> {
+    if Path::new(program).components().count() > 1 {
+        Some(PathBuf::from(program))
+    } else {
+        std::env::var_os("PATH").and_then(|path| {
+            std::env::split_paths(&path)
+                .map(|dir| dir.join(program))
+                .find(|candidate| candidate.is_file())
+        })
+    }
+}
+
+/// Body of the [`Error::FlatcErrorCode`] message: the diagnostics `flatc` reported, or its raw
+/// output if we couldn't make out any.
+fn flatc_error_details(diagnostics: &[Diagnostic], stdout: &str, stderr: &str) -> String {
+    if diagnostics.is_empty() {
+        format!("-- stdout:\n{stdout}\n-- stderr:\n{stderr}\n")
+    } else {
+        diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_schemas(dir: &Path, schemas: &[(&str, &str)]) -> Vec<PathBuf> {
+        schemas
+            .iter()
+            .map(|(name, contents)| {
+                let path = dir.join(name);
+                std::fs::write(&path, contents).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_file_order_rejects_schema_before_its_include() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = write_schemas(
+            dir.path(),
+            &[
+                ("example.fbs", "include \"weapon.fbs\";\ntable Monster {}\n"),
+                ("weapon.fbs", "table Weapon {}\n"),
+            ],
+        );
+
+        let Err(Error::IncorrectFileOrdering {
+            file,
+            include,
+            suggested_order,
+        }) = check_file_order(&files, &[])
+        else {
+            panic!("expected an ordering error");
+        };
+        assert_eq!(file, files[0]);
+        assert_eq!(include, files[1]);
+        assert_eq!(suggested_order, [files[1].clone(), files[0].clone()]);
+
+        let reordered = [files[1].clone(), files[0].clone()];
+        assert!(check_file_order(&reordered, &[]).is_ok());
+    }
+
+    #[test]
+    fn check_file_order_accepts_include_cycles_in_any_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = write_schemas(
+            dir.path(),
+            &[
+                ("a.fbs", "include \"b.fbs\";\ntable A {}\n"),
+                ("b.fbs", "include \"a.fbs\";\ntable B {}\n"),
+            ],
+        );
+
+        assert!(check_file_order(&files, &[]).is_ok());
+        let reversed = [files[1].clone(), files[0].clone()];
+        assert!(check_file_order(&reversed, &[]).is_ok());
+    }
+}
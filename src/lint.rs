@@ -0,0 +1,355 @@
+//! Lints for schemas, checking conventions `flatc` doesn't enforce but that keep schemas safe to
+//! evolve and the code generated from them idiomatic. Like [`crate::schema`], this works off the
+//! tokens of a schema rather than a full parse, and skips over anything it doesn't understand.
+
+use crate::{
+    schema::{self, Token},
+    Error, Result,
+};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// A convention checked by [`crate::BuilderOptions::lint_schemas`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LintRule {
+    /// A schema declares a `root_type` without a `file_identifier`, so buffers of it can't be
+    /// told apart from buffers of other schemas.
+    MissingFileIdentifier,
+    /// A table field has no `id` attribute, so its id depends on where it's declared, and
+    /// reordering the fields of the table breaks compatibility with existing buffers.
+    ImplicitFieldId,
+    /// A name doesn't follow the flatbuffers style guide: `UpperCamelCase` for namespaces, types
+    /// and enum values, `snake_case` for fields.
+    NamingConvention,
+}
+
+impl LintRule {
+    /// Every rule there is.
+    pub const ALL: [LintRule; 3] = [
+        LintRule::MissingFileIdentifier,
+        LintRule::ImplicitFieldId,
+        LintRule::NamingConvention,
+    ];
+
+    /// Name of the rule, in kebab-case.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            LintRule::MissingFileIdentifier => "missing-file-identifier",
+            LintRule::ImplicitFieldId => "implicit-field-id",
+            LintRule::NamingConvention => "naming-convention",
+        }
+    }
+
+    /// The rule with the kebab-case `name`, if there's one.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<LintRule> {
+        LintRule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A place where a schema breaks one of the [`LintRule`]s.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// The rule being broken.
+    pub rule: LintRule,
+    /// The schema breaking it.
+    pub file: PathBuf,
+    /// 1-based line number in [`Self::file`].
+    pub line: usize,
+    /// 1-based column, in characters, in [`Self::line`].
+    pub column: usize,
+    /// Length, in characters, of the part of the line breaking the rule, like the name of a field.
+    pub length: usize,
+    /// Description of the problem, with a suggested fix where there's one.
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {} [{}]",
+            self.file.display(),
+            self.line,
+            self.column,
+            self.message,
+            self.rule
+        )
+    }
+}
+
+/// Checks the schema at `path` against every rule.
+pub(crate) fn lint_file(path: &Path) -> Result<Vec<Lint>> {
+    let contents = std::fs::read_to_string(path).map_err(|source| Error::SchemaReadFailure {
+        path: path.into(),
+        source,
+    })?;
+    Ok(lint_source(path, &contents))
+}
+
+/// Checks `contents`, the text of the schema at `path`, against every rule.
+fn lint_source(path: &Path, contents: &str) -> Vec<Lint> {
+    let mut linter = Linter {
+        file: path,
+        tokens: &schema::tokenize(contents),
+        lints: Vec::new(),
+    };
+    linter.run();
+    linter.lints
+}
+
+struct Linter<'a> {
+    file: &'a Path,
+    tokens: &'a [Token],
+    lints: Vec<Lint>,
+}
+
+impl Linter<'_> {
+    fn run(&mut self) {
+        let tokens = self.tokens;
+        let mut root_type = None;
+        let mut has_file_identifier = false;
+        let mut idx = 0;
+        while let Some(token) = tokens.get(idx) {
+            match token.text.as_str() {
+                "namespace" => {
+                    idx += 1;
+                    while let Some(part) = tokens.get(idx).filter(|t| t.text != ";") {
+                        if part.text != "." {
+                            self.check_upper_camel_case(part, "namespace");
+                        }
+                        idx += 1;
+                    }
+                }
+                "root_type" => {
+                    root_type = Some(token);
+                    idx += 1;
+                }
+                "file_identifier" => {
+                    has_file_identifier = true;
+                    idx += 1;
+                }
+                "table" | "struct" => idx = self.table_or_struct(idx),
+                "enum" => idx = self.enumeration(idx),
+                "union" | "rpc_service" => {
+                    if let Some(name) = tokens.get(idx + 1) {
+                        self.check_upper_camel_case(name, &token.text);
+                    }
+                    idx = schema::skip_block(tokens, idx + 2);
+                }
+                "{" => idx = schema::skip_block(tokens, idx),
+                _ => idx += 1,
+            }
+        }
+
+        if let (Some(root_type), false) = (root_type, has_file_identifier) {
+            self.lint(
+                LintRule::MissingFileIdentifier,
+                root_type,
+                "schema declares a root_type without a file_identifier".into(),
+            );
+        }
+    }
+
+    /// Checks the table or struct declared at `idx`, and returns the index of the first token
+    /// after it.
+    fn table_or_struct(&mut self, idx: usize) -> usize {
+        let tokens = self.tokens;
+        let is_table = tokens[idx].text == "table";
+        let Some(name) = tokens.get(idx + 1) else {
+            return idx + 1;
+        };
+        self.check_upper_camel_case(name, &tokens[idx].text);
+        let Some(mut idx) = self.block_start(idx + 2) else {
+            return schema::skip_block(tokens, idx + 2);
+        };
+
+        // Fields look like `name: type = default (attribute: value, ...);`
+        while let Some(field) = tokens.get(idx).filter(|t| t.text != "}") {
+            let end = (idx..tokens.len())
+                .find(|&end| matches!(tokens[end].text.as_str(), ";" | "}"))
+                .unwrap_or(tokens.len());
+            self.check_snake_case(field, "field");
+            let attributes = tokens[idx..end]
+                .iter()
+                .position(|token| token.text == "(")
+                .map_or(&[][..], |start| &tokens[idx + start..end]);
+            let has_id = attributes
+                .windows(2)
+                .any(|pair| pair[0].text == "id" && pair[1].text == ":");
+            if is_table && !has_id {
+                self.lint(
+                    LintRule::ImplicitFieldId,
+                    field,
+                    format!(
+                        "field `{}` of table `{}` has no explicit id, add an `(id: N)` attribute",
+                        field.text, name.text
+                    ),
+                );
+            }
+            idx = if tokens.get(end).is_some_and(|t| t.text == ";") {
+                end + 1
+            } else {
+                end
+            };
+        }
+        idx + 1
+    }
+
+    /// Checks the enum declared at `idx`, and returns the index of the first token after it.
+    fn enumeration(&mut self, idx: usize) -> usize {
+        let tokens = self.tokens;
+        let Some(name) = tokens.get(idx + 1) else {
+            return idx + 1;
+        };
+        self.check_upper_camel_case(name, "enum");
+        let Some(mut idx) = self.block_start(idx + 2) else {
+            return schema::skip_block(tokens, idx + 2);
+        };
+
+        // Values look like `Name = 1,`, each name coming first or right after a comma
+        let mut expecting_value = true;
+        while let Some(token) = tokens.get(idx).filter(|t| t.text != "}") {
+            if expecting_value {
+                self.check_upper_camel_case(token, "enum value");
+            }
+            expecting_value = token.text == ",";
+            idx += 1;
+        }
+        idx + 1
+    }
+
+    /// Index of the first token inside the block of a declaration whose name is followed by the
+    /// token at `idx`, past any attributes or underlying type. `None` if it has no block.
+    fn block_start(&self, mut idx: usize) -> Option<usize> {
+        while let Some(token) = self.tokens.get(idx) {
+            match token.text.as_str() {
+                "{" => return Some(idx + 1),
+                ";" | "}" => return None,
+                _ => idx += 1,
+            }
+        }
+        None
+    }
+
+    fn check_upper_camel_case(&mut self, name: &Token, what: &str) {
+        let first = name.text.chars().next();
+        if first.is_some_and(|c| c.is_ascii_lowercase()) || name.text.contains('_') {
+            self.lint(
+                LintRule::NamingConvention,
+                name,
+                format!(
+                    "{what} `{}` should be UpperCamelCase, like `{}`",
+                    name.text,
+                    to_upper_camel_case(&name.text)
+                ),
+            );
+        }
+    }
+
+    fn check_snake_case(&mut self, name: &Token, what: &str) {
+        if name.text.chars().any(|c| c.is_ascii_uppercase()) {
+            self.lint(
+                LintRule::NamingConvention,
+                name,
+                format!(
+                    "{what} `{}` should be snake_case, like `{}`",
+                    name.text,
+                    schema::to_snake_case(&name.text)
+                ),
+            );
+        }
+    }
+
+    fn lint(&mut self, rule: LintRule, token: &Token, message: String) {
+        self.lints.push(Lint {
+            rule,
+            file: self.file.into(),
+            line: token.line,
+            column: token.column,
+            length: token.text.chars().count(),
+            message,
+        });
+    }
+}
+
+/// Converts `snake_case` or `lowerCamelCase` into `UpperCamelCase`.
+fn to_upper_camel_case(input: &str) -> String {
+    input
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The rule, line and column of every lint for the schema `contents`.
+    fn lints(contents: &str) -> Vec<(LintRule, usize, usize)> {
+        lint_source(Path::new("schema.fbs"), contents)
+            .into_iter()
+            .map(|lint| (lint.rule, lint.line, lint.column))
+            .collect()
+    }
+
+    #[test]
+    fn missing_file_identifier() {
+        assert_eq!(
+            lints("table Monster {}\nroot_type Monster;\n"),
+            [(LintRule::MissingFileIdentifier, 2, 1)]
+        );
+        assert!(
+            lints("table Monster {}\nroot_type Monster;\nfile_identifier \"MONS\";\n").is_empty()
+        );
+        assert!(lints("table Monster {}\n").is_empty());
+    }
+
+    #[test]
+    fn implicit_field_id() {
+        assert_eq!(
+            lints("table Monster {\n  hp: short = 100;\n  mana: short (id: 1);\n}\n"),
+            [(LintRule::ImplicitFieldId, 2, 3)]
+        );
+        assert!(lints("table Monster {\n  hp: short = 100 (id: 0, deprecated);\n}\n").is_empty());
+        // Struct fields can't be reordered anyway, so they take no ids
+        assert!(lints("struct Vec3 {\n  x: float;\n  y: float;\n}\n").is_empty());
+    }
+
+    #[test]
+    fn naming_convention() {
+        assert_eq!(
+            lints(
+                "namespace my_game;\ntable monster {\n  maxHp: short (id: 0);\n}\n\
+                 enum Color: byte { red, Green }\n"
+            ),
+            [
+                (LintRule::NamingConvention, 1, 11),
+                (LintRule::NamingConvention, 2, 7),
+                (LintRule::NamingConvention, 3, 3),
+                (LintRule::NamingConvention, 5, 20),
+            ]
+        );
+        assert!(lints(
+            "namespace MyGame.Sample;\ntable Monster {\n  max_hp: short (id: 0);\n}\n\
+             enum Color: byte { Red, Green }\nunion Equipment { Monster }\n"
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+//! Reporting of what happens during compilation. By default, anything worth showing gets printed
+//! as a cargo warning, which is the only way for a build script to get a message in front of the
+//! user, unless the [`crate::directives::Directives`] passed in turn warnings off. With the
+//! `tracing` feature, everything gets emitted as `tracing` events instead, so it can be routed
+//! through whatever subscriber the application has set up.
+
+/// Reports something that's likely a problem.
+macro_rules! warning {
+    ($directives:expr, $($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        {
+            let _ = &$directives;
+            tracing::warn!($($arg)*);
+        }
+        #[cfg(not(feature = "tracing"))]
+        $directives.warning(format_args!($($arg)*));
+    }};
+}
+
+/// Reports something the user explicitly asked to see, like verbose output or timings.
+macro_rules! notice {
+    ($directives:expr, $($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        {
+            let _ = &$directives;
+            tracing::info!($($arg)*);
+        }
+        #[cfg(not(feature = "tracing"))]
+        $directives.warning(format_args!($($arg)*));
+    }};
+}
+
+/// Reports a step of the compilation. These only go anywhere with the `tracing` feature, as
+/// printing them as cargo warnings would bury actual warnings.
+macro_rules! step {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
+
+pub(crate) use {notice, step, warning};
@@ -0,0 +1,132 @@
+//! Generation of the `manifest.json` file describing what a compilation produced. This is meant
+//! for external build systems and caching layers that need to map input schemas to the files
+//! generated from them.
+
+use crate::{schema::OutputLayout, Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+/// Name of the manifest file written at the root of the output directory.
+pub(crate) const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    pub flatc_version: String,
+    pub schemas: Vec<SchemaEntry>,
+    pub generated_files: Vec<GeneratedFileEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SchemaEntry {
+    /// Path of the schema, relative to the directory of the crate when it's in there.
+    pub path: PathBuf,
+    pub sha256: String,
+    /// Generated files produced from the types declared in this schema, relative to the output
+    /// directory.
+    pub generated_files: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct GeneratedFileEntry {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Builds the manifest for a compilation of `inputs` that produced the files under
+    /// `generated_dir`, laid out according to `layout`. `schemas` holds the flatbuffer schema that
+    /// got compiled for each input, which only differs from it for translated `.proto` files.
+    pub fn new<P: AsRef<Path>>(
+        flatc_version: &str,
+        inputs: &[PathBuf],
+        schemas: &[PathBuf],
+        generated_dir: P,
+        layout: &OutputLayout,
+    ) -> Result<Manifest> {
+        let generated_dir = generated_dir.as_ref();
+        let current_dir = std::env::current_dir().ok();
+        let generated_paths: BTreeSet<_> = crate::output::list_files(generated_dir)?
+            .into_iter()
+            .collect();
+
+        let schemas = inputs
+            .iter()
+            .zip(schemas)
+            .map(|(path, schema)| {
+                let sha256 = hash_file(path).map_err(|source| Error::SchemaReadFailure {
+                    path: path.clone(),
+                    source,
+                })?;
+                let mut generated_files = layout.generated_files_for(schema)?;
+                generated_files.retain(|file| generated_paths.contains(file));
+                generated_files.dedup();
+                let path = current_dir
+                    .as_deref()
+                    .and_then(|current_dir| path.strip_prefix(current_dir).ok())
+                    .unwrap_or(path);
+                Ok(SchemaEntry {
+                    path: portable_path(path),
+                    sha256,
+                    generated_files: generated_files.iter().map(|f| portable_path(f)).collect(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let generated_files = generated_paths
+            .into_iter()
+            .map(|path| {
+                let full_path = generated_dir.join(&path);
+                let sha256 = hash_file(&full_path).map_err(|source| Error::OutputWriteFailure {
+                    path: full_path,
+                    source,
+                })?;
+                Ok(GeneratedFileEntry {
+                    path: portable_path(&path),
+                    sha256,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Manifest {
+            flatc_version: flatc_version.into(),
+            schemas,
+            generated_files,
+        })
+    }
+
+    /// Reads the manifest in `output_dir`, if it has one.
+    pub fn read(output_dir: &Path) -> Option<Manifest> {
+        let contents = std::fs::read(output_dir.join(MANIFEST_FILE_NAME)).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    /// Writes the manifest as pretty-printed JSON to `path`.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result {
+        crate::output::write_json(path.as_ref(), self)
+    }
+}
+
+/// `path` with `/` separators, so that the manifest is the same on every platform. Absolute paths
+/// are left as they are.
+fn portable_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.into();
+    }
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+        .into()
+}
+
+/// Computes the hex-encoded SHA-256 of the file at `path`, streaming it through the hash rather
+/// than reading it whole.
+pub(crate) fn hash_file<P: AsRef<Path>>(path: P) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
@@ -0,0 +1,238 @@
+//! Configuration of the compilation through a `[package.metadata.flatbuffers]` table in
+//! `Cargo.toml`, which keeps the list of schemas next to the rest of the package definition:
+//!
+//! ```toml
+//! [package.metadata.flatbuffers]
+//! schemas = ["schemas/weapon.fbs", "schemas/example.fbs"]
+//! include-dirs = ["schemas/common"]
+//! symlink-directory = "src/gen_flatbuffers"
+//! options = ["gen-onefile", "warnings-as-errors"]
+//! ```
+//!
+//! All paths are relative to the directory of the manifest. The same keys can also go at the top
+//! level of a file of their own, like a `flatbuffers-build.toml` read by the `cargo flatbuffers`
+//! command, with paths relative to the directory of that file.
+
+use crate::{BuilderOptions, Error, Result, Visibility};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Name of the manifest file in a package directory.
+pub(crate) const MANIFEST_FILE_NAME: &str = "Cargo.toml";
+
+/// Contents of the `[package.metadata.flatbuffers]` table.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Schemas to compile, in order. Entries can be glob patterns, whose matches get compiled in
+    /// alphabetical order.
+    pub schemas: Vec<String>,
+    #[serde(default)]
+    pub include_dirs: Vec<PathBuf>,
+    /// `links` names of dependencies whose exported schemas to include.
+    #[serde(default)]
+    pub dependency_schemas: Vec<String>,
+    pub output_path: Option<PathBuf>,
+    pub symlink_directory: Option<PathBuf>,
+    pub root_type: Option<String>,
+    pub filename_suffix: Option<String>,
+    pub filename_extension: Option<String>,
+    pub include_prefix: Option<PathBuf>,
+    /// Feature of the crate the object API gets compiled with.
+    pub object_api_feature: Option<String>,
+    /// Visibility of the generated items: `public`, `crate` or `super`.
+    pub visibility: Option<Visibility>,
+    /// Attributes to add to the generated module root.
+    #[serde(default)]
+    pub root_attributes: Vec<String>,
+    /// Attributes to add to every generated Rust file.
+    #[serde(default)]
+    pub file_attributes: Vec<String>,
+    /// Derives to add to the generated types, keyed by the pattern of the types to add them to.
+    #[serde(default)]
+    pub derives: BTreeMap<String, Vec<String>>,
+    /// Names of the builder options without arguments to turn on, in kebab-case.
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: Option<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    metadata: Option<Metadata>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    flatbuffers: Option<Config>,
+}
+
+/// Reads the `[package.metadata.flatbuffers]` table of the manifest at `manifest_path`, if it has
+/// one.
+pub(crate) fn read_config(manifest_path: &Path) -> Result<Option<Config>> {
+    let contents =
+        std::fs::read_to_string(manifest_path).map_err(|source| Error::ManifestReadFailure {
+            path: manifest_path.into(),
+            source,
+        })?;
+    let manifest: Manifest =
+        toml::from_str(&contents).map_err(|e| Error::InvalidCargoMetadata {
+            path: manifest_path.into(),
+            reason: e.to_string(),
+        })?;
+    Ok(manifest
+        .package
+        .and_then(|package| package.metadata)
+        .and_then(|metadata| metadata.flatbuffers))
+}
+
+/// Reads the configuration making up the whole of the file at `config_path`.
+pub(crate) fn read_config_file(config_path: &Path) -> Result<Config> {
+    let contents =
+        std::fs::read_to_string(config_path).map_err(|source| Error::ConfigReadFailure {
+            path: config_path.into(),
+            source,
+        })?;
+    toml::from_str(&contents).map_err(|e| Error::InvalidCargoMetadata {
+        path: config_path.into(),
+        reason: e.to_string(),
+    })
+}
+
+/// Turns `config`, read from the manifest at `manifest_path`, into builder options.
+pub(crate) fn builder_from_config(manifest_path: &Path, config: &Config) -> Result<BuilderOptions> {
+    let manifest_dir = manifest_path.parent().unwrap_or(Path::new(""));
+    let invalid = |reason: String| Error::InvalidCargoMetadata {
+        path: manifest_path.into(),
+        reason,
+    };
+
+    let mut files = Vec::new();
+    let mut rerun_paths = vec![manifest_path.to_path_buf()];
+    for schema in &config.schemas {
+        let pattern = manifest_dir.join(schema);
+        if !is_glob(schema) {
+            files.push(pattern);
+            continue;
+        }
+        let matches = glob::glob(&pattern.to_string_lossy())
+            .map_err(|e| invalid(format!("invalid schema pattern {schema}: {e}")))?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| invalid(format!("failed to expand schema pattern {schema}: {e}")))?;
+        if matches.is_empty() {
+            return Err(invalid(format!("schema pattern {schema} matched no files")));
+        }
+        files.extend(matches);
+        // Schemas added later on should get picked up too
+        rerun_paths.push(manifest_dir.join(glob_base(schema)));
+    }
+
+    let mut builder = BuilderOptions::new_with_files(files);
+    builder.rerun_paths = rerun_paths;
+    for include_dir in &config.include_dirs {
+        builder = builder.add_include_path(manifest_dir.join(include_dir));
+    }
+    for dependency in &config.dependency_schemas {
+        builder = builder.add_dependency_schemas(dependency);
+    }
+    if let Some(output_path) = &config.output_path {
+        builder = builder.set_output_path(manifest_dir.join(output_path));
+    }
+    if let Some(symlink_directory) = &config.symlink_directory {
+        builder = builder.set_symlink_directory(manifest_dir.join(symlink_directory));
+    }
+    if let Some(root_type) = &config.root_type {
+        builder = builder.set_root_type(root_type);
+    }
+    if let Some(suffix) = &config.filename_suffix {
+        builder = builder.set_filename_suffix(suffix);
+    }
+    if let Some(extension) = &config.filename_extension {
+        builder = builder.set_filename_extension(extension);
+    }
+    if let Some(include_prefix) = &config.include_prefix {
+        builder = builder.set_include_prefix(include_prefix);
+    }
+    if let Some(feature) = &config.object_api_feature {
+        builder = builder.set_object_api_feature(feature);
+    }
+    if let Some(visibility) = config.visibility {
+        builder = builder.set_visibility(visibility);
+    }
+    for attribute in &config.root_attributes {
+        builder = builder.add_root_attribute(attribute);
+    }
+    for attribute in &config.file_attributes {
+        builder = builder.add_file_attribute(attribute);
+    }
+    for (pattern, derives) in &config.derives {
+        for derive in derives {
+            builder = builder.add_derive(pattern, derive);
+        }
+    }
+    for option in &config.options {
+        builder = apply_option(builder, option)
+            .ok_or_else(|| invalid(format!("unknown option {option}")))?;
+    }
+    Ok(builder)
+}
+
+/// Turns on the builder option named `option`, if there's one by that name.
+fn apply_option(builder: BuilderOptions, option: &str) -> Option<BuilderOptions> {
+    Some(match option {
+        "keep-prefix" => builder.keep_prefix(),
+        "generate-binary-schemas" => builder.generate_binary_schemas(),
+        "generate-json-schemas" => builder.generate_json_schemas(),
+        "bfbs-gen-embed" => builder.bfbs_gen_embed(),
+        "natural-utf8" => builder.natural_utf8(),
+        "warnings-as-errors" => builder.warnings_as_errors(),
+        "no-warnings" => builder.no_warnings(),
+        "reflect-types" => builder.reflect_types(),
+        "reflect-names" => builder.reflect_names(),
+        "bfbs-comments" => builder.bfbs_comments(),
+        "bfbs-builtins" => builder.bfbs_builtins(),
+        "generate-manifest" => builder.generate_manifest(),
+        "generate-prelude" => builder.generate_prelude(),
+        "generate-aliases" => builder.generate_aliases(),
+        "gen-mutable" => builder.gen_mutable(),
+        "gen-name-strings" => builder.gen_name_strings(),
+        "gen-compare" => builder.gen_compare(),
+        "gen-onefile" => builder.gen_onefile(),
+        "gen-all" => builder.gen_all(),
+        "no-includes" => builder.no_includes(),
+        "force-defaults" => builder.force_defaults(),
+        "check-generated-code" => builder.check_generated_code(),
+        "format-generated-code" => builder.format_generated_code(),
+        "make-no-std" => builder.make_no_std(),
+        "supress-buildrs-directives" => builder.supress_buildrs_directives(),
+        "supress-rerun-directives" => builder.supress_rerun_directives(),
+        "supress-warning-directives" => builder.supress_warning_directives(),
+        "verbose" => builder.verbose(),
+        "offline" => builder.offline(),
+        "shared-output" => builder.shared_output(),
+        "reuse-output-without-flatc" => builder.reuse_output_without_flatc(),
+        "ignore-schema-formatting" => builder.ignore_schema_formatting(),
+        "print-timings" => builder.print_timings(),
+        "print-counters" => builder.print_counters(),
+        _ => return None,
+    })
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// The leading part of `pattern` without any wildcards in it.
+fn glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|component| !is_glob(&component.as_os_str().to_string_lossy()))
+        .collect()
+}
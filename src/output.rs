@@ -0,0 +1,390 @@
+//! Handling of the files `flatc` generates. Rather than letting `flatc` write straight into the
+//! output directory, we have it write into a staging directory and then only copy over the files
+//! whose contents actually changed. This keeps the modification times of untouched files intact,
+//! which in turn avoids needless recompilation of anything that depends on them.
+//!
+//! Some schemas generate Rust files of several megabytes, so generated files only ever get read a
+//! chunk at a time here: comparing, copying and hashing them takes the same memory whatever their
+//! size.
+
+use crate::{manifest, parallel, schema::OutputLayout, stamp, Error, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Contents of the files written in place of the generated code when compilation is skipped.
+const STUB_CONTENTS: &str =
+    "// Stub written by flatbuffers-build: flatc was not run for this build.\n";
+
+/// How much of a file gets read at once when comparing files.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Name of the module root file `flatc` generates when not in single-file mode.
+pub(crate) const MOD_ROOT_FILE_NAME: &str = "mod.rs";
+
+/// Summary of what happened when syncing a staging directory into the output directory. All paths
+/// are relative to the output directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SyncSummary {
+    /// Files that were either created or had their contents replaced.
+    pub written: Vec<PathBuf>,
+    /// Files that already existed with identical contents and were left untouched.
+    pub unchanged: Vec<PathBuf>,
+    /// Files that were no longer generated and got removed.
+    pub removed: Vec<PathBuf>,
+}
+
+/// Takes an exclusive lock on `output_dir`, held until the returned file is dropped. This keeps
+/// concurrent builds sharing an output directory from stepping on each other's writes. The lock
+/// file lives in the temporary directory rather than next to the output, so it never shows up
+/// among the generated files.
+pub(crate) fn lock_directory(output_dir: &Path) -> Result<File> {
+    let output_dir =
+        std::path::absolute(output_dir).map_err(|source| Error::OutputWriteFailure {
+            path: output_dir.into(),
+            source,
+        })?;
+    let digest = Sha256::digest(output_dir.as_os_str().as_encoded_bytes());
+    let lock_path = std::env::temp_dir().join(format!("flatbuffers-build-{digest:x}.lock"));
+    let lock = File::create(&lock_path)
+        .and_then(|file| file.lock().map(|()| file))
+        .map_err(|source| Error::OutputWriteFailure {
+            path: lock_path,
+            source,
+        })?;
+    Ok(lock)
+}
+
+/// Copies every file under `staging_dir` into `output_dir`, preserving the relative directory
+/// structure. Files that already exist in `output_dir` with identical contents are not rewritten,
+/// and files an earlier run generated into `output_dir` that aren't in `staging_dir` get removed,
+/// along with any directory that leaves empty, so that code of removed or renamed schemas and types
+/// doesn't linger. See [`previously_generated`] for which files those are.
+pub(crate) fn sync_directory<P: AsRef<Path>, Q: AsRef<Path>>(
+    staging_dir: P,
+    output_dir: Q,
+) -> Result<SyncSummary> {
+    let staging_dir = staging_dir.as_ref();
+    let output_dir = output_dir.as_ref();
+    let files = list_files(staging_dir)?;
+    let written = parallel::map(&files, parallel::default_jobs(), |relative_path| {
+        let staged_path = staging_dir.join(relative_path);
+        let output_path = output_dir.join(relative_path);
+        if same_contents(&staged_path, &output_path)? {
+            return Ok(false);
+        }
+        copy_file(&staged_path, &output_path)?;
+        Ok(true)
+    });
+
+    let mut summary = SyncSummary::default();
+    for (relative_path, written) in files.iter().cloned().zip(written) {
+        if written? {
+            summary.written.push(relative_path);
+        } else {
+            summary.unchanged.push(relative_path);
+        }
+    }
+
+    for relative_path in previously_generated(output_dir)? {
+        if files.binary_search(&relative_path).is_ok() {
+            continue;
+        }
+        let path = output_dir.join(&relative_path);
+        fs::remove_file(&path).map_err(|source| Error::OutputWriteFailure {
+            path: path.clone(),
+            source,
+        })?;
+        // Only succeeds on empty directories, which is the point
+        for dir in path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| *dir != output_dir)
+        {
+            if fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+        summary.removed.push(relative_path);
+    }
+    Ok(summary)
+}
+
+/// The files in `output_dir` other than the ones flatbuffers-build keeps track of it with, which
+/// don't come from the staging directory. Nothing if it doesn't exist yet.
+pub(crate) fn existing_files(output_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !output_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = list_files(output_dir)?;
+    files.retain(|f| {
+        f != Path::new(stamp::STAMP_FILE_NAME) && f != Path::new(manifest::MANIFEST_FILE_NAME)
+    });
+    Ok(files)
+}
+
+/// The files in `output_dir` that an earlier run recorded generating, in its stamp or its
+/// manifest. Anything else in there was put there by someone else and is none of our business, so
+/// without either record this is nothing.
+fn previously_generated(output_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut recorded = BTreeSet::new();
+    if let Some(stamp) = stamp::Stamp::read_unchecked(output_dir) {
+        recorded.extend(stamp.files.into_keys());
+    }
+    if let Some(manifest) = manifest::Manifest::read(output_dir) {
+        recorded.extend(manifest.generated_files.into_iter().map(|entry| entry.path));
+    }
+    let mut files = existing_files(output_dir)?;
+    files.retain(|file| recorded.contains(file));
+    Ok(files)
+}
+
+/// Recursively lists all files under `root`, returning their paths relative to `root` in sorted
+/// order.
+pub(crate) fn list_files<P: AsRef<Path>>(root: P) -> Result<Vec<PathBuf>> {
+    let root = root.as_ref();
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).map_err(|source| Error::OutputWriteFailure {
+            path: dir.clone(),
+            source,
+        })?;
+        for entry in entries {
+            let path = entry
+                .map_err(|source| Error::OutputWriteFailure {
+                    path: dir.clone(),
+                    source,
+                })?
+                .path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(relative_path) = path.strip_prefix(root) {
+                files.push(relative_path.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Copies every file under `from` into `to`, preserving the directory structure.
+pub(crate) fn copy_files(from: &Path, to: &Path) -> Result {
+    for file in list_files(from)? {
+        copy_file(&from.join(&file), &to.join(&file))?;
+    }
+    Ok(())
+}
+
+/// Copies the file at `from` to `to`, creating the directory it goes into if needed.
+pub(crate) fn copy_file(from: &Path, to: &Path) -> Result {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|source| Error::OutputWriteFailure {
+            path: parent.into(),
+            source,
+        })?;
+    }
+    fs::copy(from, to)
+        .map(|_| ())
+        .map_err(|source| Error::OutputWriteFailure {
+            path: to.into(),
+            source,
+        })
+}
+
+/// Whether the generated file at `staged_path` has the same contents as the file at
+/// `output_path`. An output file that can't be read, e.g. because it doesn't exist yet, counts as
+/// different.
+fn same_contents(staged_path: &Path, output_path: &Path) -> Result<bool> {
+    let read_failure = |source| Error::OutputWriteFailure {
+        path: staged_path.into(),
+        source,
+    };
+    let mut staged = File::open(staged_path).map_err(read_failure)?;
+    let Ok(mut existing) = File::open(output_path) else {
+        return Ok(false);
+    };
+    let staged_len = staged.metadata().map_err(read_failure)?.len();
+    if existing
+        .metadata()
+        .map_or(true, |metadata| metadata.len() != staged_len)
+    {
+        return Ok(false);
+    }
+
+    let mut staged_chunk = Vec::new();
+    let mut existing_chunk = Vec::new();
+    loop {
+        staged_chunk.clear();
+        existing_chunk.clear();
+        read_chunk(&mut staged, &mut staged_chunk).map_err(read_failure)?;
+        if read_chunk(&mut existing, &mut existing_chunk).is_err() {
+            return Ok(false);
+        }
+        if staged_chunk != existing_chunk {
+            return Ok(false);
+        }
+        if staged_chunk.is_empty() {
+            return Ok(true);
+        }
+    }
+}
+
+/// Reads up to [`CHUNK_SIZE`] bytes of `file` into `chunk`, fewer only at the end of the file.
+fn read_chunk(file: &mut File, chunk: &mut Vec<u8>) -> io::Result<usize> {
+    file.take(CHUNK_SIZE).read_to_end(chunk)
+}
+
+/// Compares the contents of `staging_dir` against `output_dir` without modifying anything.
+/// Returns the relative paths of every file that differs between them, including files missing
+/// from `output_dir` and files an earlier run generated into `output_dir` that would no longer be.
+pub(crate) fn diff_directory<P: AsRef<Path>, Q: AsRef<Path>>(
+    staging_dir: P,
+    output_dir: Q,
+) -> Result<Vec<PathBuf>> {
+    let staging_dir = staging_dir.as_ref();
+    let output_dir = output_dir.as_ref();
+    let staged_files = list_files(staging_dir)?;
+    let previous_files = previously_generated(output_dir)?;
+
+    let same = parallel::map(&staged_files, parallel::default_jobs(), |relative_path| {
+        same_contents(
+            &staging_dir.join(relative_path),
+            &output_dir.join(relative_path),
+        )
+    });
+    let mut differing = Vec::new();
+    for (relative_path, same) in staged_files.iter().zip(same) {
+        if !same? {
+            differing.push(relative_path.clone());
+        }
+    }
+    differing.extend(
+        previous_files
+            .into_iter()
+            .filter(|relative_path| staged_files.binary_search(relative_path).is_err()),
+    );
+    differing.sort();
+    Ok(differing)
+}
+
+/// Writes empty stand-ins for the generated code into `staging_dir`, for when compilation gets
+/// skipped. See [`stub_files`] for which files get written.
+pub(crate) fn write_stub(layout: &OutputLayout, inputs: &[PathBuf], staging_dir: &Path) -> Result {
+    for stub_file in stub_files(layout, inputs)? {
+        let path = staging_dir.join(stub_file);
+        fs::write(&path, STUB_CONTENTS)
+            .map_err(|source| Error::OutputWriteFailure { path, source })?;
+    }
+    Ok(())
+}
+
+/// Files written in place of the generated code when compilation gets skipped. This is a `mod.rs`
+/// module root, or one file per input in single-file mode, so that the `include!`s and `mod`
+/// declarations pointing at the generated code keep resolving.
+pub(crate) fn stub_files(layout: &OutputLayout, inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    Ok(if layout.one_file {
+        inputs
+            .iter()
+            .map(|input| layout.generated_files_for(input))
+            .collect::<Result<Vec<_>>>()?
+            .concat()
+    } else {
+        vec![PathBuf::from(MOD_ROOT_FILE_NAME)]
+    })
+}
+
+/// Adds `contents` to the end of the file at `path`.
+pub(crate) fn append_file(path: &Path, contents: &str) -> Result {
+    fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|source| Error::OutputWriteFailure {
+            path: path.into(),
+            source,
+        })
+}
+
+/// Writes `value` as pretty-printed JSON to `path`.
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) -> Result {
+    let contents = serde_json::to_string_pretty(value).map_err(|e| Error::OutputWriteFailure {
+        path: path.into(),
+        source: e.into(),
+    })?;
+    fs::write(path, contents + "\n").map_err(|source| Error::OutputWriteFailure {
+        path: path.into(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn write_files(dir: &Path, files: &[&str]) {
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+    }
+
+    fn write_stamp(dir: &Path, files: &[&str]) {
+        let stamp = stamp::Stamp {
+            fingerprint: stamp::Fingerprint {
+                compiler: String::new(),
+                options: String::new(),
+                inputs: BTreeMap::new(),
+            },
+            outputs: BTreeMap::new(),
+            files: files
+                .iter()
+                .map(|file| (PathBuf::from(file), String::new()))
+                .collect(),
+        };
+        stamp.write(dir).unwrap();
+    }
+
+    #[test]
+    fn sync_directory_removes_only_previously_generated_files() {
+        let staging = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_files(staging.path(), &["mod.rs", "a/b_generated.rs"]);
+        write_files(
+            output.path(),
+            &["mod.rs", "old/c_generated.rs", "README.md", "user/extra.rs"],
+        );
+        write_stamp(output.path(), &["mod.rs", "old/c_generated.rs"]);
+
+        let summary = sync_directory(staging.path(), output.path()).unwrap();
+
+        assert_eq!(summary.removed, [PathBuf::from("old/c_generated.rs")]);
+        assert!(!output.path().join("old").exists());
+        assert!(output.path().join("README.md").is_file());
+        assert!(output.path().join("user/extra.rs").is_file());
+        assert!(output.path().join("a/b_generated.rs").is_file());
+    }
+
+    #[test]
+    fn sync_directory_removes_nothing_without_a_record() {
+        let staging = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_files(staging.path(), &["mod.rs"]);
+        write_files(output.path(), &["mod.rs", "old_generated.rs"]);
+
+        let summary = sync_directory(staging.path(), output.path()).unwrap();
+
+        assert!(summary.removed.is_empty());
+        assert!(output.path().join("old_generated.rs").is_file());
+        assert!(diff_directory(staging.path(), output.path())
+            .unwrap()
+            .is_empty());
+    }
+}
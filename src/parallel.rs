@@ -0,0 +1,114 @@
+//! Compilation of several independent schema groups at once, for build scripts with many of them
+//! that would otherwise compile them one after the other.
+
+use crate::{log, BuilderOptions, Result};
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+/// Environment variable overriding how many compilations [`compile_all`] runs at once, and how
+/// many generated files get post-processed at once.
+const JOBS_ENV_VAR: &str = "FLATBUFFERS_BUILD_JOBS";
+
+/// Compiles every one of `builders`, running as many of them at once as `FLATBUFFERS_BUILD_JOBS`
+/// says, or else as many as cargo runs jobs (`NUM_JOBS`), or else as many as there are CPUs. Each
+/// builder compiles exactly as with [`BuilderOptions::compile`], so they need their own output
+/// directories, unless they share one with [`BuilderOptions::shared_output`].
+///
+/// ```no_run
+/// # use flatbuffers_build::BuilderOptions;
+/// flatbuffers_build::compile_all([
+///     BuilderOptions::new_with_files(["schemas/weapon.fbs"]).set_output_path("src/gen/weapon"),
+///     BuilderOptions::new_with_files(["schemas/monster.fbs"]).set_output_path("src/gen/monster"),
+/// ])
+/// .expect("flatbuffer compilation failed");
+/// ```
+///
+/// # Arguments
+/// * `builders` - The schema groups to compile.
+///
+/// # Errors
+/// Fails with the error of the first of the `builders` that failed to compile, after all of them
+/// were given a go. The errors of any others get printed as warnings.
+pub fn compile_all<I: IntoIterator<Item = BuilderOptions>>(builders: I) -> Result {
+    compile_all_with_jobs(builders, default_jobs())
+}
+
+/// Same as [`compile_all`], but running at most `jobs` compilations at once.
+///
+/// # Arguments
+/// * `builders` - The schema groups to compile.
+/// * `jobs` - How many compilations to run at once. Zero counts as one.
+///
+/// # Errors
+/// Fails with the error of the first of the `builders` that failed to compile, after all of them
+/// were given a go. The errors of any others get printed as warnings.
+pub fn compile_all_with_jobs<I: IntoIterator<Item = BuilderOptions>>(
+    builders: I,
+    jobs: usize,
+) -> Result {
+    let builders: Vec<_> = builders.into_iter().collect();
+    // Errors get reported in the order the builders came in, not in the order they happened to
+    // fail
+    let mut errors = map(&builders, jobs, crate::compile)
+        .into_iter()
+        .zip(&builders)
+        .filter_map(|(result, builder)| result.err().map(|e| (builder, e)));
+    let Some((_, first)) = errors.next() else {
+        return Ok(());
+    };
+    for (builder, e) in errors {
+        log::warning!(
+            builder.directives,
+            "failed to compile {:?}: {e}",
+            builder.files
+        );
+    }
+    Err(first)
+}
+
+/// Calls `f` on every one of `items`, on up to `jobs` threads at once, and returns the results in
+/// the order of `items`.
+pub(crate) fn map<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    if jobs <= 1 || items.len() <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(items.len()) {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(idx) else {
+                    break;
+                };
+                let result = f(item);
+                results
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push((idx, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_else(PoisonError::into_inner);
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// How many compilations [`compile_all`] runs at once, and how many generated files get
+/// post-processed at once.
+pub(crate) fn default_jobs() -> usize {
+    [JOBS_ENV_VAR, "NUM_JOBS"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok()?.parse().ok())
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, NonZeroUsize::get))
+}
@@ -0,0 +1,77 @@
+//! Dry runs of a compilation. Rather than running anything, these describe every `flatc`
+//! invocation a compilation would make, so that build systems can inspect it or replay it with
+//! their own executors.
+
+use crate::{container, flatc::Backend, Error, Flatc, Result};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// Everything a call to [`crate::BuilderOptions::compile`] would do, as returned by
+/// [`crate::BuilderOptions::plan`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompilationPlan {
+    /// The schema files the compilation reads, as given to the builder.
+    pub inputs: Vec<PathBuf>,
+    /// Every schema the generated code depends on: the inputs, followed by every schema they
+    /// include, directly or not.
+    pub dependencies: Vec<PathBuf>,
+    /// The directory the generated code gets written to.
+    pub output_path: PathBuf,
+    /// The symlink pointing at the generated code, if one was set with
+    /// [`crate::BuilderOptions::set_symlink_directory`].
+    pub symlink_path: Option<PathBuf>,
+    /// Paths, relative to [`Self::output_path`], of the files the compilation is expected to
+    /// generate. For `.proto` inputs this can only be worked out after translating them, so
+    /// their generated code is only listed in single-file mode.
+    pub outputs: Vec<PathBuf>,
+    /// The commands to run, in order. Unlike an actual compilation, which goes through a staging
+    /// directory, these write straight into [`Self::output_path`]. `.proto` inputs get translated
+    /// into a directory next to it, named the same with a `-proto` suffix. Empty if compilation
+    /// gets skipped, see
+    /// [`crate::BuilderOptions::skip_if`].
+    pub commands: Vec<PlannedCommand>,
+}
+
+/// A single invocation of `flatc`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedCommand {
+    /// The program to run. When `flatc` runs through WASI, this is the path of the module to run
+    /// under a WASI runtime with the same arguments.
+    pub program: OsString,
+    /// Arguments to run the program with.
+    pub args: Vec<OsString>,
+}
+
+impl PlannedCommand {
+    /// The command running `compiler` with `args`.
+    pub(crate) fn new(compiler: &Flatc, args: Vec<OsString>) -> Result<PlannedCommand> {
+        Ok(match &compiler.backend {
+            Backend::Native(program) => PlannedCommand {
+                program: program.into(),
+                args,
+            },
+            Backend::Container(image) => {
+                let command = container::command(image, args, compiler.offline)
+                    .map_err(Error::FlatcSpawnFailure)?;
+                PlannedCommand {
+                    program: command.get_program().to_owned(),
+                    args: command.get_args().map(ToOwned::to_owned).collect(),
+                }
+            }
+            #[cfg(feature = "wasi")]
+            Backend::Wasi(module) => PlannedCommand {
+                program: module.into(),
+                args,
+            },
+        })
+    }
+}
+
+/// Directory `.proto` inputs get translated into in a plan writing to `output_path`.
+pub(crate) fn proto_dir(output_path: &Path) -> PathBuf {
+    let mut name = output_path.file_name().unwrap_or_default().to_owned();
+    name.push("-proto");
+    output_path.with_file_name(name)
+}
@@ -0,0 +1,662 @@
+//! Post-processing of the generated code, once `flatc` is done generating it into the staging
+//! directory and before it gets cached or copied into the output directory. Generated files get
+//! processed on several threads at once, and are streamed through each step rather than read into
+//! memory whole, since some schemas generate Rust files of several megabytes.
+
+use crate::{
+    log, output, parallel,
+    schema::{self, DeclarationKind, OutputLayout},
+    BuilderOptions, Error, Result,
+};
+use serde::Deserialize;
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    ffi::{OsStr, OsString},
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::Arc,
+};
+
+/// Environment variable with the `rustfmt` to format generated code with, the same one cargo and
+/// rustup go by.
+pub(crate) const RUSTFMT_ENV_VAR: &str = "RUSTFMT";
+/// Edition the generated code gets formatted as. `rustfmt` can't tell on its own when it isn't
+/// run through cargo.
+const RUSTFMT_EDITION: &str = "2021";
+/// Modules `std` re-exports from `alloc` rather than `core`, along with its macros needing
+/// `alloc`. `std::sync` is one of them, but for `std::sync::atomic`.
+const ALLOC_MODULES: [&str; 8] = [
+    "borrow",
+    "boxed",
+    "collections",
+    "format",
+    "rc",
+    "string",
+    "sync",
+    "vec",
+];
+
+/// A closure rewriting generated code, as added with [`BuilderOptions::add_post_processing_hook`].
+#[derive(Clone)]
+pub(crate) struct Hook(Arc<HookFn>);
+
+type HookFn = dyn Fn(&Path, String) -> String + Send + Sync;
+
+impl Hook {
+    pub fn new(hook: impl Fn(&Path, String) -> String + Send + Sync + 'static) -> Hook {
+        Hook(Arc::new(hook))
+    }
+}
+
+// There's no telling closures apart, so they're told apart by identity
+impl fmt::Debug for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+impl PartialEq for Hook {
+    fn eq(&self, other: &Hook) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Hook {}
+
+/// Runs every post-processing step turned on in `builder_options` over the code generated from
+/// `schemas` into `staging_dir` with `layout`.
+pub(crate) fn run(
+    builder_options: &BuilderOptions,
+    schemas: &[PathBuf],
+    layout: &OutputLayout,
+    staging_dir: &Path,
+) -> Result {
+    normalize(layout, staging_dir)?;
+    if !builder_options.format_generated_code
+        && builder_options.root_attributes.is_empty()
+        && builder_options.file_attributes.is_empty()
+        && builder_options.derives.is_empty()
+        && !builder_options.no_std
+        && builder_options.object_api_feature.is_none()
+        && builder_options.visibility == Visibility::Public
+        && builder_options.post_processing_hooks.is_empty()
+    {
+        return Ok(());
+    }
+    let derives = builder_options
+        .derives
+        .iter()
+        .map(|(pattern, derive)| {
+            let compiled = glob::Pattern::new(pattern).map_err(|e| Error::InvalidTypePattern {
+                pattern: pattern.clone(),
+                reason: e.to_string(),
+            })?;
+            Ok(DeriveRule {
+                pattern: compiled,
+                full_path: pattern.contains("::"),
+                derive,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let object_api_gate = match &builder_options.object_api_feature {
+        Some(feature) => Some(ObjectApiGate {
+            feature,
+            types: object_api_types(schemas)?,
+        }),
+        None => None,
+    };
+    let rules = Rules {
+        derives,
+        no_std: builder_options.no_std,
+        object_api_gate,
+    };
+    let rustfmt = builder_options.format_generated_code.then(rustfmt);
+    let files: Vec<_> = output::list_files(staging_dir)?
+        .into_iter()
+        .filter(|file| {
+            file.extension()
+                .is_some_and(|ext| *ext == *layout.extension)
+        })
+        .collect();
+    parallel::map(&files, parallel::default_jobs(), |file| {
+        let path = staging_dir.join(file);
+        let is_root = layout.one_file || file == Path::new(output::MOD_ROOT_FILE_NAME);
+        let attributes = attributes_for(builder_options, is_root);
+        let changes = Changes {
+            attributes: &attributes,
+            visibility: builder_options.visibility,
+            is_root,
+            rules: &rules,
+        };
+        if !changes.is_empty() {
+            replace(&path, |generated, rewritten| {
+                rewrite(generated, rewritten, file, &changes).map_err(write_failure(&path))
+            })?;
+        }
+        if !builder_options.post_processing_hooks.is_empty() {
+            replace(&path, |mut generated, mut rewritten| {
+                let mut contents = String::new();
+                generated
+                    .read_to_string(&mut contents)
+                    .map_err(write_failure(&path))?;
+                for hook in &builder_options.post_processing_hooks {
+                    contents = (hook.0)(file, contents);
+                }
+                rewritten
+                    .write_all(contents.as_bytes())
+                    .map_err(write_failure(&path))
+            })?;
+        }
+        if let Some(rustfmt) = &rustfmt {
+            format(rustfmt, &path, file)?;
+        }
+        Ok(())
+    })
+    .into_iter()
+    .collect()
+}
+
+/// Makes the text files `flatc` generated into `staging_dir` with `layout` the same wherever they
+/// got generated: with `\n` line endings, as `flatc` writes `\r\n` ones on Windows, and with the
+/// paths into the directory of the crate, or into `staging_dir`, made relative to it. Files that
+/// are already that way are left untouched.
+fn normalize(layout: &OutputLayout, staging_dir: &Path) -> Result {
+    // Leaving out the root directory, whose path every absolute path starts with
+    let dirs: Vec<_> = [std::env::current_dir().ok(), Some(staging_dir.into())]
+        .into_iter()
+        .flatten()
+        .filter(|dir| dir.parent().is_some())
+        .map(|dir| format!("{}{}", dir.display(), std::path::MAIN_SEPARATOR))
+        .collect();
+    let files: Vec<_> = output::list_files(staging_dir)?
+        .into_iter()
+        .filter(|file| layout.is_generated_code(file) || is_json_schema(file))
+        .collect();
+    parallel::map(&files, parallel::default_jobs(), |file| {
+        let path = staging_dir.join(file);
+        let machine_specific =
+            |line: &str| line.ends_with("\r\n") || dirs.iter().any(|dir| line.contains(dir));
+        if !any_line(&path, machine_specific).map_err(write_failure(&path))? {
+            return Ok(());
+        }
+        replace(&path, |generated, normalized| {
+            let mut generated = BufReader::new(generated);
+            let mut normalized = BufWriter::new(normalized);
+            let mut line = String::new();
+            while generated
+                .read_line(&mut line)
+                .map_err(write_failure(&path))?
+                > 0
+            {
+                if line.ends_with("\r\n") {
+                    line.truncate(line.len() - 2);
+                    line.push('\n');
+                }
+                for dir in &dirs {
+                    line = line.replace(dir.as_str(), "");
+                }
+                normalized
+                    .write_all(line.as_bytes())
+                    .map_err(write_failure(&path))?;
+                line.clear();
+            }
+            normalized.flush().map_err(write_failure(&path))
+        })
+    })
+    .into_iter()
+    .collect()
+}
+
+/// Whether any line of the file at `path`, along with its line ending, matches `predicate`.
+fn any_line(path: &Path, predicate: impl Fn(&str) -> bool) -> io::Result<bool> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut line = String::new();
+    while file.read_line(&mut line)? > 0 {
+        if predicate(&line) {
+            return Ok(true);
+        }
+        line.clear();
+    }
+    Ok(false)
+}
+
+/// Whether `file` is a JSON Schema generated with [`BuilderOptions::generate_json_schemas`].
+fn is_json_schema(file: &Path) -> bool {
+    file.to_string_lossy().ends_with(".schema.json")
+}
+
+/// Visibility of the items of the generated code, as set with [`BuilderOptions::set_visibility`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// `pub`, as `flatc` generates them.
+    #[default]
+    Public,
+    /// `pub(crate)`, so that they're only visible within the crate.
+    Crate,
+    /// `pub(super)`, so that they're only visible to the module declaring the generated code's
+    /// root module, and the modules in it.
+    Super,
+}
+
+impl Visibility {
+    fn keyword(self) -> &'static str {
+        match self {
+            Visibility::Public => "pub",
+            Visibility::Crate => "pub(crate)",
+            Visibility::Super => "pub(super)",
+        }
+    }
+}
+
+/// The attributes to add to a generated file: the ones for every file, and the ones for the
+/// module root if it's one. With [`OutputLayout::one_file`], every file is a module root.
+fn attributes_for(builder_options: &BuilderOptions, is_root: bool) -> Vec<&str> {
+    is_root
+        .then_some(&builder_options.root_attributes)
+        .into_iter()
+        .chain([&builder_options.file_attributes])
+        .flatten()
+        .map(String::as_str)
+        .collect()
+}
+
+/// A derive to add to the generated types whose name, or path with [`Self::full_path`], matches
+/// the pattern, as set with [`BuilderOptions::add_derive`].
+struct DeriveRule<'a> {
+    pattern: glob::Pattern,
+    /// Whether the pattern is matched against the path of types, rather than their name.
+    full_path: bool,
+    derive: &'a str,
+}
+
+/// The object API types to only compile with a feature, as set with
+/// [`BuilderOptions::set_object_api_feature`].
+struct ObjectApiGate<'a> {
+    feature: &'a str,
+    /// Names of the object API types, the tables, structs and unions of the schemas with a `T`
+    /// after them.
+    types: HashSet<String>,
+}
+
+/// The changes to make to every line of the generated code.
+struct Rules<'a> {
+    derives: Vec<DeriveRule<'a>>,
+    /// Whether to rewrite paths into `std` into paths into `core` and `alloc`, as set with
+    /// [`BuilderOptions::make_no_std`].
+    no_std: bool,
+    object_api_gate: Option<ObjectApiGate<'a>>,
+}
+
+impl Rules<'_> {
+    fn is_empty(&self) -> bool {
+        self.derives.is_empty() && !self.no_std && self.object_api_gate.is_none()
+    }
+}
+
+/// The changes to make to a generated file.
+struct Changes<'a> {
+    /// Attributes to add after the comments the file starts with.
+    attributes: &'a [&'a str],
+    /// Visibility to give its items in place of `pub`.
+    visibility: Visibility,
+    /// Whether it's a module root, see [`attributes_for`].
+    is_root: bool,
+    rules: &'a Rules<'a>,
+}
+
+impl Changes<'_> {
+    fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+            && (self.visibility == Visibility::Public
+                || (self.visibility == Visibility::Super && !self.is_root))
+            && self.rules.is_empty()
+    }
+
+    /// Whether to change the visibility of the items at the current line, going by `modules`.
+    /// `pub(super)` only goes on the items at the root of the generated code, as it'd hide the
+    /// ones in the modules it declares from the code using them, which can only reach them
+    /// through the root anyway.
+    fn changes_visibility(&self, modules: &ModuleTracker) -> bool {
+        match self.visibility {
+            Visibility::Public => false,
+            Visibility::Crate => modules.at_module_level(),
+            Visibility::Super => self.is_root && modules.at_root(),
+        }
+    }
+}
+
+/// Names of the object API types generated from `schemas`.
+fn object_api_types(schemas: &[PathBuf]) -> Result<HashSet<String>> {
+    let mut types = HashSet::new();
+    for schema in schemas {
+        for declaration in schema::Schema::from_file(schema)?.declarations {
+            if matches!(
+                declaration.kind,
+                DeclarationKind::Table | DeclarationKind::Struct | DeclarationKind::Union
+            ) {
+                types.insert(format!("{}T", declaration.name));
+            }
+        }
+    }
+    Ok(types)
+}
+
+/// Copies the generated code of `file` from `generated` into `rewritten`, adding the attributes
+/// of `changes` after the comments it starts with, where inner attributes are still allowed, and
+/// making the rest of its changes to every line after them.
+fn rewrite(
+    mut generated: File,
+    rewritten: &File,
+    file: &Path,
+    changes: &Changes,
+) -> io::Result<()> {
+    let rules = changes.rules;
+    // Paths into `alloc` need it declared, which flatc only does when it uses it itself
+    let declare_alloc = rules.no_std && needs_alloc_declared(&generated)?;
+    generated.rewind()?;
+    let mut generated = BufReader::new(generated);
+    let mut rewritten = BufWriter::new(rewritten);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if generated.read_line(&mut line)? == 0 || !line.trim_start().starts_with("//") {
+            break;
+        }
+        rewritten.write_all(line.as_bytes())?;
+    }
+    for attribute in changes.attributes {
+        writeln!(rewritten, "{attribute}")?;
+    }
+    if declare_alloc {
+        writeln!(rewritten, "extern crate alloc;")?;
+    }
+    if changes.visibility == Visibility::Public && rules.is_empty() {
+        rewritten.write_all(line.as_bytes())?;
+        io::copy(&mut generated, &mut rewritten)?;
+        return rewritten.flush();
+    }
+
+    // Generated files live in the directory of their module, and one-file mode nests modules in
+    // the file itself
+    let mut modules = ModuleTracker::new(file);
+    while !line.is_empty() {
+        let mut code = if rules.no_std {
+            without_std(&line).0
+        } else {
+            Cow::Borrowed(line.as_str())
+        };
+        if changes.changes_visibility(&modules) {
+            let item = code.trim_start();
+            if let Some(rest) = item.strip_prefix("pub ") {
+                let indent = &code[..code.len() - item.len()];
+                code = Cow::Owned(format!("{indent}{} {rest}", changes.visibility.keyword()));
+            }
+        }
+        if let Some(gate) = rules
+            .object_api_gate
+            .as_ref()
+            .filter(|gate| is_object_api_item(&code, &gate.types))
+        {
+            let indent = &code[..code.len() - code.trim_start().len()];
+            writeln!(rewritten, "{indent}#[cfg(feature = \"{}\")]", gate.feature)?;
+        }
+        if let Some(name) = declared_type(&code) {
+            let path = modules.path_of(name);
+            let mut matching = rules
+                .derives
+                .iter()
+                .filter(|rule| {
+                    rule.pattern
+                        .matches(if rule.full_path { &path } else { name })
+                })
+                .map(|rule| rule.derive)
+                .peekable();
+            if matching.peek().is_some() {
+                let indent = &code[..code.len() - code.trim_start().len()];
+                let matching: Vec<_> = matching.collect();
+                writeln!(rewritten, "{indent}#[derive({})]", matching.join(", "))?;
+            }
+        }
+        // The original line, as the module tracker only goes by the `pub mod` blocks
+        modules.track(&line);
+        rewritten.write_all(code.as_bytes())?;
+        line.clear();
+        generated.read_line(&mut line)?;
+    }
+    rewritten.flush()
+}
+
+/// Keeps track of the module a line of generated code is in, going by the modules its file is in
+/// and the `pub mod` blocks opened in it so far.
+struct ModuleTracker {
+    /// Modules the file itself is in.
+    file_modules: Vec<String>,
+    /// The `pub mod` blocks open, with the brace depth each of them got opened at.
+    open: Vec<(String, usize)>,
+    depth: usize,
+}
+
+impl ModuleTracker {
+    fn new(file: &Path) -> ModuleTracker {
+        let file_modules = file
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        ModuleTracker {
+            file_modules,
+            open: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    /// Path of the type `name` declared at the current line, e.g. `my_game::sample::Monster`.
+    fn path_of(&self, name: &str) -> String {
+        self.file_modules
+            .iter()
+            .map(String::as_str)
+            .chain(self.open.iter().map(|(module, _)| module.as_str()))
+            .chain([name])
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+
+    /// Whether the current line is directly in a module, rather than in one of its items.
+    fn at_module_level(&self) -> bool {
+        self.depth == self.open.last().map_or(0, |(_, depth)| depth + 1)
+    }
+
+    /// Whether the current line is directly in the file, rather than in one of its items.
+    fn at_root(&self) -> bool {
+        self.depth == 0
+    }
+
+    /// Moves past `line`, entering and leaving the modules it opens and closes.
+    fn track(&mut self, line: &str) {
+        if let Some(module) = line
+            .trim_start()
+            .strip_prefix("pub mod ")
+            .map(identifier)
+            .filter(|module| !module.is_empty())
+        {
+            self.open.push((module.to_owned(), self.depth));
+        }
+        for brace in code_braces(line) {
+            if brace == '{' {
+                self.depth += 1;
+            } else {
+                self.depth = self.depth.saturating_sub(1);
+                if self
+                    .open
+                    .last()
+                    .is_some_and(|(_, depth)| *depth == self.depth)
+                {
+                    self.open.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Whether the generated code has paths into `std` that [`without_std`] turns into paths into
+/// `alloc`, without declaring `alloc` itself.
+fn needs_alloc_declared(generated: &File) -> io::Result<bool> {
+    let mut needs_alloc = false;
+    for line in BufReader::new(generated).lines() {
+        let line = line?;
+        if line.trim() == "extern crate alloc;" {
+            return Ok(false);
+        }
+        needs_alloc |= without_std(&line).1;
+    }
+    Ok(needs_alloc)
+}
+
+/// `line` with its paths into `std` turned into paths into `core` or `alloc`, and whether any of
+/// them went into `alloc`.
+fn without_std(line: &str) -> (Cow<'_, str>, bool) {
+    if !line.contains("std::") {
+        return (Cow::Borrowed(line), false);
+    }
+    let mut rewritten = String::with_capacity(line.len());
+    let mut uses_alloc = false;
+    let mut rest = line;
+    while let Some(idx) = rest.find("std::") {
+        let (before, after) = (&rest[..idx], &rest[idx + "std::".len()..]);
+        rewritten.push_str(before);
+        // Leaves out identifiers ending in `std`
+        if before.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+            rewritten.push_str("std::");
+        } else {
+            let module = identifier(after);
+            let alloc = ALLOC_MODULES.contains(&module) && !after.starts_with("sync::atomic");
+            uses_alloc |= alloc;
+            rewritten.push_str(if alloc { "alloc::" } else { "core::" });
+        }
+        rest = after;
+    }
+    rewritten.push_str(rest);
+    (Cow::Owned(rewritten), uses_alloc)
+}
+
+/// Whether `line` starts an item of the object API: the declaration of one of its `types`, one
+/// of their impls, or the `unpack` method turning tables and structs into them.
+fn is_object_api_item(line: &str, types: &HashSet<String>) -> bool {
+    let line = line.trim_start();
+    let name = if let Some(name) = declared_type(line) {
+        name
+    } else if let Some(returned) = line.strip_prefix("pub fn unpack(&self) -> ") {
+        identifier(returned)
+    } else if let Some(rest) = line.strip_prefix("impl") {
+        let target = match rest.rsplit_once(" for ") {
+            Some((_, target)) => target,
+            // Skips the generic parameters of the impl, if any
+            None => rest
+                .strip_prefix('<')
+                .and_then(|rest| rest.split_once("> "))
+                .map_or(rest, |(_, target)| target),
+        };
+        identifier(target.trim_start())
+    } else {
+        return false;
+    };
+    types.contains(name)
+}
+
+/// Name of the struct or enum `line` declares, if any.
+fn declared_type(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let rest = line
+        .strip_prefix("pub struct ")
+        .or_else(|| line.strip_prefix("pub enum "))?;
+    Some(identifier(rest)).filter(|name| !name.is_empty())
+}
+
+/// The identifier `s` starts with.
+fn identifier(s: &str) -> &str {
+    let end = s
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(s.len());
+    &s[..end]
+}
+
+/// The braces in `line`, leaving out the ones in string literals and comments.
+fn code_braces(line: &str) -> impl Iterator<Item = char> + '_ {
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = line.chars().peekable();
+    std::iter::from_fn(move || {
+        while let Some(c) = chars.next() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_string => escaped = true,
+                '"' => in_string = !in_string,
+                '/' if !in_string && chars.peek() == Some(&'/') => return None,
+                '{' | '}' if !in_string => return Some(c),
+                _ => {}
+            }
+        }
+        None
+    })
+}
+
+/// The `rustfmt` set in [`RUSTFMT_ENV_VAR`], or else the one in the `PATH`.
+fn rustfmt() -> OsString {
+    std::env::var_os(RUSTFMT_ENV_VAR)
+        .filter(|rustfmt| !rustfmt.is_empty())
+        .unwrap_or_else(|| "rustfmt".into())
+}
+
+/// Formats the generated `file` at `path` with `rustfmt`. The file goes through the standard
+/// input and output of `rustfmt`, which keeps it from also formatting the files of the modules it
+/// declares, as those get formatted on their own.
+fn format(rustfmt: &OsStr, path: &Path, file: &Path) -> Result {
+    replace(path, |generated, formatted| {
+        let mut command = Command::new(rustfmt);
+        command
+            .args(["--edition", RUSTFMT_EDITION])
+            .stdin(generated)
+            .stdout(formatted.try_clone().map_err(write_failure(path))?)
+            .stderr(Stdio::piped());
+        log::step!("running {command:?} on {}", file.display());
+        let output = command.output().map_err(Error::RustfmtSpawnFailure)?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(Error::RustfmtErrorCode {
+                path: file.into(),
+                status_code: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            })
+        }
+    })
+}
+
+/// Replaces the file at `path` with what `rewrite` writes into a new file, given the original
+/// one. The original only gets replaced once `rewrite` succeeds.
+fn replace(path: &Path, rewrite: impl FnOnce(File, &File) -> Result) -> Result {
+    let failure = write_failure(path);
+    let generated = File::open(path).map_err(failure)?;
+    let permissions = generated.metadata().map_err(failure)?.permissions();
+    let rewritten =
+        tempfile::NamedTempFile::new_in(path.parent().unwrap_or(Path::new(""))).map_err(failure)?;
+    rewrite(generated, rewritten.as_file())?;
+    // Temporary files are only readable by their owner, unlike what flatc generates
+    std::fs::set_permissions(rewritten.path(), permissions).map_err(failure)?;
+    rewritten.persist(path).map_err(|e| failure(e.error))?;
+    Ok(())
+}
+
+/// Turns an I/O error rewriting the generated file at `path` into an [`Error`].
+fn write_failure(path: &Path) -> impl Fn(io::Error) -> Error + Copy + '_ {
+    |source| Error::OutputWriteFailure {
+        path: path.into(),
+        source,
+    }
+}
@@ -0,0 +1,158 @@
+//! The prelude of the generated code, written with [`crate::BuilderOptions::generate_prelude`]. It
+//! re-exports the types generated from the schemas at the root of the generated code, so they
+//! don't have to be imported from the modules of their namespaces one by one.
+
+use crate::{
+    log, output,
+    schema::{self, DeclarationKind, OutputLayout},
+    BuilderOptions, Error, Result,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+/// Name of the module the prelude goes in, and of its file next to the module root.
+pub(crate) const PRELUDE_MODULE_NAME: &str = "prelude";
+
+/// Adds the prelude to the code generated from `schemas` into `staging_dir` with `layout`. That's
+/// a `prelude.rs` declared in `mod.rs`, or with [`OutputLayout::one_file`], a `prelude` module at
+/// the end of the file generated from each schema, covering the types of that schema.
+pub(crate) fn write(
+    builder_options: &BuilderOptions,
+    schemas: &[PathBuf],
+    layout: &OutputLayout,
+    staging_dir: &Path,
+) -> Result {
+    let object_api = builder_options
+        .flatc_flags
+        .contains(&crate::OBJECT_API_FLAG);
+    let gate = builder_options.object_api_feature.as_deref();
+    if layout.one_file {
+        for schema in schemas {
+            let exports = exports(builder_options, std::slice::from_ref(schema), object_api)?;
+            for file in layout.generated_files_for(schema)? {
+                let contents = format!(
+                    "\npub mod {PRELUDE_MODULE_NAME} {{\n{}}}\n",
+                    use_statements(&exports, gate, "    ")
+                );
+                output::append_file(&staging_dir.join(file), &contents)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let exports = exports(builder_options, schemas, object_api)?;
+    let prelude_path = staging_dir.join(format!("{PRELUDE_MODULE_NAME}.rs"));
+    let contents = format!(
+        "// Re-exports of the generated types, written by flatbuffers-build.\n// @generated\n{}",
+        use_statements(&exports, gate, "")
+    );
+    std::fs::write(&prelude_path, contents).map_err(|source| Error::OutputWriteFailure {
+        path: prelude_path,
+        source,
+    })?;
+    output::append_file(
+        &staging_dir.join(output::MOD_ROOT_FILE_NAME),
+        &format!("pub mod {PRELUDE_MODULE_NAME};\n"),
+    )
+}
+
+/// A type to re-export.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Export {
+    name: String,
+    /// Whether it's one of the object API types, which might only compile with a feature.
+    object_api: bool,
+}
+
+/// The types generated from `schemas` to re-export, by the path of the module they're in. Names
+/// declared in more than one namespace are left out, as they'd clash.
+fn exports(
+    builder_options: &BuilderOptions,
+    schemas: &[PathBuf],
+    object_api: bool,
+) -> Result<BTreeMap<Vec<String>, BTreeSet<Export>>> {
+    let mut modules_by_name: BTreeMap<Export, BTreeSet<Vec<String>>> = BTreeMap::new();
+    for schema in schemas {
+        for declaration in schema::Schema::from_file(schema)?.declarations {
+            let name = &declaration.name;
+            let export = |name: String, object_api| Export { name, object_api };
+            let mut names = vec![export(name.clone(), false)];
+            match declaration.kind {
+                DeclarationKind::Table => names.push(export(format!("{name}Args"), false)),
+                DeclarationKind::RpcService => continue,
+                DeclarationKind::Struct | DeclarationKind::Enum | DeclarationKind::Union => {}
+            }
+            if object_api && declaration.kind != DeclarationKind::Enum {
+                names.push(export(format!("{name}T"), true));
+            }
+            let module: Vec<_> = declaration
+                .namespace
+                .iter()
+                .map(|part| schema::to_snake_case(part))
+                .collect();
+            for name in names {
+                modules_by_name
+                    .entry(name)
+                    .or_default()
+                    .insert(module.clone());
+            }
+        }
+    }
+
+    let mut exports: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+    for (name, modules) in modules_by_name {
+        let mut modules = modules.into_iter();
+        match (modules.next(), modules.next()) {
+            (Some(module), None) => {
+                exports.entry(module).or_default().insert(name);
+            }
+            _ => log::warning!(
+                builder_options.directives,
+                "leaving {} out of the prelude, as it's declared in more than one namespace",
+                name.name
+            ),
+        }
+    }
+    Ok(exports)
+}
+
+/// The `pub use` statements re-exporting `exports` from the module one level down from the root
+/// of the generated code, each on a line starting with `indent`. With a `gate`, the object API
+/// types get re-exported separately, only with that feature enabled, same as they're compiled.
+fn use_statements(
+    exports: &BTreeMap<Vec<String>, BTreeSet<Export>>,
+    gate: Option<&str>,
+    indent: &str,
+) -> String {
+    let mut statements = String::new();
+    for (module, names) in exports {
+        let path = module
+            .iter()
+            .fold(String::new(), |path, part| path + part + "::");
+        let (gated, names): (Vec<_>, Vec<_>) = names
+            .iter()
+            .partition(|export| gate.is_some() && export.object_api);
+        for (names, attribute) in [
+            (names, None),
+            (
+                gated,
+                gate.map(|feature| format!("#[cfg(feature = \"{feature}\")]\n{indent}")),
+            ),
+        ] {
+            if names.is_empty() {
+                continue;
+            }
+            let names: Vec<_> = names.iter().map(|export| export.name.as_str()).collect();
+            let _ = writeln!(
+                statements,
+                "{indent}{}pub use super::{path}{{{}}};",
+                attribute.unwrap_or_default(),
+                names.join(", ")
+            );
+        }
+    }
+    statements
+}
@@ -0,0 +1,113 @@
+//! Translation of protobuf (`.proto`) schemas into flatbuffer schemas, using `flatc --proto`. This
+//! is mostly meant to ease migrating services from protobuf to flatbuffers.
+
+use crate::{confirm_flatc_version, resolve_compiler, run_flatc, Error, Flatc, Result};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// Translates each of the `.proto` files provided into a flatbuffer schema (by running `flatc`
+/// with `--proto`). The schema for `name.proto` gets written to `output_path` as `name.fbs`, and
+/// the paths of all translated schemas are returned in the same order as the inputs. The compiler
+/// is resolved the same way as when calling [`crate::BuilderOptions::compile`] without setting a
+/// compiler.
+///
+/// The schemas returned can be fed straight into [`crate::BuilderOptions`]:
+/// ```no_run
+/// use flatbuffers_build::{convert_proto, BuilderOptions};
+///
+/// let out_dir = std::env::var("OUT_DIR").unwrap();
+/// let schemas = convert_proto(["protos/service.proto"], format!("{out_dir}/fbs"))
+///     .expect("proto translation failed");
+/// BuilderOptions::new_with_files(schemas)
+///     .compile()
+///     .expect("flatbuffer compilation failed");
+/// ```
+///
+/// Note that you can also list `.proto` files directly in
+/// [`crate::BuilderOptions::new_with_files`], in which case they get translated as part of
+/// compilation.
+///
+/// # Arguments
+/// * `proto_files` - The protobuf schemas to translate.
+/// * `output_path` - The directory to write the translated schemas to.
+///
+/// # Errors
+/// Will fail if `flatc` can't translate any of the files, or for any of the reasons compilation
+/// can fail. See [`Error`] for more details.
+pub fn convert_proto<P: AsRef<Path>, Q: AsRef<Path>, I: IntoIterator<Item = P>>(
+    proto_files: I,
+    output_path: Q,
+) -> Result<Vec<PathBuf>> {
+    let compiler = resolve_compiler(None);
+    confirm_flatc_version(&compiler)?;
+    let proto_files: Vec<_> = proto_files
+        .into_iter()
+        .map(|f| f.as_ref().to_path_buf())
+        .collect();
+    run_proto_conversion(&compiler, &proto_files, output_path.as_ref())
+}
+
+/// Maps each of the input `files` into the flatbuffer schema to compile for it. Flatbuffer schemas
+/// map to themselves, while `.proto` files get translated into `output_dir` first.
+pub(crate) fn schemas_for_inputs(
+    compiler: &Flatc,
+    files: &[PathBuf],
+    output_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let proto_files: Vec<_> = files.iter().filter(|f| is_proto(f)).cloned().collect();
+    if proto_files.is_empty() {
+        return Ok(files.to_vec());
+    }
+
+    let mut translated = run_proto_conversion(compiler, &proto_files, output_dir)?.into_iter();
+    Ok(files
+        .iter()
+        .map(|f| {
+            if is_proto(f) {
+                translated.next().unwrap_or_else(|| f.clone())
+            } else {
+                f.clone()
+            }
+        })
+        .collect())
+}
+
+fn run_proto_conversion(
+    compiler: &Flatc,
+    proto_files: &[PathBuf],
+    output_path: &Path,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(output_path).map_err(|source| Error::OutputWriteFailure {
+        path: output_path.into(),
+        source,
+    })?;
+    run_flatc(compiler, conversion_args(proto_files, output_path))?;
+
+    Ok(proto_files
+        .iter()
+        .map(|f| translated_path(f, output_path))
+        .collect())
+}
+
+/// Arguments for the `flatc` invocation translating `proto_files` into `output_path`.
+pub(crate) fn conversion_args(proto_files: &[PathBuf], output_path: &Path) -> Vec<OsString> {
+    let mut args = vec![
+        OsString::from("--proto"),
+        OsString::from("-o"),
+        output_path.as_os_str().to_owned(),
+    ];
+    args.extend(proto_files.iter().map(|f| f.as_os_str().to_owned()));
+    args
+}
+
+/// Path `proto_file` gets translated into when writing to `output_path`.
+pub(crate) fn translated_path(proto_file: &Path, output_path: &Path) -> PathBuf {
+    let stem = proto_file.file_stem().unwrap_or_default().to_string_lossy();
+    output_path.join(format!("{stem}.fbs"))
+}
+
+pub(crate) fn is_proto(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "proto")
+}
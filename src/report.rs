@@ -0,0 +1,129 @@
+//! The JSON report written with [`crate::BuilderOptions::set_report_path`]. It describes how a
+//! compilation went, whether it succeeded or not, so that CI systems can surface schema errors
+//! without scraping cargo's output.
+
+use crate::{directives::Directives, flatc::Runs, output, Diagnostic, Error, Result};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub(crate) struct Report {
+    pub success: bool,
+    /// Message of the error compilation failed with.
+    pub error: Option<String>,
+    /// Errors and warnings reported by `flatc`.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Files generated, relative to the output directory.
+    pub generated_files: Vec<PathBuf>,
+    pub duration_secs: f64,
+    /// Seconds spent in each phase of the compilation.
+    pub phase_secs: Phases,
+    pub counters: Counters,
+    /// Runs of `flatc` during the compilation, recorded by the compiler it uses.
+    #[serde(skip)]
+    pub flatc_runs: Arc<Runs>,
+}
+
+/// Counters describing the work a compilation did, as returned by
+/// [`crate::BuilderOptions::compile_with_counters`]. They're also part of the report written with
+/// [`crate::BuilderOptions::set_report_path`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Counters {
+    /// Schemas compiled, not counting the ones they include.
+    pub schemas: usize,
+    /// Files generated into the output directory, whether they were written this time or not.
+    pub generated_files: usize,
+    /// Bytes written to the output directory. Generated files that were already up to date don't
+    /// get written again, so they don't count.
+    pub bytes_written: u64,
+    /// Times the code was reused rather than generated: because the output directory was up to
+    /// date, or from the cache set with [`crate::BuilderOptions::set_output_cache_dir`] or the
+    /// remote one.
+    pub cache_hits: usize,
+    /// Times `flatc` had to generate the code after all.
+    pub cache_misses: usize,
+    /// Times `flatc` was run, including to check its version.
+    pub subprocesses: usize,
+    /// Seconds spent running `flatc`. Runs happening at once each count in full.
+    pub subprocess_secs: f64,
+}
+
+/// How long each phase of a compilation took, in seconds. Phases that didn't run are left at 0.
+#[derive(Clone, Debug, Default, Serialize)]
+pub(crate) struct Phases {
+    /// Checking the version of `flatc`.
+    pub version_check: f64,
+    /// Running `flatc` to translate, check and compile the schemas.
+    pub flatc: f64,
+    /// Formatting the generated files, and comparing or copying them into the output directory.
+    pub post_processing: f64,
+    /// Creating the symlink to the output.
+    pub symlink: f64,
+}
+
+impl Phases {
+    /// Runs `f`, adding the time it took to `phase`.
+    pub fn time<T>(phase: &mut f64, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        *phase += started.elapsed().as_secs_f64();
+        result
+    }
+}
+
+impl Report {
+    /// Fills in the outcome of a compilation that took `duration`.
+    pub fn finish(&mut self, result: &Result, duration: Duration) {
+        self.success = result.is_ok();
+        self.duration_secs = duration.as_secs_f64();
+        self.counters.generated_files = self.generated_files.len();
+        self.counters.subprocesses = self.flatc_runs.count();
+        self.counters.subprocess_secs = self.flatc_runs.duration().as_secs_f64();
+        if let Err(e) = result {
+            self.error = Some(e.to_string());
+            if let Error::FlatcErrorCode { diagnostics, .. } = e {
+                self.diagnostics.extend(diagnostics.iter().cloned());
+            }
+        }
+    }
+
+    /// Prints how long compilation and each of its phases took as cargo warnings.
+    pub fn print_timings(&self, directives: &Directives) {
+        crate::log::notice!(
+            directives,
+            "flatbuffers-build took {:.3}s: version check {:.3}s, flatc {:.3}s, \
+             post-processing {:.3}s, symlink {:.3}s",
+            self.duration_secs,
+            self.phase_secs.version_check,
+            self.phase_secs.flatc,
+            self.phase_secs.post_processing,
+            self.phase_secs.symlink,
+        );
+    }
+
+    /// Prints the counters of the compilation as a cargo warning.
+    pub fn print_counters(&self, directives: &Directives) {
+        let counters = &self.counters;
+        crate::log::notice!(
+            directives,
+            "flatbuffers-build compiled {} schemas into {} files: {} bytes written, \
+             {} cache hits, {} cache misses, {} flatc runs taking {:.3}s",
+            counters.schemas,
+            counters.generated_files,
+            counters.bytes_written,
+            counters.cache_hits,
+            counters.cache_misses,
+            counters.subprocesses,
+            counters.subprocess_secs,
+        );
+    }
+
+    /// Writes the report as pretty-printed JSON to `path`.
+    pub fn write(&self, path: &Path) -> Result {
+        output::write_json(path, self)
+    }
+}
@@ -0,0 +1,436 @@
+//! A lightweight scanner for flatbuffer schema (`.fbs`) files. This is by no means a full parser
+//! and we still rely on `flatc` for anything that requires actual semantics. It only extracts the
+//! top-level structure of a schema: its namespaces and the types it declares, which is
+//! enough to figure out what code `flatc` will generate for it.
+
+use crate::{depfile, Error, Result};
+use std::path::{Path, PathBuf};
+
+/// The kind of a top-level declaration in a schema file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DeclarationKind {
+    Table,
+    Struct,
+    Enum,
+    Union,
+    RpcService,
+}
+
+/// A type declared at the top level of a schema file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Declaration {
+    pub kind: DeclarationKind,
+    pub name: String,
+    /// Namespace in effect when the type was declared, split on `.`.
+    pub namespace: Vec<String>,
+    /// 1-based line number where the declaration starts.
+    pub line: usize,
+}
+
+/// Top-level structure of a single schema file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Schema {
+    pub declarations: Vec<Declaration>,
+    /// Extension declared with `file_extension`, used for binary files of this schema.
+    pub file_extension: Option<String>,
+    /// Paths of the schemas pulled in with `include`, exactly as written.
+    pub includes: Vec<String>,
+    /// Type declared with `root_type`, split on `.`, with the namespace in effect in front of it
+    /// if it wasn't written out.
+    pub root_type: Option<Vec<String>>,
+}
+
+impl Schema {
+    /// Reads and scans the schema at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Schema> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read_to_string(path).map_err(|source| Error::SchemaReadFailure {
+                path: path.into(),
+                source,
+            })?;
+        Ok(Schema::parse(&contents))
+    }
+
+    /// Scans the schema contents provided. Anything we don't understand is skipped over.
+    pub fn parse(contents: &str) -> Schema {
+        let tokens = tokenize(contents);
+        let mut schema = Schema::default();
+        let mut namespace = Vec::new();
+        let mut idx = 0;
+
+        while idx < tokens.len() {
+            let token = &tokens[idx];
+            let next = tokens.get(idx + 1).map(|t| t.text.as_str());
+            match (token.text.as_str(), next) {
+                ("namespace", _) => {
+                    namespace.clear();
+                    idx += 1;
+                    while let Some(part) = tokens.get(idx).filter(|t| t.text != ";") {
+                        if part.text != "." {
+                            namespace.push(part.text.clone());
+                        }
+                        idx += 1;
+                    }
+                }
+                (keyword, Some(name)) if declaration_kind(keyword).is_some() => {
+                    schema.declarations.push(Declaration {
+                        kind: declaration_kind(keyword).unwrap_or(DeclarationKind::Table),
+                        name: name.into(),
+                        namespace: namespace.clone(),
+                        line: token.line,
+                    });
+                    idx = skip_block(&tokens, idx + 2);
+                }
+                ("include", Some(include)) if is_string(include) => {
+                    schema.includes.push(unquote(include));
+                    idx += 2;
+                }
+                ("root_type", Some(_)) => {
+                    let mut root_type = Vec::new();
+                    idx += 1;
+                    while let Some(part) = tokens.get(idx).filter(|t| t.text != ";") {
+                        if part.text != "." {
+                            root_type.push(part.text.clone());
+                        }
+                        idx += 1;
+                    }
+                    if root_type.len() == 1 {
+                        root_type.splice(0..0, namespace.iter().cloned());
+                    }
+                    schema.root_type = Some(root_type);
+                }
+                ("file_extension", Some(extension)) if is_string(extension) => {
+                    schema.file_extension = Some(unquote(extension));
+                    idx += 2;
+                }
+                ("{", _) => idx = skip_block(&tokens, idx),
+                _ => idx += 1,
+            }
+        }
+
+        schema
+    }
+}
+
+/// Resolves an `include` of the schema at `schema_path` the same way `flatc` does: relative to the
+/// directory of the schema first, then to the current directory, then to each of the
+/// `include_paths`. Returns the canonical path of the included schema, if it exists.
+pub(crate) fn resolve_include(
+    schema_path: &Path,
+    include: &str,
+    include_paths: &[PathBuf],
+) -> Option<PathBuf> {
+    let schema_dir = schema_path.parent().unwrap_or(Path::new(""));
+    [schema_dir.join(include), PathBuf::from(include)]
+        .into_iter()
+        .chain(include_paths.iter().map(|dir| dir.join(include)))
+        .find(|candidate| candidate.is_file())
+        .and_then(|candidate| std::fs::canonicalize(candidate).ok())
+}
+
+/// Converts an identifier into `snake_case` the same way `flatc` does when naming generated
+/// files and directories. Notably, a run of digits gets its own underscore, so `Vec3` becomes
+/// `vec_3`.
+pub(crate) fn to_snake_case(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = String::with_capacity(input.len() + 4);
+    for (idx, &c) in chars.iter().enumerate() {
+        if idx == 0 {
+            output.push(c.to_ascii_lowercase());
+        } else if c == '_' {
+            output.push('_');
+        } else if !c.is_ascii_lowercase() {
+            let prev = chars[idx - 1];
+            if prev.is_ascii_lowercase() || (prev.is_ascii_digit() && !c.is_ascii_digit()) {
+                output.push('_');
+            }
+            output.push(c.to_ascii_lowercase());
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+/// Describes how `flatc` lays out and names the files it generates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct OutputLayout {
+    /// Whether `flatc` runs with `--gen-onefile`, generating a single file per schema.
+    pub one_file: bool,
+    /// Whether `.bfbs` binary schemas get generated alongside the code.
+    pub binary_schemas: bool,
+    /// Whether `.schema.json` JSON Schemas get generated alongside the code.
+    pub json_schemas: bool,
+    /// Suffix appended to the name of every generated file (`--filename-suffix`).
+    pub suffix: String,
+    /// Extension of every generated file, without the leading dot (`--filename-ext`).
+    pub extension: String,
+    /// When code also gets generated for the types of every included schema (`--gen-all`), the
+    /// directories `include`s get resolved against to find them.
+    pub gen_all: Option<Vec<PathBuf>>,
+}
+
+impl Default for OutputLayout {
+    fn default() -> Self {
+        OutputLayout {
+            one_file: false,
+            binary_schemas: false,
+            json_schemas: false,
+            suffix: "_generated".into(),
+            extension: "rs".into(),
+            gen_all: None,
+        }
+    }
+}
+
+impl OutputLayout {
+    /// Paths, relative to the output directory, of the files `flatc` generates from the schema at
+    /// `schema_path`. That's a file per type declared in it, and in the schemas it includes with
+    /// [`Self::gen_all`], except for RPC services, which `flatc` generates no Rust code for.
+    pub fn generated_files_for<P: AsRef<Path>>(&self, schema_path: P) -> Result<Vec<PathBuf>> {
+        let schema_path = schema_path.as_ref();
+        if self.one_file {
+            let stem = schema_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy();
+            return Ok(vec![PathBuf::from(self.file_name(&stem))]);
+        }
+
+        let schemas = match &self.gen_all {
+            Some(include_paths) => {
+                depfile::schema_dependencies(&[schema_path.to_path_buf()], include_paths)?
+            }
+            None => vec![schema_path.to_path_buf()],
+        };
+        let mut files = Vec::new();
+        for schema in schemas {
+            for declaration in Schema::from_file(&schema)?.declarations {
+                if declaration.kind == DeclarationKind::RpcService {
+                    continue;
+                }
+                let mut path: PathBuf = declaration
+                    .namespace
+                    .iter()
+                    .map(|part| to_snake_case(part))
+                    .collect();
+                path.push(self.file_name(&to_snake_case(&declaration.name)));
+                if !files.contains(&path) {
+                    files.push(path);
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Whether `path` has the extension of generated code files.
+    pub fn is_generated_code<P: AsRef<Path>>(&self, path: P) -> bool {
+        path.as_ref()
+            .extension()
+            .is_some_and(|ext| *ext == *self.extension)
+    }
+
+    fn file_name(&self, stem: &str) -> String {
+        format!("{stem}{}.{}", self.suffix, self.extension)
+    }
+}
+
+fn declaration_kind(keyword: &str) -> Option<DeclarationKind> {
+    match keyword {
+        "table" => Some(DeclarationKind::Table),
+        "struct" => Some(DeclarationKind::Struct),
+        "enum" => Some(DeclarationKind::Enum),
+        "union" => Some(DeclarationKind::Union),
+        "rpc_service" => Some(DeclarationKind::RpcService),
+        _ => None,
+    }
+}
+
+/// Skips forward from `idx` past the next balanced `{ ... }` block, returning the index of the
+/// first token after it.
+pub(crate) fn skip_block(tokens: &[Token], mut idx: usize) -> usize {
+    let mut depth = 0usize;
+    while let Some(token) = tokens.get(idx) {
+        idx += 1;
+        match token.text.as_str() {
+            "{" => depth += 1,
+            "}" => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    break;
+                }
+            }
+            ";" if depth == 0 => break,
+            _ => {}
+        }
+    }
+    idx
+}
+
+fn is_string(token: &str) -> bool {
+    token.starts_with('"')
+}
+
+fn unquote(token: &str) -> String {
+    token.trim_matches('"').into()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub text: String,
+    pub line: usize,
+    /// 1-based column, in characters, where the token starts.
+    pub column: usize,
+}
+
+/// Splits schema contents into identifiers, string literals and single punctuation characters,
+/// dropping whitespace and comments.
+pub(crate) fn tokenize(contents: &str) -> Vec<Token> {
+    tokenize_with(contents, false)
+}
+
+/// The schema in `contents` with everything that can't affect the generated code, like comments
+/// and formatting, taken out. `///` documentation comments stay, since `flatc` copies them into
+/// the generated code.
+pub(crate) fn canonical_form(contents: &str) -> String {
+    tokenize_with(contents, true)
+        .into_iter()
+        .map(|token| token.text)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Same as [`tokenize`], but keeping every `///` documentation comment as a single token when
+/// `doc_comments` is set.
+fn tokenize_with(contents: &str, doc_comments: bool) -> Vec<Token> {
+    let chars: Vec<char> = contents.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    // Index of the first character of the current line
+    let mut line_start = 0;
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        let column = idx - line_start + 1;
+        if c == '\n' {
+            line += 1;
+            idx += 1;
+            line_start = idx;
+        } else if c.is_whitespace() {
+            idx += 1;
+        } else if c == '/' && chars.get(idx + 1) == Some(&'/') {
+            let start = idx;
+            while idx < chars.len() && chars[idx] != '\n' {
+                idx += 1;
+            }
+            if doc_comments && chars.get(start + 2) == Some(&'/') {
+                tokens.push(Token {
+                    text: chars[start..idx]
+                        .iter()
+                        .collect::<String>()
+                        .trim_end()
+                        .into(),
+                    line,
+                    column,
+                });
+            }
+        } else if c == '/' && chars.get(idx + 1) == Some(&'*') {
+            idx += 2;
+            while idx < chars.len() && !(chars[idx] == '*' && chars.get(idx + 1) == Some(&'/')) {
+                if chars[idx] == '\n' {
+                    line += 1;
+                    line_start = idx + 1;
+                }
+                idx += 1;
+            }
+            idx += 2;
+        } else if c == '"' {
+            let start = idx;
+            idx += 1;
+            while idx < chars.len() && chars[idx] != '"' {
+                if chars[idx] == '\\' {
+                    idx += 1;
+                }
+                idx += 1;
+            }
+            idx += 1;
+            tokens.push(Token {
+                text: chars[start..idx.min(chars.len())].iter().collect(),
+                line,
+                column,
+            });
+        } else if c.is_alphanumeric() || c == '_' || c == '-' || c == '+' {
+            let start = idx;
+            while idx < chars.len()
+                && (chars[idx].is_alphanumeric() || matches!(chars[idx], '_' | '-' | '+'))
+            {
+                idx += 1;
+            }
+            tokens.push(Token {
+                text: chars[start..idx].iter().collect(),
+                line,
+                column,
+            });
+        } else {
+            tokens.push(Token {
+                text: c.into(),
+                line,
+                column,
+            });
+            idx += 1;
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_files_for_skips_rpc_services() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("monster.fbs");
+        std::fs::write(
+            &schema,
+            "namespace Game;\ntable Monster {}\nrpc_service MonsterStorage {\n  Store(Monster):Monster;\n}\n",
+        )
+        .unwrap();
+
+        let files = OutputLayout::default()
+            .generated_files_for(&schema)
+            .unwrap();
+
+        assert_eq!(files, [PathBuf::from("game/monster_generated.rs")]);
+    }
+
+    #[test]
+    fn generated_files_for_covers_included_schemas_with_gen_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let include_dir = dir.path().join("common");
+        std::fs::create_dir(&include_dir).unwrap();
+        std::fs::write(include_dir.join("weapon.fbs"), "table Weapon {}\n").unwrap();
+        let schema = dir.path().join("monster.fbs");
+        std::fs::write(&schema, "include \"weapon.fbs\";\ntable Monster {}\n").unwrap();
+
+        let layout = OutputLayout::default();
+        assert_eq!(
+            layout.generated_files_for(&schema).unwrap(),
+            [PathBuf::from("monster_generated.rs")]
+        );
+        let layout = OutputLayout {
+            gen_all: Some(vec![include_dir]),
+            ..layout
+        };
+        assert_eq!(
+            layout.generated_files_for(&schema).unwrap(),
+            [
+                PathBuf::from("monster_generated.rs"),
+                PathBuf::from("weapon_generated.rs")
+            ]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Stamp files recording what the code in an output directory was generated from. When the
+//! stamp in an output directory matches what we're about to compile, the code in it is already
+//! what `flatc` would generate, so running it again can be skipped. When only some of the
+//! inputs changed, it tells which ones need their code regenerated.
+
+use crate::{
+    depfile, directives::Directives, expected_flatc_version, flatc::Backend, manifest, output,
+    parallel, proto, run_flatc, schema, schema::OutputLayout, BuilderOptions, Flatc, Result,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// Name of the stamp file, at the root of the output directory.
+pub(crate) const STAMP_FILE_NAME: &str = ".flatbuffers-build-stamp.json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Stamp {
+    /// What the code got generated from, see [`fingerprint`].
+    pub fingerprint: Fingerprint,
+    /// The files each input is expected to generate, relative to the output directory, as found
+    /// in its schema. Inputs are keyed as in [`Fingerprint::inputs`].
+    pub outputs: BTreeMap<PathBuf, Vec<PathBuf>>,
+    /// SHA-256 of each generated file, keyed by its path relative to the output directory.
+    pub files: BTreeMap<PathBuf, String>,
+}
+
+impl Stamp {
+    /// Records the `files` generated into `output_dir` with `layout` from what `fingerprint`
+    /// describes.
+    pub fn new(
+        fingerprint: Fingerprint,
+        layout: &OutputLayout,
+        output_dir: &Path,
+        files: &[PathBuf],
+    ) -> Result<Stamp> {
+        let outputs = fingerprint
+            .inputs
+            .keys()
+            .filter(|input| !proto::is_proto(input))
+            .map(|input| Ok((input.clone(), layout.generated_files_for(input)?)))
+            .collect::<Result<_>>()?;
+        let files = parallel::map(files, parallel::default_jobs(), |file| {
+            let path = output_dir.join(file);
+            manifest::hash_file(&path)
+                .map(|sha256| (file.clone(), sha256))
+                .map_err(|source| crate::Error::OutputWriteFailure { path, source })
+        })
+        .into_iter()
+        .collect::<Result<_>>()?;
+        Ok(Stamp {
+            fingerprint,
+            outputs,
+            files,
+        })
+    }
+
+    /// Reads the stamp in `output_dir`, if it has one and every file it lists is still there,
+    /// untouched.
+    pub fn read(output_dir: &Path) -> Option<Stamp> {
+        let stamp = Stamp::read_unchecked(output_dir)?;
+        let files: Vec<_> = stamp.files.iter().collect();
+        parallel::map(&files, parallel::default_jobs(), |(file, sha256)| {
+            manifest::hash_file(output_dir.join(file)).is_ok_and(|actual| actual == **sha256)
+        })
+        .into_iter()
+        .all(|untouched| untouched)
+        .then_some(stamp)
+    }
+
+    /// Reads the stamp in `output_dir`, if it has one, whatever happened to the files it lists
+    /// since.
+    pub fn read_unchecked(output_dir: &Path) -> Option<Stamp> {
+        let contents = std::fs::read(output_dir.join(STAMP_FILE_NAME)).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    /// Reads the stamp in `output_dir`, if it has one matching `fingerprint` and every file it
+    /// lists is still there, untouched.
+    pub fn read_current(output_dir: &Path, fingerprint: &Fingerprint) -> Option<Stamp> {
+        Stamp::read(output_dir).filter(|stamp| stamp.fingerprint == *fingerprint)
+    }
+
+    /// Writes the stamp into `output_dir`.
+    pub fn write(&self, output_dir: &Path) -> Result {
+        output::write_json(&output_dir.join(STAMP_FILE_NAME), self)
+    }
+}
+
+/// Everything that affects the generated code, hashed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Fingerprint {
+    /// Hash of the `flatc` generating the code.
+    pub compiler: String,
+    /// Hash of the options, along with the version of this crate.
+    pub options: String,
+    /// Hash of each input along with every schema it includes, directly or not, keyed by
+    /// [`input_key`]. Telling the inputs apart lets us regenerate only the code of the ones that
+    /// changed.
+    pub inputs: BTreeMap<PathBuf, String>,
+}
+
+impl Fingerprint {
+    /// A single hash of everything the fingerprint covers.
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.compiler);
+        hasher.update(&self.options);
+        for (input, sha256) in &self.inputs {
+            hasher.update(input.as_os_str().as_encoded_bytes());
+            hasher.update(sha256);
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Hashes everything that affects the code generated for `builder_options` with `compiler`: the
+/// version of this crate and of `flatc`, every option that affects the code, and the path and
+/// contents of every schema, including the ones pulled in through `include`. The only thing `flatc`
+/// gets run for is its version, so that an up to date output costs a single quick process spawn.
+/// Running a container just for that would cost more than it saves though, so there's no
+/// fingerprint when running `flatc` in a container, nor with post-processing hooks.
+pub(crate) fn fingerprint(
+    builder_options: &BuilderOptions,
+    compiler: &Flatc,
+) -> Result<Option<Fingerprint>> {
+    // Hooks are arbitrary code, whose changes there's no telling apart
+    if !builder_options.post_processing_hooks.is_empty() {
+        return Ok(None);
+    }
+    let Some(compiler_identity) = compiler_identity(compiler) else {
+        return Ok(None);
+    };
+    let mut compiler_hasher = Sha256::new();
+    compiler_hasher.update(expected_flatc_version(compiler));
+    compiler_hasher.update(compiler_identity);
+
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    // Where the code ends up and how the compilation reports on itself don't affect the code
+    // itself, and leaving them out lets the output cache share it between target directories.
+    // The compiler is already accounted for.
+    let options = BuilderOptions {
+        compiler: None,
+        output_path: None,
+        symlink_path: None,
+        shared_output: false,
+        reuse_output_without_flatc: false,
+        output_cache_dir: None,
+        #[cfg(feature = "remote-cache")]
+        remote_cache: None,
+        directives: Directives::none(),
+        verbose: false,
+        timeout: None,
+        report_path: None,
+        depfile_path: None,
+        print_timings: false,
+        print_counters: false,
+        rerun_paths: Vec::new(),
+        ..builder_options.clone()
+    };
+    // Paths from `Cargo.toml` and included schemas get resolved to absolute paths. Hashing them
+    // relative to the current directory, the crate's directory in build scripts, keeps the
+    // fingerprint the same across checkouts in different places, so that they can share generated
+    // code.
+    let options = BuilderOptions {
+        files: options.files.iter().map(|file| input_key(file)).collect(),
+        include_paths: options
+            .include_paths
+            .iter()
+            .map(|path| input_key(path))
+            .collect(),
+        ..options
+    };
+    // Hashing the debug representation means options added later on are accounted for too.
+    // Options that don't affect the generated code only cause the odd unneeded recompilation.
+    hasher.update(format!("{options:?}"));
+
+    // Inputs often include the same schemas, which only need reading once
+    let mut schema_hashes = HashMap::new();
+    let mut inputs = BTreeMap::new();
+    for input in &builder_options.files {
+        let mut input_hasher = Sha256::new();
+        for schema in depfile::schema_dependencies(
+            std::slice::from_ref(input),
+            &builder_options.include_paths,
+        )? {
+            if !schema_hashes.contains_key(&schema) {
+                let sha256 = hash_schema(&schema, builder_options.ignore_schema_formatting)
+                    .map_err(|source| crate::Error::SchemaReadFailure {
+                        path: schema.clone(),
+                        source,
+                    })?;
+                schema_hashes.insert(schema.clone(), sha256);
+            }
+            input_hasher.update(input_key(&schema).as_os_str().as_encoded_bytes());
+            input_hasher.update(&schema_hashes[&schema]);
+        }
+        inputs.insert(input_key(input), format!("{:x}", input_hasher.finalize()));
+    }
+    Ok(Some(Fingerprint {
+        compiler: format!("{:x}", compiler_hasher.finalize()),
+        options: format!("{:x}", hasher.finalize()),
+        inputs,
+    }))
+}
+
+/// `path` relative to the current directory when it's in there, as it's recorded in stamps so that
+/// they're the same wherever the crate is checked out. Relative paths are left as they are, and
+/// so still point at the same file.
+pub(crate) fn input_key(path: &Path) -> PathBuf {
+    if path.is_relative() {
+        return path.to_path_buf();
+    }
+    let Ok(current_dir) = std::env::current_dir() else {
+        return path.to_path_buf();
+    };
+    let canonical_dir = std::fs::canonicalize(&current_dir).ok();
+    [Some(current_dir), canonical_dir]
+        .into_iter()
+        .flatten()
+        .find_map(|dir| path.strip_prefix(dir).ok())
+        .map_or_else(|| path.to_path_buf(), Path::to_path_buf)
+}
+
+/// Hashes the schema at `path`, leaving out changes to comments and formatting when `canonical`
+/// is set. `.proto` files always get hashed as they are, since their comments make it into the
+/// schemas translated from them.
+fn hash_schema(path: &Path, canonical: bool) -> std::io::Result<String> {
+    if !canonical || proto::is_proto(path) {
+        return manifest::hash_file(path);
+    }
+    let contents = std::fs::read_to_string(path)?;
+    Ok(format!(
+        "{:x}",
+        Sha256::digest(schema::canonical_form(&contents))
+    ))
+}
+
+/// Identifies `compiler` by what it reports with `--version`, which is the same for a given release
+/// wherever it's installed, so that stamps and cache entries carry over between machines. A
+/// `flatc` that can't be run is told apart from any that can, so that its absence only ever makes
+/// the code look out of date.
+fn compiler_identity(compiler: &Flatc) -> Option<String> {
+    if matches!(compiler.backend, Backend::Container(_)) {
+        return None;
+    }
+    Some(
+        run_flatc(compiler, ["--version"])
+            .map(|output| output.stdout.trim_end().to_owned())
+            .unwrap_or_default(),
+    )
+}
@@ -0,0 +1,132 @@
+//! Running a `flatc` compiled to WASI through an embedded wasmtime runtime. Since the same module
+//! runs the same way everywhere, this makes code generation independent of the host platform.
+
+use crate::{Error, Result};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Duration,
+};
+use wasmtime::{Config, Engine, Linker, Module, Store, Trap};
+use wasmtime_wasi::{
+    pipe::MemoryOutputPipe,
+    preview1::{self, WasiP1Ctx},
+    DirPerms, FilePerms, I32Exit, WasiCtxBuilder,
+};
+
+/// Output of a WASI run, mirroring what we get out of [`std::process::Output`].
+pub(crate) struct WasiOutput {
+    pub status_code: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Runs the `flatc` WASI module at `module_path` with `args`. WASI modules can only see the
+/// directories we explicitly give them access to, so we give it the whole filesystem, and turn
+/// any argument that's a relative path to an existing file into an absolute one. The module gets
+/// interrupted if it runs for longer than `timeout`.
+pub(crate) fn run(
+    module_path: &Path,
+    args: Vec<OsString>,
+    timeout: Option<Duration>,
+) -> Result<WasiOutput> {
+    let mut config = Config::new();
+    config.epoch_interruption(timeout.is_some());
+    let engine = Engine::new(&config).map_err(spawn_failure)?;
+    let module = Module::from_file(&engine, module_path).map_err(spawn_failure)?;
+    let mut linker: Linker<WasiP1Ctx> = Linker::new(&engine);
+    preview1::add_to_linker_sync(&mut linker, |ctx| ctx).map_err(spawn_failure)?;
+
+    let stdout = MemoryOutputPipe::new(usize::MAX);
+    let stderr = MemoryOutputPipe::new(usize::MAX);
+    let mut builder = WasiCtxBuilder::new();
+    builder
+        .arg("flatc")
+        .stdout(stdout.clone())
+        .stderr(stderr.clone());
+    for arg in args {
+        builder.arg(absolute_if_exists(arg).to_string_lossy());
+    }
+    for root in filesystem_roots() {
+        builder
+            .preopened_dir(
+                &root,
+                root.to_string_lossy(),
+                DirPerms::all(),
+                FilePerms::all(),
+            )
+            .map_err(spawn_failure)?;
+    }
+
+    let mut store = Store::new(&engine, builder.build_p1());
+    // Bumping the epoch from another thread once the timeout elapses traps the running module
+    let _cancel_timeout = timeout.map(|timeout| {
+        store.set_epoch_deadline(1);
+        let engine = engine.clone();
+        let (cancel, cancelled) = mpsc::channel::<()>();
+        std::thread::spawn(move || {
+            if cancelled.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout) {
+                engine.increment_epoch();
+            }
+        });
+        cancel
+    });
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .map_err(spawn_failure)?;
+    let start = instance
+        .get_typed_func::<(), ()>(&mut store, "_start")
+        .map_err(spawn_failure)?;
+    // Exiting with a non-zero code shows up as an error, but so do traps, which we report the
+    // same way a native process getting killed by a signal would be.
+    let (status_code, trap) = match start.call(&mut store, ()) {
+        Ok(()) => (Some(0), None),
+        Err(e) => match (
+            e.downcast_ref::<I32Exit>(),
+            e.downcast_ref::<Trap>(),
+            timeout,
+        ) {
+            (Some(exit), _, _) => (Some(exit.0), None),
+            (None, Some(Trap::Interrupt), Some(timeout)) => return Err(Error::Timeout(timeout)),
+            (None, _, _) => (None, Some(e)),
+        },
+    };
+    drop(store);
+
+    let mut stderr = stderr.contents().to_vec();
+    if let Some(trap) = trap {
+        stderr.extend(format!("{trap:?}").into_bytes());
+    }
+    Ok(WasiOutput {
+        status_code,
+        stdout: stdout.contents().to_vec(),
+        stderr,
+    })
+}
+
+fn absolute_if_exists(arg: OsString) -> OsString {
+    let path = Path::new(&arg);
+    if path.is_relative() && path.exists() {
+        std::path::absolute(path).map_or(arg, PathBuf::into_os_string)
+    } else {
+        arg
+    }
+}
+
+#[cfg(unix)]
+fn filesystem_roots() -> Vec<PathBuf> {
+    vec![PathBuf::from("/")]
+}
+
+#[cfg(windows)]
+fn filesystem_roots() -> Vec<PathBuf> {
+    ('A'..='Z')
+        .map(|drive| PathBuf::from(format!("{drive}:\\")))
+        .filter(|root| root.exists())
+        .collect()
+}
+
+fn spawn_failure(e: wasmtime::Error) -> Error {
+    Error::FlatcSpawnFailure(std::io::Error::other(e))
+}
@@ -0,0 +1,139 @@
+//! Compilation of the schemas of every package in a cargo workspace in one go, for workspaces
+//! where many crates would otherwise each need a near identical build script.
+
+use crate::{metadata, Error, Result};
+use serde::Deserialize;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Directory inside the target directory that packages without an `output-path` get their code
+/// generated into, in a subdirectory named after the package.
+const WORKSPACE_OUTPUT_DIR: &str = "flatbuffers";
+
+/// The parts of the output of `cargo metadata` we care about.
+#[derive(Deserialize)]
+struct CargoMetadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    workspace_root: PathBuf,
+    target_directory: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    manifest_path: PathBuf,
+}
+
+/// Compiles the schemas of every package in the workspace of the package being built that has a
+/// `[package.metadata.flatbuffers]` table, as configured by it (see
+/// [`crate::BuilderOptions::from_cargo_metadata`]). Every package can include the schemas of
+/// every other package: the `include-dirs` of all packages, along with the directories of all
+/// their schemas, are passed as include paths to all of them. Packages without an `output-path` get their code
+/// generated into `flatbuffers/<package name>` in the workspace's target directory, so they're
+/// best paired with a `symlink-directory`. Packages get compiled in parallel, the same way as
+/// with [`crate::compile_all`].
+///
+/// ```no_run
+/// flatbuffers_build::compile_workspace().expect("flatbuffer compilation failed");
+/// ```
+///
+/// # Errors
+/// Fails if not running from a build script, if `cargo metadata` fails, if the configuration of
+/// any package is invalid, or with the first error compiling any of the packages.
+pub fn compile_workspace() -> Result {
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").ok_or(Error::ManifestDirNotSet)?;
+    compile_workspace_with_manifest(Path::new(&manifest_dir).join(metadata::MANIFEST_FILE_NAME))
+}
+
+/// Same as [`compile_workspace`], but for the workspace of the `Cargo.toml` at `manifest_path`
+/// rather than the one of the package being built. Useful outside of build scripts, e.g. in an
+/// `xtask` regenerating the code of the whole workspace.
+///
+/// # Arguments
+/// * `manifest_path` - Path of the `Cargo.toml` of the workspace, or of any package in it.
+///
+/// # Errors
+/// Fails if `cargo metadata` fails, if the configuration of any package is invalid, or with the
+/// first error compiling any of the packages.
+pub fn compile_workspace_with_manifest<P: AsRef<Path>>(manifest_path: P) -> Result {
+    let cargo_metadata = read_cargo_metadata(manifest_path.as_ref())?;
+
+    let mut members = Vec::new();
+    for id in &cargo_metadata.workspace_members {
+        let Some(package) = cargo_metadata.packages.iter().find(|p| &p.id == id) else {
+            continue;
+        };
+        if let Some(config) = metadata::read_config(&package.manifest_path)? {
+            let builder = metadata::builder_from_config(&package.manifest_path, &config)?;
+            members.push((package, config, builder));
+        }
+    }
+
+    let mut shared_include_paths: Vec<PathBuf> = Vec::new();
+    for (_, _, builder) in &members {
+        let schema_dirs = builder.files.iter().filter_map(|file| file.parent());
+        for path in builder
+            .include_paths
+            .iter()
+            .map(PathBuf::as_path)
+            .chain(schema_dirs)
+        {
+            if !shared_include_paths.iter().any(|p| p == path) {
+                shared_include_paths.push(path.to_path_buf());
+            }
+        }
+    }
+
+    let mut builders = Vec::with_capacity(members.len());
+    for (package, config, mut builder) in members {
+        for path in &shared_include_paths {
+            if !builder.include_paths.contains(path) {
+                builder = builder.add_include_path(path);
+            }
+        }
+        if config.output_path.is_none() {
+            builder = builder.set_output_path(
+                cargo_metadata
+                    .target_directory
+                    .join(WORKSPACE_OUTPUT_DIR)
+                    .join(&package.name),
+            );
+        }
+        // Packages joining or leaving the workspace change what every package can include
+        builder.rerun_paths.push(
+            cargo_metadata
+                .workspace_root
+                .join(metadata::MANIFEST_FILE_NAME),
+        );
+        builders.push(builder);
+    }
+    crate::compile_all(builders)
+}
+
+/// Runs `cargo metadata` on the workspace of the manifest at `manifest_path`.
+fn read_cargo_metadata(manifest_path: &Path) -> Result<CargoMetadata> {
+    // Build scripts get the cargo running them through `CARGO`
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+    let output = Command::new(cargo)
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--no-deps",
+            "--manifest-path",
+        ])
+        .arg(manifest_path)
+        .output()
+        .map_err(|e| Error::CargoMetadataFailure(e.to_string()))?;
+    if !output.status.success() {
+        return Err(Error::CargoMetadataFailure(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| Error::CargoMetadataFailure(e.to_string()))
+}